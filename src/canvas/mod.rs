@@ -0,0 +1,292 @@
+//! The surfaces charts can be drawn onto.
+
+use std::io::Write;
+
+use ab_glyph::{FontRef, PxScale};
+use image::{codecs::png::PngEncoder, imageops, ImageEncoder, Rgb, RgbImage};
+use imageproc::rect::Rect;
+use itertools::Itertools;
+
+use crate::{
+	common_types::{MultiPointGradient, Point},
+	drawing::{dashes, stroke_coverage, BresenhamLineIter, Stroke},
+	error::Error,
+};
+
+pub mod svg;
+
+/// A backend for the drawing primitives. Coordinates are in pixels from the top left.
+pub trait Canvas: Sized {
+	/// File extension for the encoded output.
+	const EXTENSION: &'static str;
+	fn new(width: u32, height: u32, background: Rgb<u8>) -> Self;
+	fn width(&self) -> u32;
+	fn height(&self) -> u32;
+	fn put_pixel(&mut self, x: u32, y: u32, colour: Rgb<u8>);
+	/// A 1 pixel line including both ends.
+	fn draw_line(&mut self, start: Point<u32>, end: Point<u32>, colour: Rgb<u8>);
+	/// Connects the points with lines. [`Stroke::THIN`] should look the same as [`Canvas::draw_line`] between each pair of points rounded down.
+	fn draw_polyline(&mut self, points: &[Point<f32>], colour: Rgb<u8>, stroke: Stroke);
+	fn fill_rect(&mut self, rectangle: Rect, colour: Rgb<u8>);
+	/// Fills the shape with its corners at `points`, anti-aliased. Like [`Canvas::draw_polyline`], the points are at the middle of pixels. Below an `opacity` of 1 it is blended with what is already there.
+	fn fill_polygon(&mut self, points: &[Point<f32>], colour: Rgb<u8>, opacity: f32);
+	/// Gradient based on height
+	fn fill_rect_with_gradient(&mut self, rectangle: Rect, gradient: &MultiPointGradient);
+	/// Text with the top of the line at `y`.
+	fn draw_text(
+		&mut self,
+		text: &str,
+		colour: Rgb<u8>,
+		x: i32,
+		y: i32,
+		font: &FontRef,
+		font_scale: PxScale,
+	);
+	/// Draws another canvas on top of this one, with its top left corner at `x`, `y`.
+	fn overlay(&mut self, other: Self, x: u32, y: u32);
+	fn encode(&self, writer: impl Write) -> Result<(), Error>;
+}
+
+impl Canvas for RgbImage {
+	const EXTENSION: &'static str = "png";
+
+	fn new(width: u32, height: u32, background: Rgb<u8>) -> Self {
+		RgbImage::from_pixel(width, height, background)
+	}
+	fn width(&self) -> u32 {
+		self.width()
+	}
+	fn height(&self) -> u32 {
+		self.height()
+	}
+	fn put_pixel(&mut self, x: u32, y: u32, colour: Rgb<u8>) {
+		self.put_pixel(x, y, colour);
+	}
+	fn draw_line(&mut self, start: Point<u32>, end: Point<u32>, colour: Rgb<u8>) {
+		for point in BresenhamLineIter::new(start, end) {
+			self.put_pixel(point.x, point.y, colour);
+		}
+	}
+	fn draw_polyline(&mut self, points: &[Point<f32>], colour: Rgb<u8>, stroke: Stroke) {
+		let pieces = match stroke.style.pattern() {
+			Some((on, off)) => dashes(points, on * stroke.width, off * stroke.width),
+			None if stroke.width <= 1.0 && !stroke.anti_aliased => {
+				for (start, end) in points.iter().tuple_windows() {
+					let [start, end] = [start, end].map(|point| Point {
+						x: point.x as u32,
+						y: point.y as u32,
+					});
+					self.draw_line(start, end, colour);
+				}
+				return;
+			}
+			None => vec![points.to_vec()],
+		};
+		let segments: Vec<(Point<f32>, Point<f32>)> = pieces
+			.iter()
+			.flat_map(|piece| piece.iter().copied().tuple_windows())
+			.collect();
+		let reach = stroke.width / 2.0 + 1.0;
+		// Only the pixels the stroke can reach are looked at, so small strokes stay cheap on big canvases.
+		let (Some((min_x, max_x)), Some((min_y, max_y))) = (
+			segments
+				.iter()
+				.flat_map(|(start, end)| [start.x, end.x])
+				.minmax()
+				.into_option(),
+			segments
+				.iter()
+				.flat_map(|(start, end)| [start.y, end.y])
+				.minmax()
+				.into_option(),
+		) else {
+			return;
+		};
+		let left = (min_x - reach).max(0.0) as u32;
+		let top = (min_y - reach).max(0.0) as u32;
+		let right = ((max_x + reach).max(0.0) as u32).min(self.width() - 1);
+		let bottom = ((max_y + reach).max(0.0) as u32).min(self.height() - 1);
+		if left > right || top > bottom {
+			return;
+		}
+		let box_width = right - left + 1;
+		// Taking the most coverage of any segment, rather than blending each, stops the joins being drawn twice.
+		let mut coverage = vec![0.0_f32; (box_width * (bottom - top + 1)) as usize];
+		for (start, end) in segments {
+			let segment_left = (start.x.min(end.x) - reach).max(left as f32) as u32;
+			let segment_right = ((start.x.max(end.x) + reach) as u32).min(right);
+			let segment_top = (start.y.min(end.y) - reach).max(top as f32) as u32;
+			let segment_bottom = ((start.y.max(end.y) + reach) as u32).min(bottom);
+			for y in segment_top..=segment_bottom {
+				for x in segment_left..=segment_right {
+					let pixel = Point {
+						x: x as f32,
+						y: y as f32,
+					};
+					let cell = &mut coverage[((y - top) * box_width + x - left) as usize];
+					*cell = cell.max(stroke_coverage(pixel, start, end, stroke));
+				}
+			}
+		}
+		for (index, amount) in coverage.into_iter().enumerate() {
+			if amount > 0.0 {
+				let (x, y) = (
+					left + index as u32 % box_width,
+					top + index as u32 / box_width,
+				);
+				let blended = blend(*self.get_pixel(x, y), colour, amount);
+				self.put_pixel(x, y, blended);
+			}
+		}
+	}
+	fn fill_rect(&mut self, rectangle: Rect, colour: Rgb<u8>) {
+		imageproc::drawing::draw_filled_rect_mut(self, rectangle, colour);
+	}
+	fn fill_polygon(&mut self, points: &[Point<f32>], colour: Rgb<u8>, opacity: f32) {
+		if points.len() < 3 {
+			return;
+		}
+		let (width, height) = (self.width(), self.height());
+		let (left, right) = points
+			.iter()
+			.map(|point| point.x)
+			.minmax()
+			.into_option()
+			.unwrap();
+		let (top, bottom) = points
+			.iter()
+			.map(|point| point.y)
+			.minmax()
+			.into_option()
+			.unwrap();
+		let samples = POLYGON_SAMPLES as f32;
+		for y in (top.floor().max(0.0) as u32)..=(bottom.ceil() as u32).min(height - 1) {
+			for x in (left.floor().max(0.0) as u32)..=(right.ceil() as u32).min(width - 1) {
+				// How many of a grid of points spread over the pixel are inside
+				let inside = (0..POLYGON_SAMPLES)
+					.cartesian_product(0..POLYGON_SAMPLES)
+					.filter(|&(column, row)| {
+						let sample = Point {
+							x: x as f32 - 0.5 + (column as f32 + 0.5) / samples,
+							y: y as f32 - 0.5 + (row as f32 + 0.5) / samples,
+						};
+						polygon_contains(points, sample)
+					})
+					.count();
+				if inside > 0 {
+					let amount = opacity * inside as f32 / (samples * samples);
+					let blended = blend(*self.get_pixel(x, y), colour, amount);
+					self.put_pixel(x, y, blended);
+				}
+			}
+		}
+	}
+	fn fill_rect_with_gradient(&mut self, rectangle: Rect, gradient: &MultiPointGradient) {
+		for y in rectangle.top()..=rectangle.bottom() {
+			let gradient_point = self.height() - y as u32;
+			let colour = Rgb(gradient.get_colour(gradient_point));
+			for x in rectangle.left()..=rectangle.right() {
+				self.put_pixel(x as u32, y as u32, colour)
+			}
+		}
+	}
+	fn draw_text(
+		&mut self,
+		text: &str,
+		colour: Rgb<u8>,
+		x: i32,
+		y: i32,
+		font: &FontRef,
+		font_scale: PxScale,
+	) {
+		imageproc::drawing::draw_text_mut(self, colour, x, y, font_scale, font, text);
+	}
+	fn overlay(&mut self, other: Self, x: u32, y: u32) {
+		imageops::replace(self, &other, x as i64, y as i64);
+	}
+	fn encode(&self, writer: impl Write) -> Result<(), Error> {
+		let encoder = PngEncoder::new(writer);
+		encoder.write_image(
+			self,
+			self.width(),
+			self.height(),
+			image::ExtendedColorType::Rgb8,
+		)?;
+		Ok(())
+	}
+}
+
+/// How many points across and down each pixel are checked when filling polygons.
+const POLYGON_SAMPLES: u32 = 4;
+
+/// Whether `point` is inside the polygon, by counting how many of its edges a line to the right of it crosses.
+fn polygon_contains(corners: &[Point<f32>], point: Point<f32>) -> bool {
+	corners
+		.iter()
+		.circular_tuple_windows()
+		.filter(|(start, end)| {
+			(start.y > point.y) != (end.y > point.y)
+				&& point.x < start.x + (point.y - start.y) * (end.x - start.x) / (end.y - start.y)
+		})
+		.count()
+		% 2 == 1
+}
+
+/// Mixes `over` into `under` by `amount`, from 0 to 1.
+pub(crate) fn blend(Rgb(under): Rgb<u8>, Rgb(over): Rgb<u8>, amount: f32) -> Rgb<u8> {
+	Rgb(std::array::from_fn(|channel| {
+		(under[channel] as f32 + (over[channel] as f32 - under[channel] as f32) * amount).round()
+			as u8
+	}))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::drawing::LineStyle;
+
+	#[test]
+	fn polylines() {
+		let colour = Rgb([255, 255, 255]);
+		let points = [
+			Point { x: 1.0, y: 1.0 },
+			Point { x: 8.0, y: 5.0 },
+			Point { x: 15.0, y: 2.0 },
+		];
+		let mut thin = RgbImage::new(16, 8);
+		thin.draw_polyline(&points, colour, Stroke::THIN);
+		let mut lines = RgbImage::new(16, 8);
+		lines.draw_line(Point { x: 1, y: 1 }, Point { x: 8, y: 5 }, colour);
+		lines.draw_line(Point { x: 8, y: 5 }, Point { x: 15, y: 2 }, colour);
+		assert_eq!(thin, lines);
+
+		let mut smooth = RgbImage::new(16, 8);
+		let stroke = Stroke {
+			width: 2.0,
+			anti_aliased: true,
+			style: LineStyle::Solid,
+		};
+		smooth.draw_polyline(&points, colour, stroke);
+		// Fully covered on the line, and partially covered at the edge.
+		assert_eq!(*smooth.get_pixel(8, 5), colour);
+		assert_eq!(*smooth.get_pixel(8, 7), Rgb([0, 0, 0]));
+		let edge = smooth.get_pixel(8, 6).0[0];
+		assert!(0 < edge && edge < 255);
+
+		let mut dotted = RgbImage::new(16, 8);
+		dotted.draw_polyline(
+			&[Point { x: 0.0, y: 4.0 }, Point { x: 15.0, y: 4.0 }],
+			colour,
+			Stroke::THIN.with_style(LineStyle::Dotted),
+		);
+		// A dot every 2.5 pixels, so every other one falls between two pixels and covers both.
+		let row = (0..16)
+			.map(|x| *dotted.get_pixel(x, 4) == colour)
+			.collect::<Vec<_>>();
+		let expected = [0, 2, 3, 5, 7, 8, 10, 12, 13, 15];
+		assert!(row
+			.iter()
+			.enumerate()
+			.all(|(x, &on)| on == expected.contains(&x)));
+	}
+}
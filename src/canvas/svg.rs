@@ -0,0 +1,341 @@
+use std::{
+	collections::HashMap,
+	fmt::Write as _,
+	io::Write,
+	sync::atomic::{AtomicUsize, Ordering},
+};
+
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont};
+use image::Rgb;
+use imageproc::rect::Rect;
+use itertools::Itertools;
+
+use crate::{
+	canvas::Canvas,
+	common_types::{MultiPointGradient, Point},
+	drawing::Stroke,
+	error::Error,
+};
+
+/// How many colours are sampled between each pair of gradient points, since SVG interpolates in sRGB rather than Oklab.
+const GRADIENT_SAMPLES: u32 = 8;
+
+/// Gives every canvas its own prefix for ids, so they stay unique when canvases are composited.
+static CANVAS_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Builds an SVG document out of the drawing primitives.
+#[derive(Debug, Clone)]
+pub struct SvgCanvas {
+	width: u32,
+	height: u32,
+	id_prefix: String,
+	/// Gradient definitions, keyed by their stops so each is only defined once.
+	gradients: HashMap<String, String>,
+	defs: String,
+	body: String,
+	/// The pixels last drawn by [`Canvas::put_pixel`], which a pixel of the same colour just to the right joins instead of adding another rectangle.
+	pixel_run: Option<PixelRun>,
+	/// The family the text is rendered in by the viewer, which should match the font used for measuring.
+	pub font_family: String,
+}
+
+/// A row of pixels of one colour, written as a single rectangle at `start..end` in the body.
+#[derive(Debug, Clone, Copy)]
+struct PixelRun {
+	start: usize,
+	end: usize,
+	x: u32,
+	y: u32,
+	width: u32,
+	colour: Rgb<u8>,
+}
+
+impl SvgCanvas {
+	pub fn to_svg(&self) -> String {
+		let mut svg = format!(
+			r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+			self.width, self.height
+		);
+		self.write_contents(&mut svg);
+		svg.push_str("</svg>\n");
+		svg
+	}
+	fn write_contents(&self, svg: &mut String) {
+		if !self.defs.is_empty() {
+			let _ = write!(svg, "<defs>{}</defs>", self.defs);
+		}
+		svg.push_str(&self.body);
+	}
+	fn gradient_id(&mut self, gradient: &MultiPointGradient) -> String {
+		let (first, last) = gradient.range();
+		let mut stops = String::new();
+		let steps = (gradient.point_count() as u32 - 1) * GRADIENT_SAMPLES;
+		for step in 0..=steps {
+			let offset = step as f32 / steps as f32;
+			let point = first + ((last - first) as f32 * offset).round() as u32;
+			let _ = write!(
+				stops,
+				r#"<stop offset="{offset}" stop-color="{}"/>"#,
+				hex(Rgb(gradient.get_colour(point)))
+			);
+		}
+		let key = format!("{first} {last} {stops}");
+		if let Some(id) = self.gradients.get(&key) {
+			return id.clone();
+		}
+		let id = format!("{}g{}", self.id_prefix, self.gradients.len());
+		// Gradient points count up from the bottom of the canvas.
+		let _ = write!(
+			self.defs,
+			r#"<linearGradient id="{id}" gradientUnits="userSpaceOnUse" x1="0" y1="{}" x2="0" y2="{}">{stops}</linearGradient>"#,
+			self.height as i64 - first as i64,
+			self.height as i64 - last as i64,
+		);
+		self.gradients.insert(key, id.clone());
+		id
+	}
+}
+
+fn hex(Rgb([r, g, b]): Rgb<u8>) -> String {
+	format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+fn escape(text: &str) -> String {
+	text.replace('&', "&amp;")
+		.replace('<', "&lt;")
+		.replace('>', "&gt;")
+		.replace('"', "&quot;")
+}
+
+impl Canvas for SvgCanvas {
+	const EXTENSION: &'static str = "svg";
+
+	fn new(width: u32, height: u32, background: Rgb<u8>) -> Self {
+		let id_prefix = format!("c{}", CANVAS_COUNT.fetch_add(1, Ordering::Relaxed));
+		let body = format!(
+			r#"<rect width="{width}" height="{height}" fill="{}"/>"#,
+			hex(background)
+		);
+		Self {
+			width,
+			height,
+			id_prefix,
+			gradients: HashMap::new(),
+			defs: String::new(),
+			body,
+			pixel_run: None,
+			font_family: String::from("Roboto Condensed"),
+		}
+	}
+	fn width(&self) -> u32 {
+		self.width
+	}
+	fn height(&self) -> u32 {
+		self.height
+	}
+	fn put_pixel(&mut self, x: u32, y: u32, colour: Rgb<u8>) {
+		let run = match self.pixel_run {
+			// Only if nothing has been drawn since, which the longer rectangle would cover.
+			Some(run)
+				if run.end == self.body.len()
+					&& (run.x + run.width, run.y, run.colour) == (x, y, colour) =>
+			{
+				self.body.truncate(run.start);
+				PixelRun {
+					width: run.width + 1,
+					..run
+				}
+			}
+			_ => PixelRun {
+				start: self.body.len(),
+				end: self.body.len(),
+				x,
+				y,
+				width: 1,
+				colour,
+			},
+		};
+		self.fill_rect(
+			Rect::at(run.x as i32, run.y as i32).of_size(run.width, 1),
+			colour,
+		);
+		self.pixel_run = Some(PixelRun {
+			end: self.body.len(),
+			..run
+		});
+	}
+	fn draw_line(&mut self, start: Point<u32>, end: Point<u32>, colour: Rgb<u8>) {
+		// Through the middle of the pixels, with square caps so the end pixels are covered.
+		let _ = write!(
+			self.body,
+			r#"<line x1="{}.5" y1="{}.5" x2="{}.5" y2="{}.5" stroke="{}" stroke-linecap="square"/>"#,
+			start.x,
+			start.y,
+			end.x,
+			end.y,
+			hex(colour)
+		);
+	}
+	fn draw_polyline(&mut self, points: &[Point<f32>], colour: Rgb<u8>, stroke: Stroke) {
+		let pattern = stroke.style.pattern();
+		if pattern.is_none() && stroke.width <= 1.0 && !stroke.anti_aliased {
+			for (start, end) in points.iter().tuple_windows() {
+				let [start, end] = [start, end].map(|point| Point {
+					x: point.x as u32,
+					y: point.y as u32,
+				});
+				self.draw_line(start, end, colour);
+			}
+			return;
+		}
+		let points = points
+			.iter()
+			.map(|point| format!("{},{}", point.x + 0.5, point.y + 0.5))
+			.join(" ");
+		let rendering = if stroke.anti_aliased {
+			""
+		} else {
+			r#" shape-rendering="crispEdges""#
+		};
+		let dashes = match pattern {
+			Some((on, off)) => format!(
+				r#" stroke-dasharray="{} {}""#,
+				on * stroke.width,
+				off * stroke.width
+			),
+			None => String::new(),
+		};
+		let _ = write!(
+			self.body,
+			r#"<polyline points="{points}" fill="none" stroke="{}" stroke-width="{}" stroke-linejoin="round" stroke-linecap="round"{dashes}{rendering}/>"#,
+			hex(colour),
+			stroke.width
+		);
+	}
+	fn fill_rect(&mut self, rectangle: Rect, colour: Rgb<u8>) {
+		let _ = write!(
+			self.body,
+			r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" shape-rendering="crispEdges"/>"#,
+			rectangle.left(),
+			rectangle.top(),
+			rectangle.width(),
+			rectangle.height(),
+			hex(colour)
+		);
+	}
+	fn fill_polygon(&mut self, points: &[Point<f32>], colour: Rgb<u8>, opacity: f32) {
+		let points = points
+			.iter()
+			.map(|point| format!("{},{}", point.x + 0.5, point.y + 0.5))
+			.join(" ");
+		let opacity = if opacity < 1.0 {
+			format!(r#" fill-opacity="{opacity}""#)
+		} else {
+			String::new()
+		};
+		let _ = write!(
+			self.body,
+			r#"<polygon points="{points}" fill="{}"{opacity}/>"#,
+			hex(colour)
+		);
+	}
+	fn fill_rect_with_gradient(&mut self, rectangle: Rect, gradient: &MultiPointGradient) {
+		let id = self.gradient_id(gradient);
+		let _ = write!(
+			self.body,
+			r#"<rect x="{}" y="{}" width="{}" height="{}" fill="url(#{id})" shape-rendering="crispEdges"/>"#,
+			rectangle.left(),
+			rectangle.top(),
+			rectangle.width(),
+			rectangle.height(),
+		);
+	}
+	fn draw_text(
+		&mut self,
+		text: &str,
+		colour: Rgb<u8>,
+		x: i32,
+		y: i32,
+		font: &FontRef,
+		font_scale: PxScale,
+	) {
+		// `PxScale` is the height from descent to ascent, while SVG font sizes are the em size.
+		let font_size = font_scale.y * font.units_per_em().unwrap_or(font.height_unscaled())
+			/ font.height_unscaled();
+		let baseline = y as f32 + font.as_scaled(font_scale).ascent();
+		let _ = write!(
+			self.body,
+			r#"<text x="{x}" y="{baseline}" font-family="{}" font-size="{font_size}" fill="{}">{}</text>"#,
+			escape(&self.font_family),
+			hex(colour),
+			escape(text)
+		);
+	}
+	fn overlay(&mut self, other: Self, x: u32, y: u32) {
+		let _ = write!(
+			self.body,
+			r#"<svg x="{x}" y="{y}" width="{}" height="{}">"#,
+			other.width, other.height
+		);
+		other.write_contents(&mut self.body);
+		self.body.push_str("</svg>");
+	}
+	fn encode(&self, mut writer: impl Write) -> Result<(), Error> {
+		writer.write_all(self.to_svg().as_bytes())?;
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::common_types::GradientPoint;
+
+	use super::*;
+
+	#[test]
+	fn gradients_and_overlay() {
+		let gradient = MultiPointGradient::new(vec![
+			GradientPoint::from_rgb(0, [0, 0, 0]),
+			GradientPoint::from_rgb(10, [255, 255, 255]),
+		])
+		.unwrap();
+		let mut inner = SvgCanvas::new(10, 10, Rgb([0, 0, 0]));
+		inner.fill_rect_with_gradient(Rect::at(0, 0).of_size(2, 10), &gradient);
+		inner.fill_rect_with_gradient(Rect::at(4, 0).of_size(2, 10), &gradient);
+		assert_eq!(inner.gradients.len(), 1);
+		let mut outer = SvgCanvas::new(10, 20, Rgb([0, 0, 0]));
+		outer.fill_rect_with_gradient(Rect::at(0, 0).of_size(2, 10), &gradient);
+		outer.overlay(inner, 0, 10);
+		let svg = outer.to_svg();
+		assert_eq!(svg.matches("<linearGradient").count(), 2);
+		assert!(svg.contains(r#"<svg x="0" y="10" width="10" height="10">"#));
+	}
+
+	#[test]
+	fn pixel_runs() {
+		let mut canvas = SvgCanvas::new(10, 10, Rgb([0, 0, 0]));
+		let [red, blue] = [Rgb([255, 0, 0]), Rgb([0, 0, 255])];
+		for x in 0..4 {
+			canvas.put_pixel(x, 0, red);
+		}
+		canvas.put_pixel(4, 0, blue);
+		canvas.put_pixel(5, 1, blue);
+		canvas.fill_rect(Rect::at(0, 2).of_size(1, 1), red);
+		canvas.put_pixel(6, 1, blue);
+		let svg = canvas.to_svg();
+		assert_eq!(svg.matches("<rect").count(), 6);
+		assert!(svg.contains(r##"<rect x="0" y="0" width="4" height="1" fill="#ff0000""##));
+	}
+
+	#[test]
+	fn escaped_text() {
+		let font_data: &[u8] = include_bytes!("../../RobotoCondensed-Regular.ttf");
+		let font = FontRef::try_from_slice(font_data).unwrap();
+		let mut canvas = SvgCanvas::new(10, 10, Rgb([0, 0, 0]));
+		canvas.font_family = String::from(r#"A "B" & C"#);
+		canvas.draw_text(r#"<"x">"#, Rgb([0, 0, 0]), 0, 0, &font, PxScale::from(10.0));
+		let svg = canvas.to_svg();
+		assert!(svg.contains(r#"font-family="A &quot;B&quot; &amp; C""#));
+		assert!(svg.contains(">&lt;&quot;x&quot;&gt;</text>"));
+	}
+}
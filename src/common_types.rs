@@ -1,10 +1,12 @@
 use itertools::Itertools;
 use oklab::{oklab_to_srgb, srgb_to_oklab, Oklab, RGB};
 
+use crate::error::Error;
+
 #[derive(Debug, Clone, Copy)]
-pub(crate) struct Point<T: std::ops::Add + std::ops::Sub> {
-	pub(crate) x: T,
-	pub(crate) y: T,
+pub struct Point<T: std::ops::Add + std::ops::Sub> {
+	pub x: T,
+	pub y: T,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -14,11 +16,11 @@ pub struct Range<T: std::ops::Sub<Output = T> + Ord + Copy> {
 }
 
 impl<T: std::ops::Sub<Output = T> + Ord + Copy> Range<T> {
-	pub fn new(start: T, end: T) -> Self {
+	pub fn new(start: T, end: T) -> Result<Self, Error> {
 		if end < start {
-			panic!("End of range is before start of range")
+			Err(Error::ReversedRange)
 		} else {
-			Self { start, end }
+			Ok(Self { start, end })
 		}
 	}
 	pub fn len(&self) -> T {
@@ -56,16 +58,43 @@ pub struct MultiPointGradient {
 }
 
 impl MultiPointGradient {
-	pub fn new(points: Vec<GradientPoint>) -> Self {
-		if points
+	/// Needs at least two points, in increasing order.
+	pub fn new(points: Vec<GradientPoint>) -> Result<Self, Error> {
+		if points.len() < 2 {
+			return Err(Error::GradientPoints {
+				index: points.len(),
+			});
+		}
+		if let Some(index) = points
 			.iter()
 			.tuple_windows()
-			.any(|(a, b)| a.point() >= b.point())
+			.position(|(a, b)| a.point() >= b.point())
 		{
-			panic!("Gradient points not in increasing order");
+			return Err(Error::GradientPoints { index: index + 1 });
 		}
+		Ok(Self { points })
+	}
+	/// The first and last points.
+	pub(crate) fn range(&self) -> (u32, u32) {
+		let first = self.points.first().map_or(0, GradientPoint::point);
+		let last = self.points.last().map_or(0, GradientPoint::point);
+		(first, last)
+	}
+	/// The same gradient with the points multiplied by `factor`, for charts drawn at a larger scale.
+	pub(crate) fn scaled(&self, factor: u32) -> Self {
+		let points = self
+			.points
+			.iter()
+			.map(|point| GradientPoint {
+				point: point.point * factor,
+				colour: point.colour,
+			})
+			.collect();
 		Self { points }
 	}
+	pub(crate) fn point_count(&self) -> usize {
+		self.points.len()
+	}
 	pub(crate) fn get_colour(&self, point: u32) -> [u8; 3] {
 		let (start, end) = self
 			.points
@@ -106,7 +135,8 @@ mod tests {
 		let gradient = MultiPointGradient::new(vec![
 			GradientPoint::from_rgb(0, [0, 0, 0]),
 			GradientPoint::from_rgb(100, [255, 255, 255]),
-		]);
+		])
+		.unwrap();
 		for n in (0..=100).step_by(10) {
 			println!("{:?}", gradient.get_colour(n));
 		}
@@ -119,7 +149,8 @@ mod tests {
 			GradientPoint::from_rgb(100, [255, 255, 255]),
 			GradientPoint::from_rgb(200, [0, 0, 0]),
 			GradientPoint::from_rgb(300, [255, 255, 255]),
-		]);
+		])
+		.unwrap();
 		for n in (0..=300).step_by(10) {
 			println!("{:?}", gradient.get_colour(n))
 		}
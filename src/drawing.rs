@@ -1,27 +1,19 @@
 use ab_glyph::{FontRef, PxScale};
-use image::{Rgb, RgbImage};
-use imageproc::{drawing::draw_filled_rect_mut, rect::Rect};
+use image::Rgb;
+use imageproc::rect::Rect;
 use itertools::Itertools;
 
 use crate::{
-	colours,
+	canvas::Canvas,
 	common_types::{MultiPointGradient, Point, Range},
+	error::Error,
+	theme::Theme,
+	time::ZonedTime,
 };
 
-pub(crate) fn draw_line_segment(
-	canvas: &mut RgbImage,
-	start: Point<u32>,
-	end: Point<u32>,
-	colour: Rgb<u8>,
-) {
-	for point in BresenhamLineIter::new(start, end) {
-		canvas.put_pixel(point.x, point.y, colour);
-	}
-}
-
 /// Gradient based on height
-pub(crate) fn _draw_line_segment_with_gradient(
-	canvas: &mut RgbImage,
+pub(crate) fn _draw_line_segment_with_gradient<C: Canvas>(
+	canvas: &mut C,
 	start: Point<u32>,
 	end: Point<u32>,
 	gradient: &MultiPointGradient,
@@ -33,25 +25,10 @@ pub(crate) fn _draw_line_segment_with_gradient(
 	}
 }
 
-/// Gradient based on height
-pub(crate) fn draw_bar_with_gradient(
-	canvas: &mut RgbImage,
-	rectangle: Rect,
-	gradient: &MultiPointGradient,
-) {
-	for y in rectangle.top()..=rectangle.bottom() {
-		let gradient_point = canvas.height() - y as u32;
-		let colour = Rgb(gradient.get_colour(gradient_point));
-		for x in rectangle.left()..=rectangle.right() {
-			canvas.put_pixel(x as u32, y as u32, colour)
-		}
-	}
-}
-
 /// Iterates over the coordinates in a line segment using
 /// [Bresenham's line drawing algorithm](https://en.wikipedia.org/wiki/Bresenham%27s_line_algorithm).
 /// Stolen/adapted from imageproc crate
-struct BresenhamLineIter {
+pub(crate) struct BresenhamLineIter {
 	dx: u32,
 	dy: u32,
 	x: u32,
@@ -65,7 +42,7 @@ struct BresenhamLineIter {
 impl BresenhamLineIter {
 	/// Creates a [`BresenhamLineIter`](struct.BresenhamLineIter.html) which will iterate over the integer coordinates
 	/// between `start` and `end`.
-	fn new(start: Point<u32>, end: Point<u32>) -> BresenhamLineIter {
+	pub(crate) fn new(start: Point<u32>, end: Point<u32>) -> BresenhamLineIter {
 		let Point {
 			x: mut x0,
 			y: mut y0,
@@ -136,6 +113,130 @@ impl Iterator for BresenhamLineIter {
 	}
 }
 
+/// How the line of a line chart is drawn.
+#[derive(Debug, Clone, Copy)]
+pub struct Stroke {
+	/// Width in pixels
+	pub width: f32,
+	/// Blends the edges of the line into what is underneath by how much of each pixel it covers, instead of drawing whole pixels.
+	pub anti_aliased: bool,
+	pub style: LineStyle,
+}
+
+impl Stroke {
+	/// A 1 pixel line without anti-aliasing.
+	pub const THIN: Self = Self {
+		width: 1.0,
+		anti_aliased: false,
+		style: LineStyle::Solid,
+	};
+	pub const fn with_style(self, style: LineStyle) -> Self {
+		Self { style, ..self }
+	}
+}
+
+impl Default for Stroke {
+	fn default() -> Self {
+		Self::THIN
+	}
+}
+
+/// A pattern along a line, so series can be told apart without relying on colour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineStyle {
+	#[default]
+	Solid,
+	Dashed,
+	Dotted,
+}
+
+impl LineStyle {
+	/// Lengths of the drawn and skipped parts of the pattern, in multiples of the stroke width.
+	///
+	/// The drawn parts get round ends half the width long on top of this, so dots are drawn parts of length 0.
+	pub(crate) const fn pattern(&self) -> Option<(f32, f32)> {
+		match self {
+			Self::Solid => None,
+			Self::Dashed => Some((3.0, 3.0)),
+			Self::Dotted => Some((0.0, 2.5)),
+		}
+	}
+}
+
+/// Splits a line into the pieces that are drawn for a pattern of `on` pixels drawn and `off` pixels skipped.
+pub(crate) fn dashes(points: &[Point<f32>], on: f32, off: f32) -> Vec<Vec<Point<f32>>> {
+	if points.len() < 2 || on + off <= 0.0 {
+		return vec![points.to_vec()];
+	}
+	// Distance along the line to each point.
+	let mut distances = vec![0.0];
+	for (start, end) in points.iter().tuple_windows() {
+		let travelled = distances[distances.len() - 1];
+		distances.push(travelled + (end.x - start.x).hypot(end.y - start.y));
+	}
+	let total = distances[distances.len() - 1];
+	let point_at = |distance: f32| {
+		let index = distances
+			.partition_point(|&point| point <= distance)
+			.clamp(1, points.len() - 1);
+		let (start, end) = (points[index - 1], points[index]);
+		let length = distances[index] - distances[index - 1];
+		let along = if length == 0.0 {
+			0.0
+		} else {
+			((distance - distances[index - 1]) / length).clamp(0.0, 1.0)
+		};
+		Point {
+			x: start.x + (end.x - start.x) * along,
+			y: start.y + (end.y - start.y) * along,
+		}
+	};
+	(0..)
+		.map(|n| n as f32 * (on + off))
+		.take_while(|&start| start <= total)
+		.map(|start| {
+			let end = (start + on).min(total);
+			let mut piece = vec![point_at(start)];
+			piece.extend(
+				points
+					.iter()
+					.zip(&distances)
+					.filter(|(_, &distance)| start < distance && distance < end)
+					.map(|(point, _)| *point),
+			);
+			piece.push(point_at(end));
+			piece
+		})
+		.collect()
+}
+
+/// How much of the pixel centred on `pixel` is covered by a stroke along the segment from `start` to `end`, from 0 to 1.
+///
+/// The ends are rounded, so consecutive segments join smoothly.
+pub(crate) fn stroke_coverage(
+	pixel: Point<f32>,
+	start: Point<f32>,
+	end: Point<f32>,
+	stroke: Stroke,
+) -> f32 {
+	let (dx, dy) = (end.x - start.x, end.y - start.y);
+	let length_squared = dx * dx + dy * dy;
+	// How far along the segment the closest point to the pixel is.
+	let along = if length_squared == 0.0 {
+		0.0
+	} else {
+		(((pixel.x - start.x) * dx + (pixel.y - start.y) * dy) / length_squared).clamp(0.0, 1.0)
+	};
+	let distance = (pixel.x - start.x - along * dx).hypot(pixel.y - start.y - along * dy);
+	if stroke.anti_aliased {
+		(stroke.width / 2.0 + 0.5 - distance).clamp(0.0, 1.0)
+	} else if distance <= stroke.width / 2.0 {
+		1.0
+	} else {
+		0.0
+	}
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Padding {
 	pub above: u32,
@@ -145,6 +246,14 @@ pub struct Padding {
 }
 
 impl Padding {
+	pub(crate) const fn scaled(&self, scale: u32) -> Self {
+		Self {
+			above: self.above * scale,
+			below: self.below * scale,
+			left: self.left * scale,
+			right: self.right * scale,
+		}
+	}
 	/** This is just left + right. */
 	pub(crate) const fn horizontal(&self) -> u32 {
 		self.left + self.right
@@ -161,6 +270,15 @@ pub struct Spacing {
 	pub vertical: u32,
 }
 
+impl Spacing {
+	pub(crate) const fn scaled(&self, scale: u32) -> Self {
+		Self {
+			horizontal: self.horizontal * scale,
+			vertical: self.vertical * scale,
+		}
+	}
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct MarkIntervals {
 	line: usize,
@@ -168,12 +286,12 @@ pub struct MarkIntervals {
 }
 
 impl MarkIntervals {
-	/** Panics if label is not a multiple of line */
-	pub const fn new(line: usize, label: usize) -> Self {
-		if label % line != 0 {
-			panic!("Labelling interval needs to be a multiple of line drawing interval.");
+	/** Fails if label is not a multiple of line */
+	pub const fn new(line: usize, label: usize) -> Result<Self, Error> {
+		if line == 0 || label == 0 || !label.is_multiple_of(line) {
+			return Err(Error::MarkIntervals { line, label });
 		}
-		Self { line, label }
+		Ok(Self { line, label })
 	}
 	pub(crate) const fn line(&self) -> usize {
 		self.line
@@ -183,10 +301,36 @@ impl MarkIntervals {
 	}
 }
 
-pub(crate) fn draw_outer_lines(canvas: &mut RgbImage, padding: Padding) {
+/// A horizontal or vertical line `scale` pixels thick, covering from the top left of `start` to `scale` pixels past `end`.
+pub(crate) fn draw_scaled_line<C: Canvas>(
+	canvas: &mut C,
+	start: Point<u32>,
+	end: Point<u32>,
+	colour: Rgb<u8>,
+	scale: u32,
+) {
+	if scale == 1 {
+		canvas.draw_line(start, end, colour);
+		return;
+	}
+	canvas.fill_rect(
+		Rect::at(start.x.min(end.x) as i32, start.y.min(end.y) as i32).of_size(
+			start.x.abs_diff(end.x) + scale,
+			start.y.abs_diff(end.y) + scale,
+		),
+		colour,
+	);
+}
+
+pub(crate) fn draw_outer_lines<C: Canvas>(
+	canvas: &mut C,
+	padding: Padding,
+	scale: u32,
+	theme: &Theme,
+) {
 	let height = canvas.height();
-	let x = padding.left - 1;
-	draw_line_segment(
+	let x = padding.left - scale;
+	draw_scaled_line(
 		canvas,
 		Point {
 			x,
@@ -196,58 +340,51 @@ pub(crate) fn draw_outer_lines(canvas: &mut RgbImage, padding: Padding) {
 			x,
 			y: height - padding.below,
 		},
-		colours::MAIN_LINES,
+		theme.main_lines,
+		scale,
 	);
-	let y = height - padding.below + 1;
+	let y = height - padding.below + scale;
 	let width = canvas.width();
-	draw_line_segment(
+	draw_scaled_line(
 		canvas,
 		Point {
-			x: padding.left - 1,
+			x: padding.left - scale,
 			y,
 		},
 		Point {
 			x: width - padding.right,
 			y,
 		},
-		colours::MAIN_LINES,
-	);
-}
-
-pub(crate) fn fill_canvas(canvas: &mut RgbImage, colour: Rgb<u8>) {
-	let width = canvas.width();
-	let height = canvas.height();
-	imageproc::drawing::draw_filled_rect_mut(
-		canvas,
-		imageproc::rect::Rect::at(0, 0).of_size(width, height),
-		colour,
+		theme.main_lines,
+		scale,
 	);
 }
 
-pub(crate) fn horizontal_lines_and_labels(
-	canvas: &mut RgbImage,
+#[allow(clippy::too_many_arguments)]
+/// The grid lines across the chart, with labels on the left. The range and intervals are in the hundredths of the data, and the spacing is in pixels per whole unit.
+pub(crate) fn horizontal_lines_and_labels<C: Canvas>(
+	canvas: &mut C,
 	data_range: Range<i32>,
 	intervals: MarkIntervals,
 	font: &FontRef,
 	font_scale: PxScale,
 	padding: Padding,
 	spacing: u32,
+	scale: u32,
+	theme: &Theme,
 ) {
 	let width = canvas.width();
-	let max_value = data_range.end() / 100;
-	for value in (data_range.start()..=data_range.end())
-		.step_by(intervals.line() * 100)
-		.map(|n| n / 100)
-	{
-		let y = padding.above + max_value.abs_diff(value) * spacing;
+	let max_value = data_range.end();
+	for value in (data_range.start()..=data_range.end()).step_by(intervals.line()) {
+		let y = padding.above + max_value.abs_diff(value) * spacing / 100;
 		let line_colour = if value == 0 {
-			colours::MAIN_LINES
-		} else if value / 100 % intervals.label() as i32 == 0 {
-			colours::BRIGHTER_GRID_LINES
+			theme.main_lines
+		} else if value % intervals.label() as i32 == 0 {
+			theme.brighter_grid_lines
 		} else {
-			colours::GRID_LINES
+			theme.grid_lines
 		};
-		draw_line_segment(
+		draw_scaled_line(
 			canvas,
 			Point { x: padding.left, y },
 			Point {
@@ -255,33 +392,40 @@ pub(crate) fn horizontal_lines_and_labels(
 				y,
 			},
 			line_colour,
+			scale,
 		);
 		if value % intervals.label() as i32 == 0 {
-			let text = &format!("{}", value);
+			let text = &if value % 100 == 0 {
+				format!("{}", value / 100)
+			} else {
+				format!("{}", value as f32 / 100.0)
+			};
 			let (text_width, text_height) = imageproc::drawing::text_size(font_scale, &font, text);
 			let text_width = text_width as i32;
 			let text_height = text_height as i32;
-			imageproc::drawing::draw_text_mut(
-				canvas,
-				colours::TEXT,
-				padding.left as i32 - text_width - 3,
+			canvas.draw_text(
+				text,
+				theme.text,
+				padding.left as i32 - text_width - 3 * scale as i32,
 				y as i32 - text_height / 2,
+				font,
 				font_scale,
-				&font,
-				text,
 			);
 		}
 	}
 }
 
-pub(crate) fn vertical_lines_and_labels(
-	canvas: &mut RgbImage,
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn vertical_lines_and_labels<C: Canvas>(
+	canvas: &mut C,
 	data: impl Iterator<Item = u8>,
 	intervals: MarkIntervals,
 	font: &FontRef,
 	font_scale: PxScale,
 	padding: Padding,
 	spacing: u32,
+	scale: u32,
+	theme: &Theme,
 	center: bool,
 ) {
 	let height = canvas.height();
@@ -293,11 +437,11 @@ pub(crate) fn vertical_lines_and_labels(
 		}
 		let x = padding.left + index as u32 * spacing;
 		let line_colour = if index % intervals.label() == 0 {
-			colours::BRIGHTER_GRID_LINES
+			theme.brighter_grid_lines
 		} else {
-			colours::GRID_LINES
+			theme.grid_lines
 		};
-		draw_line_segment(
+		draw_scaled_line(
 			canvas,
 			Point {
 				x,
@@ -308,6 +452,7 @@ pub(crate) fn vertical_lines_and_labels(
 				y: height - padding.below,
 			},
 			line_colour,
+			scale,
 		);
 		if index % intervals.label() == 0 {
 			let text = &format!("{}", item);
@@ -318,14 +463,13 @@ pub(crate) fn vertical_lines_and_labels(
 			} else {
 				x as i32 - text_width / 2
 			};
-			imageproc::drawing::draw_text_mut(
-				canvas,
-				colours::TEXT,
+			canvas.draw_text(
+				text,
+				theme.text,
 				x,
-				(height - padding.below + 5) as i32,
+				(height - padding.below + 5 * scale) as i32,
+				font,
 				font_scale,
-				&font,
-				text,
 			);
 		}
 	}
@@ -336,11 +480,11 @@ pub(crate) fn vertical_lines_and_labels(
 			return;
 		}
 		let line_colour = if count % intervals.label() == 0 {
-			colours::BRIGHTER_GRID_LINES
+			theme.brighter_grid_lines
 		} else {
-			colours::GRID_LINES
+			theme.grid_lines
 		};
-		draw_line_segment(
+		draw_scaled_line(
 			canvas,
 			Point {
 				x,
@@ -351,35 +495,195 @@ pub(crate) fn vertical_lines_and_labels(
 				y: height - padding.below,
 			},
 			line_colour,
+			scale,
 		);
 	}
 }
 
-/// Draws the line graph lines onto the canvas.
-pub(crate) fn draw_graph_lines(
-	canvas: &mut RgbImage,
-	data: impl IntoIterator<Item = i32>,
+/// Like [`vertical_lines_and_labels`], but for points at real times. Lines and labels go on local hours that are multiples of the intervals rather than on every so many points, so they stay on the same hours across daylight saving time changes.
+///
+/// Where a new day starts there is a separator line labelled with the weekday and date, and hour labels in its way are left out.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn time_lines_and_labels<C: Canvas>(
+	canvas: &mut C,
+	times: &[ZonedTime],
+	intervals: MarkIntervals,
+	font: &FontRef,
+	font_scale: PxScale,
+	padding: Padding,
+	spacing: u32,
+	scale: u32,
+	theme: &Theme,
+	center: bool,
+) {
+	let height = canvas.height();
+	let line_colour = |hour: usize| {
+		if hour.is_multiple_of(intervals.label()) {
+			theme.brighter_grid_lines
+		} else {
+			theme.grid_lines
+		}
+	};
+	let draw_line = |canvas: &mut C, x: u32, colour: Rgb<u8>| {
+		draw_scaled_line(
+			canvas,
+			Point {
+				x,
+				y: padding.above,
+			},
+			Point {
+				x,
+				y: height - padding.below,
+			},
+			colour,
+			scale,
+		);
+	};
+	// Left and right edges, and text
+	let mut day_labels = Vec::new();
+	let mut hour_labels = Vec::new();
+	for (index, time) in times.iter().enumerate() {
+		let x = padding.left + index as u32 * spacing;
+		let hour = time.hour() as usize;
+		if index > 0 && time.day() != times[index - 1].day() {
+			draw_line(canvas, x, theme.main_lines);
+			let text = time.day_label();
+			let text_width = imageproc::drawing::text_size(font_scale, &font, &text).0 as i32;
+			let left = x as i32 - text_width / 2;
+			day_labels.push((left, left + text_width, text));
+		} else if hour.is_multiple_of(intervals.line()) {
+			draw_line(canvas, x, line_colour(hour));
+		}
+		if hour.is_multiple_of(intervals.label()) {
+			let text = hour.to_string();
+			let text_width = imageproc::drawing::text_size(font_scale, &font, &text).0 as i32;
+			let left = if center {
+				x as i32 - (text_width - spacing as i32) / 2
+			} else {
+				x as i32 - text_width / 2
+			};
+			hour_labels.push((left, left + text_width, text));
+		}
+	}
+	if let Some(last) = times.last().filter(|_| center) {
+		let x = padding.left + times.len() as u32 * spacing;
+		let hour = (last.hour() as usize + 1) % 24;
+		if x < canvas.width() && hour.is_multiple_of(intervals.line()) {
+			draw_line(canvas, x, line_colour(hour));
+		}
+	}
+
+	let y = (height - padding.below + 5 * scale) as i32;
+	let gap = 2 * scale as i32;
+	// Wider, so an hour next to a date doesn't read as part of it
+	let day_gap = 6 * scale as i32;
+	let mut previous_right = i32::MIN;
+	for (left, right, text) in hour_labels {
+		let overlaps_day = day_labels.iter().any(|&(day_left, day_right, _)| {
+			left < day_right + day_gap && day_left < right + day_gap
+		});
+		// Two labels can be next to each other when the clocks go back.
+		if overlaps_day || left < previous_right + gap {
+			continue;
+		}
+		canvas.draw_text(&text, theme.text, left, y, font, font_scale);
+		previous_right = right;
+	}
+	for (left, _, text) in day_labels {
+		canvas.draw_text(&text, theme.text, left, y, font, font_scale);
+	}
+}
+
+/// Draws the line graph lines onto the canvas. The line is broken wherever a value is missing, and points with missing values on both sides are drawn as dots.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn draw_graph_lines<C: Canvas>(
+	canvas: &mut C,
+	data: impl IntoIterator<Item = Option<i32>>,
 	colour: Rgb<u8>,
+	stroke: Stroke,
 	max: i32,
 	padding: Padding,
 	spacing: Spacing,
+	scale: u32,
 ) {
-	for (index, (start, end)) in data.into_iter().tuple_windows().enumerate() {
-		let start = Point {
-			x: index as u32 * spacing.horizontal + padding.left,
-			y: start.abs_diff(max) * spacing.vertical / 100 + padding.above,
-		};
-		let end = Point {
-			x: (index + 1) as u32 * spacing.horizontal + padding.left,
-			y: end.abs_diff(max) * spacing.vertical / 100 + padding.above,
-		};
-		draw_line_segment(canvas, start, end, colour);
+	let stroke = Stroke {
+		width: stroke.width * scale as f32,
+		..stroke
+	};
+	let mut run = Vec::new();
+	for (index, value) in data.into_iter().enumerate() {
+		match value {
+			Some(value) => run.push(line_point(index, value, max, padding, spacing, scale)),
+			None => draw_run(canvas, &mut run, colour, stroke),
+		}
+	}
+	draw_run(canvas, &mut run, colour, stroke);
+}
+
+/// Where a line's point for `value` at `index` goes, through the middle of the scaled pixels.
+fn line_point(
+	index: usize,
+	value: i32,
+	max: i32,
+	padding: Padding,
+	spacing: Spacing,
+	scale: u32,
+) -> Point<f32> {
+	let offset = (scale - 1) as f32 / 2.0;
+	Point {
+		x: (index as u32 * spacing.horizontal + padding.left) as f32 + offset,
+		y: (value.abs_diff(max) * spacing.vertical) as f32 / 100.0 + padding.above as f32 + offset,
 	}
 }
 
+/// Draws and clears one unbroken part of a line.
+fn draw_run<C: Canvas>(canvas: &mut C, run: &mut Vec<Point<f32>>, colour: Rgb<u8>, stroke: Stroke) {
+	if let [point] = run[..] {
+		run.push(point);
+	}
+	if !run.is_empty() {
+		canvas.draw_polyline(run, colour, stroke);
+	}
+	run.clear();
+}
+
+/// Fills between two series at `opacity`, blended with whatever is already drawn. The edges join their points with straight lines like [`draw_graph_lines`], and the fill has a gap wherever either series is missing.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn draw_area<C: Canvas>(
+	canvas: &mut C,
+	data: impl IntoIterator<Item = (Option<i32>, Option<i32>)>,
+	colour: Rgb<u8>,
+	opacity: f32,
+	max: i32,
+	padding: Padding,
+	spacing: Spacing,
+	scale: u32,
+) {
+	let (mut upper, mut lower) = (Vec::new(), Vec::new());
+	let mut fill_run = |upper: &mut Vec<Point<f32>>, lower: &mut Vec<Point<f32>>| {
+		// Along one edge and back along the other
+		if upper.len() > 1 {
+			upper.extend(lower.drain(..).rev());
+			canvas.fill_polygon(upper, colour, opacity);
+		}
+		upper.clear();
+		lower.clear();
+	};
+	for (index, values) in data.into_iter().enumerate() {
+		match values {
+			(Some(first), Some(second)) => {
+				upper.push(line_point(index, first, max, padding, spacing, scale));
+				lower.push(line_point(index, second, max, padding, spacing, scale));
+			}
+			_ => fill_run(&mut upper, &mut lower),
+		}
+	}
+	fill_run(&mut upper, &mut lower);
+}
+
 /// Draws the line graph lines onto the canvas with a height-based gradient.
-pub(crate) fn _draw_graph_lines_with_gradient(
-	canvas: &mut RgbImage,
+pub(crate) fn _draw_graph_lines_with_gradient<C: Canvas>(
+	canvas: &mut C,
 	data: impl IntoIterator<Item = i32>,
 	gradient: MultiPointGradient,
 	max: i32,
@@ -399,81 +703,564 @@ pub(crate) fn _draw_graph_lines_with_gradient(
 	}
 }
 
-pub(crate) fn draw_graph_bars(
-	canvas: &mut RgbImage,
-	data: impl IntoIterator<Item = i32>,
+/// Where a bar goes vertically, as its top and its height. Bars grow from `baseline` up or down to `value`, where `max` is the value at the top of the chart. Bars below the baseline start under its grid line, mirroring the ones above it.
+///
+/// Returns `None` for bars less than a pixel tall.
+fn bar_extent(
+	value: i32,
+	baseline: i32,
+	max: i32,
+	padding: Padding,
+	spacing: Spacing,
+	scale: u32,
+) -> Option<(u32, u32)> {
+	let baseline_y =
+		padding.above + max.saturating_sub(baseline).max(0) as u32 * spacing.vertical / 100;
+	let bar_height = value.abs_diff(baseline) * spacing.vertical / 100;
+	if bar_height == 0 {
+		return None;
+	}
+	if value > baseline {
+		Some((baseline_y - bar_height, bar_height))
+	} else {
+		Some((baseline_y + scale, bar_height))
+	}
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn draw_graph_bars<C: Canvas>(
+	canvas: &mut C,
+	data: impl IntoIterator<Item = Option<i32>>,
 	colour: Rgb<u8>,
+	baseline: i32,
+	max: i32,
 	padding: Padding,
 	spacing: Spacing,
+	scale: u32,
 ) {
-	let height = canvas.height();
 	for (index, value) in data.into_iter().enumerate() {
 		let x_offset = padding.left + index as u32 * spacing.horizontal;
-		let bar_height = value as u32 * spacing.vertical / 100;
-		if bar_height == 0 {
+		let Some(value) = value else {
 			continue;
-		}
-		draw_filled_rect_mut(
-			canvas,
-			Rect::at(
-				(x_offset + 1) as i32,
-				(height - padding.below - bar_height) as i32,
-			)
-			.of_size(spacing.horizontal - 1, bar_height),
+		};
+		let Some((top, bar_height)) = bar_extent(value, baseline, max, padding, spacing, scale)
+		else {
+			continue;
+		};
+		canvas.fill_rect(
+			Rect::at((x_offset + scale) as i32, top as i32)
+				.of_size(spacing.horizontal - scale, bar_height),
 			colour,
 		);
 	}
 }
 
-pub(crate) fn draw_graph_bars_with_gradient(
-	canvas: &mut RgbImage,
-	data: impl IntoIterator<Item = i32>,
+/// The gradient is positioned from the bottom of the canvas, so bars on either side of the baseline take the colours at their own height.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn draw_graph_bars_with_gradient<C: Canvas>(
+	canvas: &mut C,
+	data: impl IntoIterator<Item = Option<i32>>,
 	gradient: &MultiPointGradient,
+	baseline: i32,
+	max: i32,
 	padding: Padding,
 	spacing: Spacing,
+	scale: u32,
 ) {
-	let height = canvas.height();
 	for (index, value) in data.into_iter().enumerate() {
 		let x_offset = padding.left + index as u32 * spacing.horizontal;
-		let bar_height = value as u32 * spacing.vertical / 100;
-		if bar_height == 0 {
+		let Some(value) = value else {
 			continue;
-		}
-		draw_bar_with_gradient(
-			canvas,
-			Rect::at(
-				(x_offset + 1) as i32,
-				(height - padding.below - bar_height) as i32,
-			)
-			.of_size(spacing.horizontal - 1, bar_height),
+		};
+		let Some((top, bar_height)) = bar_extent(value, baseline, max, padding, spacing, scale)
+		else {
+			continue;
+		};
+		canvas.fill_rect_with_gradient(
+			Rect::at((x_offset + scale) as i32, top as i32)
+				.of_size(spacing.horizontal - scale, bar_height),
 			gradient,
 		);
 	}
 }
 
-/// Like bars, but just the top, including skipping any 0-height ones.
-pub(crate) fn draw_horizontal_lines(
-	canvas: &mut RgbImage,
-	data: impl IntoIterator<Item = i32>,
+/// Bars for several series on top of each other in each column, in the order of `colours` from the bottom, so the top of each stack is their total. Amounts grow up from 0 and should not be negative.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn draw_stacked_bars<C: Canvas>(
+	canvas: &mut C,
+	data: impl IntoIterator<Item = Vec<Option<i32>>>,
+	colours: &[Rgb<u8>],
+	max: i32,
+	padding: Padding,
+	spacing: Spacing,
+	scale: u32,
+) {
+	for (index, values) in data.into_iter().enumerate() {
+		let x_offset = padding.left + index as u32 * spacing.horizontal;
+		// Each part is drawn from the top of the stack so far, so rounding never leaves gaps between them.
+		let mut total = 0;
+		let mut stack_top = None;
+		for (value, &colour) in values.into_iter().zip(colours) {
+			let Some(value) = value else {
+				continue;
+			};
+			total += value.max(0);
+			let Some((top, bar_height)) = bar_extent(total, 0, max, padding, spacing, scale) else {
+				continue;
+			};
+			let height = stack_top.map_or(bar_height, |stack_top| stack_top - top);
+			if height > 0 {
+				canvas.fill_rect(
+					Rect::at((x_offset + scale) as i32, top as i32)
+						.of_size(spacing.horizontal - scale, height),
+					colour,
+				);
+			}
+			stack_top = Some(top);
+		}
+	}
+}
+
+/// Bars for several series side by side in each column, each taking an equal share of its width, in the order of `colours` from the left.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn draw_grouped_bars<C: Canvas>(
+	canvas: &mut C,
+	data: impl IntoIterator<Item = Vec<Option<i32>>>,
+	colours: &[Rgb<u8>],
+	baseline: i32,
+	max: i32,
+	padding: Padding,
+	spacing: Spacing,
+	scale: u32,
+) {
+	if colours.is_empty() {
+		return;
+	}
+	let bar_width = ((spacing.horizontal - scale) / colours.len() as u32).max(1);
+	for (index, values) in data.into_iter().enumerate() {
+		let x_offset = padding.left + index as u32 * spacing.horizontal + scale;
+		for (series, (value, &colour)) in values.into_iter().zip(colours).enumerate() {
+			let Some(value) = value else {
+				continue;
+			};
+			let Some((top, bar_height)) = bar_extent(value, baseline, max, padding, spacing, scale)
+			else {
+				continue;
+			};
+			canvas.fill_rect(
+				Rect::at((x_offset + series as u32 * bar_width) as i32, top as i32)
+					.of_size(bar_width, bar_height),
+				colour,
+			);
+		}
+	}
+}
+
+/// Like bars, but just the end furthest from the baseline, including skipping any 0-height or missing ones.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn draw_horizontal_lines<C: Canvas>(
+	canvas: &mut C,
+	data: impl IntoIterator<Item = Option<i32>>,
 	colour: Rgb<u8>,
+	baseline: i32,
+	max: i32,
 	padding: Padding,
 	spacing: Spacing,
+	scale: u32,
 ) {
-	let height = canvas.height();
 	for (index, value) in data.into_iter().enumerate() {
 		let x_offset = padding.left + index as u32 * spacing.horizontal;
-		let bar_height = value as u32 * spacing.vertical / 100;
-		if bar_height == 0 {
+		let Some(value) = value else {
+			continue;
+		};
+		let Some((top, bar_height)) = bar_extent(value, baseline, max, padding, spacing, scale)
+		else {
+			continue;
+		};
+		let y = if value > baseline {
+			top
+		} else {
+			top + bar_height - scale
+		};
+		canvas.fill_rect(
+			Rect::at((x_offset + scale) as i32, y as i32)
+				.of_size(spacing.horizontal - scale, scale),
+			colour,
+		);
+	}
+}
+
+/// Diagonal lines over `rectangle`, to mark where there is no data.
+pub(crate) fn draw_hatching<C: Canvas>(
+	canvas: &mut C,
+	rectangle: Rect,
+	colour: Rgb<u8>,
+	scale: u32,
+) {
+	let (left, right) = (rectangle.left(), rectangle.right());
+	let (top, bottom) = (rectangle.top(), rectangle.bottom());
+	let stroke = Stroke {
+		width: scale as f32,
+		..Stroke::THIN
+	};
+	let gap = 4 * scale as i32;
+	// Lined up with the canvas rather than the rectangle, so neighbouring rectangles join up.
+	let first = (left - rectangle.height() as i32).div_euclid(gap) * gap;
+	// Each line goes up and to the right from `x` on the bottom edge, clipped to the sides.
+	for x in (first..=right).step_by(gap as usize) {
+		let (start_x, end_x) = (x.max(left), (x + bottom - top).min(right));
+		if end_x < start_x {
 			continue;
 		}
-		draw_filled_rect_mut(
+		let [start, end] = [start_x, end_x].map(|point_x| Point {
+			x: point_x as f32,
+			y: (bottom - (point_x - x)) as f32,
+		});
+		canvas.draw_polyline(&[start, end], colour, stroke);
+	}
+}
+
+/// A filled triangle `size` scaled pixels wide at the base, pointing up or down, with its base or tip on `bottom`. `size` should be odd, so the tip is one pixel.
+pub(crate) fn draw_triangle<C: Canvas>(
+	canvas: &mut C,
+	centre_x: u32,
+	bottom: u32,
+	size: u32,
+	up: bool,
+	colour: Rgb<u8>,
+	scale: u32,
+) {
+	let rows = size.div_ceil(2);
+	for row in 0..rows {
+		// Counting from the tip
+		let width = if up { row } else { rows - 1 - row } * 2 + 1;
+		let y = bottom - (rows - row) * scale;
+		canvas.fill_rect(
+			Rect::at((centre_x - width / 2 * scale) as i32, y as i32).of_size(width * scale, scale),
+			colour,
+		);
+	}
+}
+
+/// Degrees between the corners that make up arcs and circles.
+const ARC_STEP: f32 = 3.0;
+
+/// The point `radius` pixels from `centre` towards `bearing`, which is in degrees clockwise from up.
+pub(crate) fn polar_point(centre: Point<f32>, radius: f32, bearing: f32) -> Point<f32> {
+	let (sin, cos) = bearing.to_radians().sin_cos();
+	Point {
+		x: centre.x + radius * sin,
+		y: centre.y - radius * cos,
+	}
+}
+
+/// Points along the circle of `radius` around `centre`, from `start` to `end` in degrees clockwise from up, including both ends.
+fn arc(
+	centre: Point<f32>,
+	radius: f32,
+	start: f32,
+	end: f32,
+) -> impl DoubleEndedIterator<Item = Point<f32>> {
+	let steps = ((end - start) / ARC_STEP).ceil().max(1.0) as u32;
+	(0..=steps).map(move |step| {
+		polar_point(
+			centre,
+			radius,
+			start + (end - start) * step as f32 / steps as f32,
+		)
+	})
+}
+
+/// A filled ring segment between `inner` and `outer` pixels from `centre`, from `start` to `end` in degrees clockwise from up. With an `inner` of 0 it is a pie slice.
+pub(crate) fn draw_sector<C: Canvas>(
+	canvas: &mut C,
+	centre: Point<f32>,
+	inner: f32,
+	outer: f32,
+	start: f32,
+	end: f32,
+	colour: Rgb<u8>,
+) {
+	let mut points: Vec<_> = arc(centre, outer, start, end).collect();
+	if inner > 0.0 {
+		points.extend(arc(centre, inner, start, end).rev());
+	} else {
+		points.push(centre);
+	}
+	canvas.fill_polygon(&points, colour, 1.0);
+}
+
+/// Circles around `centre` at each of `radii`, and `spokes` lines evenly around from straight up, out to the largest circle.
+pub(crate) fn draw_radial_grid<C: Canvas>(
+	canvas: &mut C,
+	centre: Point<f32>,
+	radii: &[f32],
+	spokes: u32,
+	colour: Rgb<u8>,
+	scale: u32,
+) {
+	let stroke = Stroke {
+		width: scale as f32,
+		anti_aliased: true,
+		style: LineStyle::Solid,
+	};
+	for &radius in radii {
+		let circle: Vec<_> = arc(centre, radius, 0.0, 360.0).collect();
+		canvas.draw_polyline(&circle, colour, stroke);
+	}
+	let outer = radii.iter().copied().fold(0.0, f32::max);
+	for spoke in 0..spokes {
+		let bearing = spoke as f32 * 360.0 / spokes as f32;
+		canvas.draw_polyline(
+			&[centre, polar_point(centre, outer, bearing)],
+			colour,
+			stroke,
+		);
+	}
+}
+
+/// N, E, S and W centred `distance` pixels out from `centre`.
+pub(crate) fn draw_compass_labels<C: Canvas>(
+	canvas: &mut C,
+	centre: Point<f32>,
+	distance: f32,
+	font: &FontRef,
+	font_scale: PxScale,
+	colour: Rgb<u8>,
+) {
+	for (text, bearing) in [("N", 0.0), ("E", 90.0), ("S", 180.0), ("W", 270.0)] {
+		let (text_width, text_height) = imageproc::drawing::text_size(font_scale, font, text);
+		let point = polar_point(centre, distance, bearing);
+		canvas.draw_text(
+			text,
+			colour,
+			(point.x - text_width as f32 / 2.0).round() as i32,
+			(point.y - text_height as f32 / 2.0).round() as i32,
+			font,
+			font_scale,
+		);
+	}
+}
+
+/// Draws anti-aliased shapes in unscaled pixels from a centre point, for small symbols like icons and arrows.
+pub(crate) struct Pen<'c, C: Canvas> {
+	canvas: &'c mut C,
+	centre: Point<f32>,
+	scale: f32,
+}
+
+impl<'c, C: Canvas> Pen<'c, C> {
+	/// Centred on the pixel at `centre`, which is already scaled.
+	pub(crate) fn new(canvas: &'c mut C, centre: Point<u32>, scale: u32) -> Self {
+		// Through the middle of the scaled pixels, like lines on the chart.
+		let offset = (scale - 1) as f32 / 2.0;
+		Self {
 			canvas,
-			Rect::at(
-				(x_offset + 1) as i32,
-				(height - padding.below - bar_height) as i32,
-			)
-			.of_size(spacing.horizontal - 1, 1),
+			centre: Point {
+				x: centre.x as f32 + offset,
+				y: centre.y as f32 + offset,
+			},
+			scale: scale as f32,
+		}
+	}
+	fn stroke(&self, width: f32) -> Stroke {
+		Stroke {
+			width: width * self.scale,
+			anti_aliased: true,
+			style: LineStyle::Solid,
+		}
+	}
+	fn point(&self, (x, y): (f32, f32)) -> Point<f32> {
+		Point {
+			x: self.centre.x + x * self.scale,
+			y: self.centre.y + y * self.scale,
+		}
+	}
+	pub(crate) fn line(&mut self, points: &[(f32, f32)], width: f32, colour: Rgb<u8>) {
+		let points: Vec<_> = points.iter().map(|&point| self.point(point)).collect();
+		self.canvas
+			.draw_polyline(&points, colour, self.stroke(width));
+	}
+	/// A filled circle, drawn as a line that goes nowhere with round ends.
+	pub(crate) fn dot(&mut self, x: f32, y: f32, diameter: f32, colour: Rgb<u8>) {
+		let point = self.point((x, y));
+		self.canvas
+			.draw_polyline(&[point, point], colour, self.stroke(diameter));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use image::RgbImage;
+
+	use super::*;
+
+	#[test]
+	fn bars_around_baseline() {
+		let colour = Rgb([255, 255, 255]);
+		let padding = Padding {
+			above: 1,
+			below: 1,
+			left: 0,
+			right: 0,
+		};
+		let spacing = Spacing {
+			horizontal: 2,
+			vertical: 1,
+		};
+		// From 4 to -4, so the baseline is at y = 5.
+		let mut canvas = RgbImage::new(6, 10);
+		draw_graph_bars(
+			&mut canvas,
+			[Some(300), Some(-200), Some(0)],
+			colour,
+			0,
+			400,
+			padding,
+			spacing,
+			1,
+		);
+		let column = |x| {
+			(0..10)
+				.filter(|&y| *canvas.get_pixel(x, y) == colour)
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(column(1), [2, 3, 4]);
+		assert_eq!(column(3), [6, 7]);
+		assert!(column(5).is_empty());
+	}
+
+	#[test]
+	fn stacked_and_grouped_bars() {
+		let [red, blue] = [Rgb([255, 0, 0]), Rgb([0, 0, 255])];
+		let padding = Padding {
+			above: 0,
+			below: 0,
+			left: 0,
+			right: 0,
+		};
+		let spacing = Spacing {
+			horizontal: 5,
+			vertical: 1,
+		};
+		let data = || [vec![Some(300), Some(200)], vec![None, Some(100)]];
+		let column = |canvas: &RgbImage, x| {
+			(0..6)
+				.map(|y| match *canvas.get_pixel(x, y) {
+					colour if colour == red => 'r',
+					colour if colour == blue => 'b',
+					_ => ' ',
+				})
+				.collect::<String>()
+		};
+
+		let mut stacked = RgbImage::new(10, 6);
+		draw_stacked_bars(&mut stacked, data(), &[red, blue], 600, padding, spacing, 1);
+		assert_eq!(column(&stacked, 1), " bbrrr");
+		assert_eq!(column(&stacked, 6), "     b");
+
+		let mut grouped = RgbImage::new(10, 6);
+		draw_grouped_bars(
+			&mut grouped,
+			data(),
+			&[red, blue],
+			0,
+			600,
+			padding,
+			spacing,
+			1,
+		);
+		// Each bar is half of the 4 pixels between the grid lines
+		assert_eq!(column(&grouped, 2), "   rrr");
+		assert_eq!(column(&grouped, 3), "    bb");
+		assert_eq!(column(&grouped, 8), "     b");
+		assert_eq!(column(&grouped, 6), "      ");
+	}
+
+	#[test]
+	fn translucent_area() {
+		let colour = Rgb([200, 200, 200]);
+		let background = Rgb([0, 0, 100]);
+		let padding = Padding {
+			above: 0,
+			below: 0,
+			left: 0,
+			right: 0,
+		};
+		let spacing = Spacing {
+			horizontal: 4,
+			vertical: 1,
+		};
+		let mut canvas = RgbImage::from_pixel(17, 9, background);
+		draw_area(
+			&mut canvas,
+			[
+				(Some(800), Some(0)),
+				(Some(800), Some(0)),
+				(None, Some(0)),
+				(Some(800), Some(0)),
+				(Some(800), Some(0)),
+			],
 			colour,
+			0.5,
+			800,
+			padding,
+			spacing,
+			1,
 		);
+		// Half way between the background and the colour inside
+		assert_eq!(*canvas.get_pixel(2, 4), Rgb([100, 100, 150]));
+		assert_eq!(*canvas.get_pixel(14, 4), Rgb([100, 100, 150]));
+		// Nothing where the top is missing
+		assert_eq!(*canvas.get_pixel(8, 4), background);
+	}
+
+	#[test]
+	fn line_gaps() {
+		let colour = Rgb([255, 255, 255]);
+		let padding = Padding {
+			above: 0,
+			below: 0,
+			left: 0,
+			right: 0,
+		};
+		let spacing = Spacing {
+			horizontal: 4,
+			vertical: 1,
+		};
+		let mut canvas = RgbImage::new(17, 1);
+		draw_graph_lines(
+			&mut canvas,
+			[Some(0), None, Some(0), Some(0), None],
+			colour,
+			Stroke::THIN,
+			0,
+			padding,
+			spacing,
+			1,
+		);
+		let row = (0..17)
+			.filter(|&x| *canvas.get_pixel(x, 0) == colour)
+			.collect::<Vec<_>>();
+		// A dot for the first point on its own, then a line between the next two.
+		assert_eq!(row, [0, 8, 9, 10, 11, 12]);
+	}
+
+	#[test]
+	fn sectors() {
+		let colour = Rgb([255, 255, 255]);
+		let centre = Point { x: 10.0, y: 10.0 };
+		let mut canvas = RgbImage::new(21, 21);
+		// The north east quarter of a ring
+		draw_sector(&mut canvas, centre, 4.0, 8.0, 0.0, 90.0, colour);
+		let filled = |x, y| *canvas.get_pixel(x, y) == colour;
+		assert!(filled(15, 5));
+		assert!(filled(14, 6));
+		// Not inside the ring, nor in the other quarters
+		assert!(!filled(11, 9));
+		assert!(!filled(5, 5));
+		assert!(!filled(15, 15));
+		let point = polar_point(centre, 8.0, 90.0);
+		assert!((point.x - 18.0).abs() < 1e-4 && (point.y - 10.0).abs() < 1e-4);
 	}
 }
@@ -0,0 +1,144 @@
+use std::fmt;
+
+/// Everything that can go wrong while reading input or making a chart.
+#[derive(Debug)]
+pub enum Error {
+	/// An argument was needed but there were no more.
+	MissingArgument(&'static str),
+	/// The arguments could not be split evenly into data.
+	ArgumentCount {
+		chunk_size: usize,
+		count: usize,
+	},
+	/// An argument could not be parsed.
+	InvalidArgument {
+		/// Index of the datum the argument belongs to
+		index: usize,
+		/// What the argument was supposed to be
+		name: &'static str,
+		value: String,
+	},
+	/// A command line option had a value that could not be parsed.
+	InvalidOption {
+		/// Which option the value was given for
+		name: &'static str,
+		value: String,
+	},
+	/// A datum had a value outside of what the chart can show.
+	InvalidDatum {
+		index: usize,
+		field: &'static str,
+	},
+	/// There was no data to make a chart from.
+	NoData,
+	/// A mode that does not exist was requested.
+	UnknownMode(String),
+	/// JSON input did not match what was expected.
+	Json(serde_json::Error),
+	/// A section or column that the chart needs was not in the API response.
+	MissingField {
+		section: &'static str,
+		name: &'static str,
+	},
+	/// A time in an API response could not be parsed.
+	InvalidTime(String),
+	/// A timezone was not a valid POSIX `TZ` string.
+	InvalidTimeZone(String),
+	/// The end of a range was before its start.
+	ReversedRange,
+	/// The label interval was not a (non-zero) multiple of the line interval.
+	MarkIntervals {
+		line: usize,
+		label: usize,
+	},
+	/// A second axis was asked for with no labels, or labels that weren't a positive interval apart.
+	SecondaryRange {
+		interval: i32,
+		steps: u32,
+	},
+	/// A gradient had fewer than two points, or the point at this index was not above the one before it.
+	GradientPoints {
+		index: usize,
+	},
+	/// The scale factor was 0.
+	InvalidScale,
+	Io(std::io::Error),
+	Image(image::ImageError),
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::MissingArgument(name) => write!(f, "No {name} argument"),
+			Self::ArgumentCount { chunk_size, count } => write!(
+				f,
+				"{count} arguments could not be grouped into {chunk_size}s"
+			),
+			Self::InvalidArgument { index, name, value } => write!(
+				f,
+				"Could not parse \"{value}\" as a {name} argument (datum {index})"
+			),
+			Self::InvalidOption { name, value } => {
+				write!(f, "Could not parse \"{value}\" as a {name} option")
+			}
+			Self::InvalidDatum { index, field } => {
+				write!(f, "Datum {index} has an out of range {field}")
+			}
+			Self::NoData => write!(f, "There was no data to chart"),
+			Self::UnknownMode(mode) => write!(f, "Unexpected mode {mode}"),
+			Self::Json(error) => write!(f, "Could not parse JSON input: {error}"),
+			Self::MissingField { section, name } => {
+				write!(f, "Response has no {section} `{name}` field")
+			}
+			Self::InvalidTime(time) => write!(f, "Could not parse time \"{time}\""),
+			Self::InvalidTimeZone(tz) => write!(f, "Could not parse timezone \"{tz}\""),
+			Self::ReversedRange => write!(f, "End of range is before start of range"),
+			Self::MarkIntervals { line, label } => write!(
+				f,
+				"Labelling interval ({label}) needs to be a multiple of line drawing interval ({line})"
+			),
+			Self::SecondaryRange { interval, steps } => write!(
+				f,
+				"Second axis needs a positive label interval ({interval}) and number of labels ({steps})"
+			),
+			Self::GradientPoints { index } => {
+				write!(
+				f,
+				"Gradient needs at least two points in increasing order (problem at point {index})"
+			)
+			}
+			Self::InvalidScale => write!(f, "Scale factor needs to be at least 1"),
+			Self::Io(error) => write!(f, "{error}"),
+			Self::Image(error) => write!(f, "{error}"),
+		}
+	}
+}
+
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Self::Json(error) => Some(error),
+			Self::Io(error) => Some(error),
+			Self::Image(error) => Some(error),
+			_ => None,
+		}
+	}
+}
+
+impl From<serde_json::Error> for Error {
+	fn from(error: serde_json::Error) -> Self {
+		Self::Json(error)
+	}
+}
+
+impl From<std::io::Error> for Error {
+	fn from(error: std::io::Error) -> Self {
+		Self::Io(error)
+	}
+}
+
+impl From<image::ImageError> for Error {
+	fn from(error: image::ImageError) -> Self {
+		Self::Image(error)
+	}
+}
@@ -1,19 +1,54 @@
+use std::str::FromStr;
+
 use itertools::Itertools;
 
-pub trait FromArgs<const L: usize> {
-	fn from_args(args: [String; L]) -> Self;
+use crate::error::Error;
+
+pub trait FromArgs<const L: usize>: Sized {
+	fn from_args(args: [String; L]) -> Result<Self, InvalidArgument>;
+}
+
+/// An argument that could not be parsed, before it is known which datum it belongs to.
+#[derive(Debug)]
+pub struct InvalidArgument {
+	name: &'static str,
+	value: String,
+}
+
+/// Parses one argument, naming it in the error if that fails.
+pub fn parse_arg<T: FromStr>(arg: String, name: &'static str) -> Result<T, InvalidArgument> {
+	arg.parse()
+		.map_err(|_| InvalidArgument { name, value: arg })
+}
+
+/// Like [`parse_arg`], but a `-` means the value is missing.
+pub fn parse_optional_arg<T: FromStr>(
+	arg: String,
+	name: &'static str,
+) -> Result<Option<T>, InvalidArgument> {
+	if arg == "-" {
+		return Ok(None);
+	}
+	parse_arg(arg, name).map(Some)
 }
 
 pub fn data_from_args<T: FromArgs<CHUNK_SIZE>, const CHUNK_SIZE: usize>(
 	args: Vec<String>,
-) -> Vec<T> {
-	let mut data = Vec::with_capacity(args.len() / CHUNK_SIZE);
-	for mut item in args.into_iter().chunks(CHUNK_SIZE).into_iter() {
-		let elements = [(); CHUNK_SIZE].map(|_| {
-			item.next()
-				.unwrap_or_else(|| panic!("Arguments could not be grouped into {CHUNK_SIZE}s"))
+) -> Result<Vec<T>, Error> {
+	if !args.len().is_multiple_of(CHUNK_SIZE) {
+		return Err(Error::ArgumentCount {
+			chunk_size: CHUNK_SIZE,
+			count: args.len(),
 		});
-		data.push(T::from_args(elements));
 	}
-	data
+	let mut data = Vec::with_capacity(args.len() / CHUNK_SIZE);
+	for (index, mut item) in args.into_iter().chunks(CHUNK_SIZE).into_iter().enumerate() {
+		// The length was checked above, so every chunk is full.
+		let elements = [(); CHUNK_SIZE].map(|_| item.next().unwrap_or_default());
+		let datum = T::from_args(elements).map_err(|InvalidArgument { name, value }| {
+			Error::InvalidArgument { index, name, value }
+		})?;
+		data.push(datum);
+	}
+	Ok(data)
 }
@@ -0,0 +1,12 @@
+use std::io::Read;
+
+use serde::de::DeserializeOwned;
+
+use crate::error::Error;
+
+/// Reads a whole JSON document into `T`.
+///
+/// The error includes the line and column of the problem if the document does not match.
+pub fn data_from_json<T: DeserializeOwned>(reader: impl Read) -> Result<T, Error> {
+	Ok(serde_json::from_reader(reader)?)
+}
@@ -1,184 +1,888 @@
 use ab_glyph::{FontRef, PxScale};
 use image::RgbImage;
+use imageproc::rect::Rect;
+use itertools::Itertools;
 
 pub use image::Rgb;
 
 use crate::{
-	colours,
-	common_types::{MultiPointGradient, Range},
+	canvas::{blend, Canvas},
+	common_types::{MultiPointGradient, Point, Range},
 	drawing::{
-		draw_graph_bars, draw_graph_bars_with_gradient, draw_graph_lines, draw_horizontal_lines,
-		draw_outer_lines, fill_canvas, horizontal_lines_and_labels, vertical_lines_and_labels,
-		MarkIntervals, Padding, Spacing,
+		draw_area, draw_graph_bars, draw_graph_bars_with_gradient, draw_graph_lines,
+		draw_grouped_bars, draw_hatching, draw_horizontal_lines, draw_outer_lines,
+		draw_stacked_bars, draw_triangle, horizontal_lines_and_labels, time_lines_and_labels,
+		vertical_lines_and_labels, MarkIntervals, Padding, Spacing, Stroke,
 	},
-	text_box::TextSegment,
+	error::Error,
+	icons::{draw_icon, Condition, ICON_SIZE},
+	sun::Daylight,
+	text_box::{TextBox, TextSegment},
+	theme::Theme,
+	time::ZonedTime,
+	units::Units,
 };
 
-pub struct Chart {
-	pub(crate) canvas: RgbImage,
+/// Settings shared by every chart, regardless of what it shows.
+#[derive(Debug, Clone)]
+pub struct ChartOptions {
+	/// How many pixels wide each pixel of the chart's layout is drawn, for high density displays. Padding, spacing, text and lines are all multiplied by this.
+	pub scale: u32,
+	pub theme: Theme,
+	/// Draws line series with different patterns as well as different colours, in smooth anti-aliased lines that some charts also draw thicker.
+	pub line_styles: bool,
+	/// Explains the series above each chart, in their colours.
+	pub captions: bool,
+	pub units: Units,
+	/// Hatches the parts of a chart where a series has no data, rather than just leaving them empty.
+	pub no_data: bool,
+	/// Shades between the temperature and the feels-like temperature on hourly temperature charts.
+	pub feels_like_area: bool,
+	/// Shades the background of hourly charts by how dark it is outside.
+	pub daylight: Option<Daylight>,
+	/// How the hourly wind chart shows which way the wind blows.
+	pub wind_directions: DirectionStyle,
+	/// How many directions the wind rose groups the wind into.
+	pub wind_rose_sectors: Sectors,
+}
+
+impl Default for ChartOptions {
+	fn default() -> Self {
+		Self {
+			scale: 1,
+			theme: Theme::DARK,
+			line_styles: false,
+			captions: false,
+			units: Units::METRIC,
+			no_data: false,
+			feels_like_area: false,
+			daylight: None,
+			wind_directions: DirectionStyle::Stripe,
+			wind_rose_sectors: Sectors::Sixteen,
+		}
+	}
+}
+
+/// How the wind direction is shown below the hourly wind chart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DirectionStyle {
+	/// A stripe of north, east, south and west lines that move up and down as the wind turns
+	#[default]
+	Stripe,
+	/// Arrows pointing the way the wind blows, longer the stronger it is
+	Arrows,
+	/// Meteorological wind barbs, with the staff pointing into the wind and a barb for every 10 knots
+	Barbs,
+}
+
+/// How many directions the wind rose groups the wind into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Sectors {
+	Eight,
+	#[default]
+	Sixteen,
+	/// Every 10 degrees
+	ThirtySix,
+}
+
+impl Sectors {
+	/// From the number of sectors, if it is one of 8, 16 and 36.
+	pub fn from_count(count: u32) -> Option<Self> {
+		match count {
+			8 => Some(Self::Eight),
+			16 => Some(Self::Sixteen),
+			36 => Some(Self::ThirtySix),
+			_ => None,
+		}
+	}
+	pub const fn count(self) -> u32 {
+		match self {
+			Self::Eight => 8,
+			Self::Sixteen => 16,
+			Self::ThirtySix => 36,
+		}
+	}
+}
+
+const CAPTION_FONT_SCALE: PxScale = PxScale { x: 14.0, y: 14.0 };
+const CAPTION_LINE_DISTANCE: u32 = 3;
+
+impl ChartOptions {
+	/// A caption wrapped to the width of `data_len` columns, if captions are enabled.
+	///
+	/// This doesn't depend on whether the chart has bars or lines, so the captions of charts with the same data and spacing are laid out alike when they are composited.
+	pub(crate) fn caption<'f, 's>(
+		&self,
+		segments: &[TextSegment<'s>],
+		font: &FontRef<'f>,
+		data_len: usize,
+		spacing: Spacing,
+	) -> Option<TextBox<'f, 's>> {
+		self.captions.then(|| {
+			TextBox::new(
+				segments,
+				font.clone(),
+				CAPTION_FONT_SCALE,
+				data_len as u32 * spacing.horizontal,
+				CAPTION_LINE_DISTANCE,
+			)
+		})
+	}
+	/// `stroke` if series are told apart by line style, otherwise a thin solid line.
+	pub(crate) fn stroke(&self, stroke: Stroke) -> Stroke {
+		if self.line_styles {
+			stroke
+		} else {
+			Stroke::THIN
+		}
+	}
+}
+
+pub struct Chart<C: Canvas = RgbImage> {
+	pub(crate) canvas: C,
+	/// Already multiplied by the scale
 	pub(crate) padding: Padding,
+	/// Already multiplied by the scale
 	pub(crate) spacing: Spacing,
+	pub(crate) scale: u32,
+	pub(crate) theme: Theme,
 }
 
-impl Chart {
-	pub fn new(data_len: usize, data_range: u32, spacing: Spacing, padding: Padding) -> Self {
+impl<C: Canvas> Chart<C> {
+	pub fn new(
+		data_len: usize,
+		data_range: u32,
+		spacing: Spacing,
+		padding: Padding,
+		options: &ChartOptions,
+	) -> Result<Self, Error> {
+		if data_len == 0 {
+			return Err(Error::NoData);
+		}
+		let scale = options.scale;
+		if scale == 0 {
+			return Err(Error::InvalidScale);
+		}
+		let spacing = spacing.scaled(scale);
+		let padding = padding.scaled(scale);
 		let width = (data_len as u32 - 1) * spacing.horizontal + padding.horizontal();
 		let height = data_range * spacing.vertical / 100 + padding.vertical();
-		let mut canvas = RgbImage::new(width, height);
-		fill_canvas(&mut canvas, colours::BACKGROUND);
-		Self {
+		let canvas = C::new(width, height, options.theme.background);
+		Ok(Self {
 			canvas,
 			padding,
 			spacing,
-		}
+			scale,
+			theme: options.theme,
+		})
 	}
 	pub fn draw<E: ChartElement>(&mut self, element: E) {
 		element.draw(self);
 	}
-	pub fn into_canvas(self) -> RgbImage {
+	pub fn into_canvas(self) -> C {
 		self.canvas
 	}
+	/// How far down the canvas `value` is drawn, where `max` is the value at the top of the chart.
+	fn value_y(&self, value: i32, max: i32) -> u32 {
+		self.padding.above + max.saturating_sub(value).max(0) as u32 * self.spacing.vertical / 100
+	}
 }
 
 pub trait ChartElement {
-	fn draw(self, chart: &mut Chart);
+	fn draw<C: Canvas>(self, chart: &mut Chart<C>);
 }
 
-pub struct AxisGridLabels<H>
+/// The background of the plot, shaded by how dark it is outside at each point's time. Drawn before [`AxisGridLabels`], so the grid stays on top.
+pub struct NightShading<'d> {
+	pub daylight: &'d Daylight,
+	/// The time of each point
+	pub times: &'d [ZonedTime],
+}
+
+impl ChartElement for NightShading<'_> {
+	fn draw<C: Canvas>(self, chart: &mut Chart<C>) {
+		if self.times.is_empty() {
+			return;
+		}
+		let column = chart.spacing.horizontal as f64;
+		let plot_left = chart.padding.left;
+		let plot_right = chart.canvas.width() - chart.padding.right;
+		let plot_height = chart.canvas.height() - chart.padding.vertical();
+		// Hourly data is taken to carry on evenly after the last point.
+		let step = match self.times {
+			[.., before, last] => last.timestamp - before.timestamp,
+			_ => 3600,
+		};
+		let timestamp = |x: u32| {
+			let position = (x - plot_left) as f64 / column;
+			let index = (position as usize).min(self.times.len() - 1);
+			let start = self.times[index].timestamp;
+			let end = self
+				.times
+				.get(index + 1)
+				.map_or(start + step, |time| time.timestamp);
+			start + ((position - index as f64) * (end - start) as f64).round() as i64
+		};
+		let colours = (plot_left..plot_right).map(|x| {
+			let darkness = self.daylight.darkness(timestamp(x));
+			blend(chart.theme.background, chart.theme.night, darkness)
+		});
+		// Columns of the same colour next to each other are filled together.
+		let mut x = plot_left;
+		for (colour, run) in &colours.chunk_by(|colour| *colour) {
+			let width = run.count() as u32;
+			if colour != chart.theme.background {
+				chart.canvas.fill_rect(
+					Rect::at(x as i32, chart.padding.above as i32).of_size(width, plot_height),
+					colour,
+				);
+			}
+			x += width;
+		}
+	}
+}
+
+pub struct AxisGridLabels<'f, H>
 where
 	H: Iterator<Item = u8>,
 {
+	/// In hundredths, like the data
 	pub vertical_intervals: MarkIntervals,
 	pub horizontal_intervals: MarkIntervals,
 	pub vertical_label_range: Range<i32>,
 	pub horizontal_labels: H,
+	/// When every point has a time, the horizontal axis is labelled from these instead of `horizontal_labels`, with a line and a label where each new day starts.
+	pub horizontal_times: Option<Vec<ZonedTime>>,
 	pub horizontal_labels_centered: bool,
-	pub font: FontRef<'static>,
+	pub font: FontRef<'f>,
 	pub font_scale: PxScale,
 }
 
-impl<H> ChartElement for AxisGridLabels<H>
+impl<H> ChartElement for AxisGridLabels<'_, H>
 where
 	H: Iterator<Item = u8>,
 {
-	fn draw(self, chart: &mut Chart) {
-		draw_outer_lines(&mut chart.canvas, chart.padding);
-		vertical_lines_and_labels(
-			&mut chart.canvas,
-			self.horizontal_labels,
-			self.horizontal_intervals,
-			&self.font,
-			self.font_scale,
-			chart.padding,
-			chart.spacing.horizontal,
-			self.horizontal_labels_centered,
-		);
+	fn draw<C: Canvas>(self, chart: &mut Chart<C>) {
+		let font_scale = scale_font(self.font_scale, chart.scale);
+		draw_outer_lines(&mut chart.canvas, chart.padding, chart.scale, &chart.theme);
+		match self.horizontal_times {
+			Some(times) => time_lines_and_labels(
+				&mut chart.canvas,
+				&times,
+				self.horizontal_intervals,
+				&self.font,
+				font_scale,
+				chart.padding,
+				chart.spacing.horizontal,
+				chart.scale,
+				&chart.theme,
+				self.horizontal_labels_centered,
+			),
+			None => vertical_lines_and_labels(
+				&mut chart.canvas,
+				self.horizontal_labels,
+				self.horizontal_intervals,
+				&self.font,
+				font_scale,
+				chart.padding,
+				chart.spacing.horizontal,
+				chart.scale,
+				&chart.theme,
+				self.horizontal_labels_centered,
+			),
+		}
 		horizontal_lines_and_labels(
 			&mut chart.canvas,
 			self.vertical_label_range,
 			self.vertical_intervals,
 			&self.font,
-			self.font_scale,
+			font_scale,
 			chart.padding,
 			chart.spacing.vertical,
+			chart.scale,
+			&chart.theme,
 		);
 	}
 }
 
+/// Horizontal bands of colour across the plot, like ranges of comfort. Drawn before [`AxisGridLabels`], so the grid stays on top.
+pub struct Bands {
+	/// The values each band spans, in the hundredths of the data, and its colour
+	pub bands: Vec<(Range<i32>, Rgb<u8>)>,
+	/// The value at the top of the chart
+	pub max: i32,
+}
+
+impl ChartElement for Bands {
+	fn draw<C: Canvas>(self, chart: &mut Chart<C>) {
+		let top = chart.padding.above as i64;
+		let bottom = (chart.canvas.height() - chart.padding.below) as i64;
+		// Bands can be open-ended, so this is done in i64 to not overflow.
+		let y = |value: i32| {
+			(top + (self.max as i64 - value as i64) * chart.spacing.vertical as i64 / 100)
+				.clamp(top, bottom) as i32
+		};
+		let width = chart.canvas.width() - chart.padding.horizontal();
+		for (range, colour) in self.bands {
+			let (band_top, band_bottom) = (y(range.end()), y(range.start()));
+			if band_bottom > band_top {
+				chart.canvas.fill_rect(
+					Rect::at(chart.padding.left as i32, band_top)
+						.of_size(width, (band_bottom - band_top) as u32),
+					colour,
+				);
+			}
+		}
+	}
+}
+
+/// Labels on the right for a second series drawn on the same grid, in its colour. The range is spread over the height of the plot, with a label at the bottom, the top and `steps - 1` evenly spaced values between.
+pub struct SecondaryLabels<'f> {
+	/// In hundredths, like the data
+	pub range: Range<i32>,
+	pub steps: u32,
+	pub colour: Rgb<u8>,
+	pub font: FontRef<'f>,
+	pub font_scale: PxScale,
+}
+
+impl ChartElement for SecondaryLabels<'_> {
+	fn draw<C: Canvas>(self, chart: &mut Chart<C>) {
+		let font_scale = scale_font(self.font_scale, chart.scale);
+		let plot_height = chart.canvas.height() - chart.padding.vertical();
+		let x = (chart.canvas.width() - chart.padding.right + 3 * chart.scale) as i32;
+		for step in 0..=self.steps {
+			let value = self.range.start() + self.range.len() * step as i32 / self.steps as i32;
+			let y = chart.padding.above + plot_height * (self.steps - step) / self.steps;
+			let text = &if value % 100 == 0 {
+				format!("{}", value / 100)
+			} else {
+				format!("{}", value as f32 / 100.0)
+			};
+			let (_text_width, text_height) =
+				imageproc::drawing::text_size(font_scale, &self.font, text);
+			chart.canvas.draw_text(
+				text,
+				self.colour,
+				x,
+				y as i32 - text_height as i32 / 2,
+				&self.font,
+				font_scale,
+			);
+		}
+	}
+}
+
 pub struct GradientBars<D>
 where
-	D: Iterator<Item = i32>,
+	D: Iterator,
+	D::Item: Into<Option<i32>>,
 {
 	pub gradient: MultiPointGradient,
 	pub data: D,
+	/// The value bars grow from, usually 0
+	pub baseline: i32,
+	/// The value at the top of the chart
+	pub max: i32,
 }
 
 impl<D> ChartElement for GradientBars<D>
 where
-	D: Iterator<Item = i32>,
+	D: Iterator,
+	D::Item: Into<Option<i32>>,
 {
-	fn draw(self, chart: &mut Chart) {
+	fn draw<C: Canvas>(self, chart: &mut Chart<C>) {
 		draw_graph_bars_with_gradient(
 			&mut chart.canvas,
-			self.data,
-			&self.gradient,
+			self.data.map(Into::into),
+			&self.gradient.scaled(chart.scale),
+			self.baseline,
+			self.max,
 			chart.padding,
 			chart.spacing,
+			chart.scale,
 		);
 	}
 }
 
 pub struct SolidBars<D>
 where
-	D: Iterator<Item = i32>,
+	D: Iterator,
+	D::Item: Into<Option<i32>>,
 {
 	pub colour: Rgb<u8>,
 	pub data: D,
+	/// The value bars grow from, usually 0
+	pub baseline: i32,
+	/// The value at the top of the chart
+	pub max: i32,
 }
 
 impl<D> ChartElement for SolidBars<D>
 where
-	D: Iterator<Item = i32>,
+	D: Iterator,
+	D::Item: Into<Option<i32>>,
 {
-	fn draw(self, chart: &mut Chart) {
+	fn draw<C: Canvas>(self, chart: &mut Chart<C>) {
 		draw_graph_bars(
 			&mut chart.canvas,
-			self.data,
+			self.data.map(Into::into),
 			self.colour,
+			self.baseline,
+			self.max,
+			chart.padding,
+			chart.spacing,
+			chart.scale,
+		);
+	}
+}
+
+/// Bars for several series in each column, on top of each other in the order of `colours` from the bottom, so the top of each stack is their total. Amounts grow up from 0.
+pub struct StackedBars<D>
+where
+	D: Iterator<Item = Vec<Option<i32>>>,
+{
+	pub colours: Vec<Rgb<u8>>,
+	/// Each column's amount for every series
+	pub data: D,
+	/// The value at the top of the chart
+	pub max: i32,
+}
+
+impl<D> ChartElement for StackedBars<D>
+where
+	D: Iterator<Item = Vec<Option<i32>>>,
+{
+	fn draw<C: Canvas>(self, chart: &mut Chart<C>) {
+		draw_stacked_bars(
+			&mut chart.canvas,
+			self.data,
+			&self.colours,
+			self.max,
+			chart.padding,
+			chart.spacing,
+			chart.scale,
+		);
+	}
+}
+
+/// Bars for several series side by side, splitting each column's width between them in the order of `colours` from the left.
+pub struct GroupedBars<D>
+where
+	D: Iterator<Item = Vec<Option<i32>>>,
+{
+	pub colours: Vec<Rgb<u8>>,
+	/// Each column's value for every series
+	pub data: D,
+	/// The value bars grow from, usually 0
+	pub baseline: i32,
+	/// The value at the top of the chart
+	pub max: i32,
+}
+
+impl<D> ChartElement for GroupedBars<D>
+where
+	D: Iterator<Item = Vec<Option<i32>>>,
+{
+	fn draw<C: Canvas>(self, chart: &mut Chart<C>) {
+		draw_grouped_bars(
+			&mut chart.canvas,
+			self.data,
+			&self.colours,
+			self.baseline,
+			self.max,
 			chart.padding,
 			chart.spacing,
+			chart.scale,
 		);
 	}
 }
 
 pub struct HorizontalLines<D>
 where
-	D: Iterator<Item = i32>,
+	D: Iterator,
+	D::Item: Into<Option<i32>>,
 {
 	pub colour: Rgb<u8>,
 	pub data: D,
+	/// The value bars grow from, usually 0
+	pub baseline: i32,
+	/// The value at the top of the chart
+	pub max: i32,
 }
 
 impl<D> ChartElement for HorizontalLines<D>
 where
-	D: Iterator<Item = i32>,
+	D: Iterator,
+	D::Item: Into<Option<i32>>,
 {
-	fn draw(self, chart: &mut Chart) {
+	fn draw<C: Canvas>(self, chart: &mut Chart<C>) {
 		draw_horizontal_lines(
 			&mut chart.canvas,
-			self.data,
+			self.data.map(Into::into),
+			self.colour,
+			self.baseline,
+			self.max,
+			chart.padding,
+			chart.spacing,
+			chart.scale,
+		);
+	}
+}
+
+/// Fills between `data` and `lower` in `colour`, at `opacity` from 0 to 1 so whatever is under it still shows through. For the area under a line, `lower` can be [`std::iter::repeat`] of the baseline. There is a gap wherever either is missing.
+pub struct Area<D, L>
+where
+	D: Iterator,
+	D::Item: Into<Option<i32>>,
+	L: Iterator,
+	L::Item: Into<Option<i32>>,
+{
+	pub colour: Rgb<u8>,
+	pub opacity: f32,
+	pub data: D,
+	/// The other edge of the area
+	pub lower: L,
+	/// The value at the top of the chart
+	pub max: i32,
+}
+
+impl<D, L> ChartElement for Area<D, L>
+where
+	D: Iterator,
+	D::Item: Into<Option<i32>>,
+	L: Iterator,
+	L::Item: Into<Option<i32>>,
+{
+	fn draw<C: Canvas>(self, chart: &mut Chart<C>) {
+		draw_area(
+			&mut chart.canvas,
+			self.data.map(Into::into).zip(self.lower.map(Into::into)),
 			self.colour,
+			self.opacity,
+			self.max,
 			chart.padding,
 			chart.spacing,
+			chart.scale,
 		);
 	}
 }
 
 pub struct Line<D>
 where
-	D: Iterator<Item = i32>,
+	D: Iterator,
+	D::Item: Into<Option<i32>>,
 {
 	pub colour: Rgb<u8>,
+	pub stroke: Stroke,
 	pub data: D,
 	pub max: i32,
 }
 
 impl<D> ChartElement for Line<D>
 where
-	D: Iterator<Item = i32>,
+	D: Iterator,
+	D::Item: Into<Option<i32>>,
 {
-	fn draw(self, chart: &mut Chart) {
+	fn draw<C: Canvas>(self, chart: &mut Chart<C>) {
 		draw_graph_lines(
 			&mut chart.canvas,
-			self.data,
+			self.data.map(Into::into),
 			self.colour,
+			self.stroke,
 			self.max,
 			chart.padding,
 			chart.spacing,
+			chart.scale,
 		);
 	}
 }
 
+/// Hatching wherever a series is missing values, for charts drawn with [`ChartOptions::no_data`].
+pub struct NoData<D>
+where
+	D: Iterator<Item = bool>,
+{
+	/// Whether each value is missing
+	pub missing: D,
+	/// Bars fill the column after their point, while points on a line get half a column either side.
+	pub bars: bool,
+}
+
+impl<D> ChartElement for NoData<D>
+where
+	D: Iterator<Item = bool>,
+{
+	fn draw<C: Canvas>(self, chart: &mut Chart<C>) {
+		let column = chart.spacing.horizontal as i32;
+		let offset = if self.bars { 0 } else { column / 2 };
+		let plot_left = chart.padding.left as i32;
+		let plot_right = (chart.canvas.width() - chart.padding.right) as i32;
+		let plot_height = chart.canvas.height() - chart.padding.vertical();
+		let mut hatch = |first: usize, after_last: usize| {
+			// Inside the grid lines on either side
+			let left =
+				(plot_left + first as i32 * column - offset).max(plot_left) + chart.scale as i32;
+			let right = (plot_left + after_last as i32 * column - offset).min(plot_right);
+			if right > left {
+				draw_hatching(
+					&mut chart.canvas,
+					Rect::at(left, (chart.padding.above + chart.scale) as i32)
+						.of_size((right - left) as u32, plot_height - chart.scale),
+					chart.theme.grid_lines,
+					chart.scale,
+				);
+			}
+		};
+		// Missing values next to each other are hatched together, so the pattern is unbroken.
+		let mut start = None;
+		for (index, missing) in self.missing.chain([false]).enumerate() {
+			match (missing, start) {
+				(true, None) => start = Some(index),
+				(false, Some(first)) => {
+					hatch(first, index);
+					start = None;
+				}
+				_ => {}
+			}
+		}
+	}
+}
+
+/// A triangle pointing up or down, to flag a point.
+#[derive(Debug, Clone, Copy)]
+pub struct Marker {
+	pub up: bool,
+	/// Drawn bigger, for the more extreme of two levels
+	pub large: bool,
+	pub colour: Rgb<u8>,
+}
+
+/// A row of [`Marker`]s in the padding above the plot, centred on the points that have one. The chart needs about 9 pixels more padding above for them.
+pub struct Markers<D>
+where
+	D: Iterator<Item = Option<Marker>>,
+{
+	pub data: D,
+}
+
+impl<D> ChartElement for Markers<D>
+where
+	D: Iterator<Item = Option<Marker>>,
+{
+	fn draw<C: Canvas>(self, chart: &mut Chart<C>) {
+		let bottom = chart.padding.above - 2 * chart.scale;
+		for (index, marker) in self.data.enumerate() {
+			let Some(marker) = marker else {
+				continue;
+			};
+			draw_triangle(
+				&mut chart.canvas,
+				chart.padding.left + index as u32 * chart.spacing.horizontal,
+				bottom,
+				if marker.large { 7 } else { 5 },
+				marker.up,
+				marker.colour,
+				chart.scale,
+			);
+		}
+	}
+}
+
+/// A row of weather icons in the padding above the plot, centred on their points. Where the columns are too narrow for an icon each, only every second or third point gets one, starting from the first. The chart needs [`ICON_ROW_HEIGHT`] pixels more padding above for them.
+pub struct Icons<D>
+where
+	D: Iterator<Item = Option<Condition>>,
+{
+	pub data: D,
+}
+
+/// How much room [`Icons`] need above the plot, before scaling
+pub(crate) const ICON_ROW_HEIGHT: u32 = ICON_SIZE + 4;
+
+impl<D> ChartElement for Icons<D>
+where
+	D: Iterator<Item = Option<Condition>>,
+{
+	fn draw<C: Canvas>(self, chart: &mut Chart<C>) {
+		let column = chart.spacing.horizontal / chart.scale;
+		let interval = (ICON_SIZE + 2).div_ceil(column) as usize;
+		let centre_y = chart.padding.above - (2 + ICON_SIZE.div_ceil(2)) * chart.scale;
+		for (index, condition) in self.data.enumerate().step_by(interval) {
+			let Some(condition) = condition else {
+				continue;
+			};
+			let centre = Point {
+				x: chart.padding.left + index as u32 * chart.spacing.horizontal,
+				y: centre_y,
+			};
+			draw_icon(
+				&mut chart.canvas,
+				condition,
+				centre,
+				chart.scale,
+				&chart.theme,
+			);
+		}
+	}
+}
+
+/// Rows of cells in the padding above the plot, one cell per bar column, mixed from the background towards `colour` by their value. The first row is drawn at the top. The chart needs `rows * (row_height + 1) + 2` pixels more padding above for them.
+pub struct ShadedRows {
+	pub rows: Vec<Vec<Option<i32>>>,
+	pub colour: Rgb<u8>,
+	/// The value that is fully `colour`
+	pub max: i32,
+	pub row_height: u32,
+}
+
+impl ChartElement for ShadedRows {
+	fn draw<C: Canvas>(self, chart: &mut Chart<C>) {
+		let row_height = self.row_height * chart.scale;
+		let gap = chart.scale;
+		let top =
+			chart.padding.above - 2 * chart.scale - self.rows.len() as u32 * (row_height + gap);
+		for (row_index, row) in self.rows.into_iter().enumerate() {
+			let y = top + row_index as u32 * (row_height + gap);
+			for (index, value) in row.into_iter().enumerate() {
+				let Some(value) = value else {
+					continue;
+				};
+				let amount = value.clamp(0, self.max) as f32 / self.max as f32;
+				// Inside the grid lines on either side, like bars
+				let x = chart.padding.left + index as u32 * chart.spacing.horizontal + chart.scale;
+				chart.canvas.fill_rect(
+					Rect::at(x as i32, y as i32)
+						.of_size(chart.spacing.horizontal - chart.scale, row_height),
+					blend(chart.theme.background, self.colour, amount),
+				);
+			}
+		}
+	}
+}
+
+/// How far [`RangeBars`] are inset from each side of their column, leaving room for [`Whiskers`] around them.
+const RANGE_BAR_INSET: u32 = 5;
+
+/// Bars spanning each range, one per column, narrower than ordinary bars. The gradient is positioned from the bottom of the canvas, so a bar's colours say how high it reaches.
+pub struct RangeBars<D>
+where
+	D: Iterator<Item = Option<Range<i32>>>,
+{
+	pub gradient: MultiPointGradient,
+	pub data: D,
+	/// The value at the top of the chart
+	pub max: i32,
+}
+
+impl<D> ChartElement for RangeBars<D>
+where
+	D: Iterator<Item = Option<Range<i32>>>,
+{
+	fn draw<C: Canvas>(self, chart: &mut Chart<C>) {
+		let gradient = self.gradient.scaled(chart.scale);
+		let inset = RANGE_BAR_INSET * chart.scale;
+		for (index, range) in self.data.enumerate() {
+			let Some(range) = range else {
+				continue;
+			};
+			let top = chart.value_y(range.end(), self.max);
+			// At least a pixel tall, so a day that stays the same temperature still shows.
+			let height = (chart.value_y(range.start(), self.max) - top).max(chart.scale);
+			let x = chart.padding.left + index as u32 * chart.spacing.horizontal + inset;
+			chart.canvas.fill_rect_with_gradient(
+				Rect::at(x as i32, top as i32)
+					.of_size(chart.spacing.horizontal + chart.scale - 2 * inset, height),
+				&gradient,
+			);
+		}
+	}
+}
+
+/// A thin line down the middle of each column across the range, capped at both ends. Drawn before [`RangeBars`], only the parts outside the bars show.
+pub struct Whiskers<D>
+where
+	D: Iterator<Item = Option<Range<i32>>>,
+{
+	pub colour: Rgb<u8>,
+	pub data: D,
+	/// The value at the top of the chart
+	pub max: i32,
+}
+
+impl<D> ChartElement for Whiskers<D>
+where
+	D: Iterator<Item = Option<Range<i32>>>,
+{
+	fn draw<C: Canvas>(self, chart: &mut Chart<C>) {
+		let scale = chart.scale;
+		// No narrower than the whisker itself when the columns are tight
+		let cap_width = chart
+			.spacing
+			.horizontal
+			.saturating_sub(2 * (RANGE_BAR_INSET + 2) * scale)
+			.max(scale);
+		for (index, range) in self.data.enumerate() {
+			let Some(range) = range else {
+				continue;
+			};
+			let [top, bottom] =
+				[range.end(), range.start()].map(|value| chart.value_y(value, self.max));
+			let centre = chart.padding.left
+				+ index as u32 * chart.spacing.horizontal
+				+ chart.spacing.horizontal / 2;
+			chart.canvas.fill_rect(
+				Rect::at(centre as i32, top as i32).of_size(scale, bottom - top + scale),
+				self.colour,
+			);
+			for y in [top, bottom] {
+				chart.canvas.fill_rect(
+					Rect::at((centre + scale / 2 - cap_width / 2) as i32, y as i32)
+						.of_size(cap_width, scale),
+					self.colour,
+				);
+			}
+		}
+	}
+}
+
+/// Short level marks within each column, splitting it evenly between the column's values in order, such as the temperatures through the parts of a day.
+pub struct Ticks<D>
+where
+	D: Iterator<Item = Vec<Option<i32>>>,
+{
+	pub colour: Rgb<u8>,
+	pub data: D,
+	/// The value at the top of the chart
+	pub max: i32,
+}
+
+impl<D> ChartElement for Ticks<D>
+where
+	D: Iterator<Item = Vec<Option<i32>>>,
+{
+	fn draw<C: Canvas>(self, chart: &mut Chart<C>) {
+		let inset = RANGE_BAR_INSET * chart.scale;
+		let width = chart.spacing.horizontal + chart.scale - 2 * inset;
+		for (index, values) in self.data.enumerate() {
+			let left = chart.padding.left + index as u32 * chart.spacing.horizontal + inset;
+			let count = values.len() as u32;
+			for (part, value) in values.into_iter().enumerate() {
+				let Some(value) = value else {
+					continue;
+				};
+				let [start, end] =
+					[part as u32, part as u32 + 1].map(|edge| left + width * edge / count);
+				chart.canvas.fill_rect(
+					Rect::at(start as i32, chart.value_y(value, self.max) as i32)
+						.of_size(end - start, chart.scale),
+					self.colour,
+				);
+			}
+		}
+	}
+}
+
 pub struct Label<'s, 'f> {
 	pub text_segments: &'s [TextSegment<'s>],
 	pub font: FontRef<'f>,
@@ -187,21 +891,37 @@ pub struct Label<'s, 'f> {
 }
 
 impl<'s, 'f> ChartElement for Label<'s, 'f> {
-	fn draw(self, chart: &mut Chart) {
+	fn draw<C: Canvas>(self, chart: &mut Chart<C>) {
+		let font_scale = scale_font(self.font_scale, chart.scale);
 		let mut cursor = chart.padding.left as i32;
 		for segment in self.text_segments {
-			imageproc::drawing::draw_text_mut(
-				&mut chart.canvas,
+			chart.canvas.draw_text(
+				segment.text,
 				segment.color,
 				cursor,
-				self.distance_from_top,
-				self.font_scale,
+				self.distance_from_top * chart.scale as i32,
 				&self.font,
-				segment.text,
+				font_scale,
 			);
 			let (text_width, _text_height) =
-				imageproc::drawing::text_size(self.font_scale, &self.font, segment.text);
+				imageproc::drawing::text_size(font_scale, &self.font, segment.text);
 			cursor += text_width as i32;
 		}
 	}
 }
+
+/// `padding` with room above the chart for the caption.
+pub(crate) fn padding_with_caption(padding: Padding, caption: Option<&TextBox>) -> Padding {
+	let caption_height = caption.map_or(0, |caption| CAPTION_LINE_DISTANCE + caption.height());
+	Padding {
+		above: padding.above + caption_height,
+		..padding
+	}
+}
+
+pub(crate) fn scale_font(font_scale: PxScale, scale: u32) -> PxScale {
+	PxScale {
+		x: font_scale.x * scale as f32,
+		y: font_scale.y * scale as f32,
+	}
+}
@@ -0,0 +1,227 @@
+//! Weather icons drawn from dots and lines, so no image files are needed.
+
+use image::Rgb;
+use serde::Deserialize;
+
+use crate::{canvas::Canvas, common_types::Point, drawing::Pen, theme::Theme};
+
+/// How wide and tall an icon is, before scaling
+pub(crate) const ICON_SIZE: u32 = 13;
+
+/// What the weather is doing, as shown by an icon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+	Clear,
+	ClearNight,
+	PartlyCloudy,
+	PartlyCloudyNight,
+	Cloudy,
+	Rain,
+	Snow,
+	Thunder,
+	Fog,
+	Wind,
+}
+
+impl Condition {
+	/// From an [OpenWeather condition code](https://openweathermap.org/weather-conditions). Drizzle and freezing rain are shown as rain.
+	pub fn from_open_weather(id: u16, night: bool) -> Option<Self> {
+		Some(match id {
+			200..300 => Self::Thunder,
+			300..600 => Self::Rain,
+			600..700 => Self::Snow,
+			// Squalls and tornadoes
+			771 | 781 => Self::Wind,
+			700..800 => Self::Fog,
+			800 => Self::clear(night),
+			801 | 802 => Self::partly_cloudy(night),
+			803 | 804 => Self::Cloudy,
+			_ => return None,
+		})
+	}
+	/// From a [WMO weather code](https://open-meteo.com/en/docs#weather_variable_documentation), as used by Open-Meteo. Drizzle and freezing rain are shown as rain.
+	pub fn from_wmo(code: u8, night: bool) -> Option<Self> {
+		Some(match code {
+			0 => Self::clear(night),
+			1 | 2 => Self::partly_cloudy(night),
+			3 => Self::Cloudy,
+			45 | 48 => Self::Fog,
+			51..=67 | 80..=82 => Self::Rain,
+			71..=77 | 85 | 86 => Self::Snow,
+			95..=99 => Self::Thunder,
+			_ => return None,
+		})
+	}
+	fn clear(night: bool) -> Self {
+		if night {
+			Self::ClearNight
+		} else {
+			Self::Clear
+		}
+	}
+	fn partly_cloudy(night: bool) -> Self {
+		if night {
+			Self::PartlyCloudyNight
+		} else {
+			Self::PartlyCloudy
+		}
+	}
+}
+
+/// Draws the icon for `condition` centred on the pixel at `centre`.
+pub(crate) fn draw_icon<C: Canvas>(
+	canvas: &mut C,
+	condition: Condition,
+	centre: Point<u32>,
+	scale: u32,
+	theme: &Theme,
+) {
+	let pen = &mut Pen::new(canvas, centre, scale);
+	let background = theme.background;
+	match condition {
+		Condition::Clear => sun(pen, 0.0, 0.0, 1.0, theme.sun),
+		Condition::ClearNight => moon(pen, 0.0, 0.0, 1.0, theme.moon, background),
+		Condition::PartlyCloudy | Condition::PartlyCloudyNight => {
+			if condition == Condition::PartlyCloudy {
+				sun(pen, 2.5, -2.0, 0.65, theme.sun);
+			} else {
+				moon(pen, 2.5, -2.0, 0.65, theme.moon, background);
+			}
+			cloud(pen, -1.0, 2.0, 0.75, theme.clouds, background);
+		}
+		Condition::Cloudy => cloud(pen, 0.0, 0.0, 1.0, theme.clouds, background),
+		Condition::Rain => {
+			cloud(pen, 0.0, -2.0, 0.85, theme.clouds, background);
+			for x in [-3.0, 0.0, 3.0] {
+				pen.line(&[(x + 0.5, 3.0), (x - 0.5, 6.0)], 1.2, theme.rain);
+			}
+		}
+		Condition::Snow => {
+			cloud(pen, 0.0, -2.0, 0.85, theme.clouds, background);
+			for (x, y) in [(-3.0, 4.0), (0.0, 5.5), (3.0, 4.0)] {
+				pen.dot(x, y, 2.0, theme.snow);
+			}
+		}
+		Condition::Thunder => {
+			cloud(pen, 0.0, -2.0, 0.85, theme.clouds, background);
+			pen.line(
+				&[(1.0, 2.0), (-1.0, 4.5), (1.0, 4.5), (-1.0, 6.5)],
+				1.2,
+				theme.lightning,
+			);
+		}
+		Condition::Fog => {
+			for (y, start, end) in [(-3.5, -4.5, 5.0), (0.0, -5.5, 4.0), (3.5, -4.0, 5.5)] {
+				pen.line(&[(start, y), (end, y)], 1.5, theme.clouds);
+			}
+		}
+		Condition::Wind => {
+			// Gusts trailing off to the left, two of them curling back at the end
+			pen.line(
+				&[
+					(-6.0, -2.0),
+					(3.0, -2.0),
+					(5.0, -3.0),
+					(5.0, -4.5),
+					(3.5, -5.0),
+				],
+				1.2,
+				theme.clouds,
+			);
+			pen.line(&[(-6.0, 1.0), (5.5, 1.0)], 1.2, theme.clouds);
+			pen.line(
+				&[(-6.0, 4.0), (2.0, 4.0), (4.0, 5.0), (4.0, 6.0), (2.5, 6.5)],
+				1.2,
+				theme.clouds,
+			);
+		}
+	}
+}
+
+fn sun<C: Canvas>(pen: &mut Pen<C>, x: f32, y: f32, size: f32, colour: Rgb<u8>) {
+	pen.dot(x, y, 7.0 * size, colour);
+	for ray in 0..8 {
+		let angle = ray as f32 * std::f32::consts::FRAC_PI_4;
+		let (sin, cos) = angle.sin_cos();
+		pen.line(
+			&[
+				(x + 5.0 * size * cos, y + 5.0 * size * sin),
+				(x + 6.5 * size * cos, y + 6.5 * size * sin),
+			],
+			1.2 * size,
+			colour,
+		);
+	}
+}
+
+/// A crescent, cut out of a full moon with the background colour.
+fn moon<C: Canvas>(
+	pen: &mut Pen<C>,
+	x: f32,
+	y: f32,
+	size: f32,
+	colour: Rgb<u8>,
+	background: Rgb<u8>,
+) {
+	pen.dot(x, y, 10.0 * size, colour);
+	pen.dot(x + 3.0 * size, y - 2.5 * size, 8.0 * size, background);
+}
+
+/// Puffs on a flat base, outlined in the background colour so it stands apart from anything behind it.
+fn cloud<C: Canvas>(
+	pen: &mut Pen<C>,
+	x: f32,
+	y: f32,
+	size: f32,
+	colour: Rgb<u8>,
+	background: Rgb<u8>,
+) {
+	let puffs = [(-3.5, 1.0, 5.0), (0.0, -1.0, 7.0), (3.5, 1.0, 5.0)];
+	for (outline, colour) in [(2.0, background), (0.0, colour)] {
+		for (puff_x, puff_y, diameter) in puffs {
+			pen.dot(
+				x + puff_x * size,
+				y + puff_y * size,
+				(diameter + outline) * size,
+				colour,
+			);
+		}
+		pen.line(
+			&[
+				(x - 3.5 * size, y + 1.5 * size),
+				(x + 3.5 * size, y + 1.5 * size),
+			],
+			(4.0 + outline) * size,
+			colour,
+		);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn condition_codes() {
+		assert_eq!(
+			Condition::from_open_weather(800, true),
+			Some(Condition::ClearNight)
+		);
+		assert_eq!(
+			Condition::from_open_weather(502, false),
+			Some(Condition::Rain)
+		);
+		assert_eq!(
+			Condition::from_open_weather(781, false),
+			Some(Condition::Wind)
+		);
+		assert_eq!(
+			Condition::from_open_weather(741, false),
+			Some(Condition::Fog)
+		);
+		assert_eq!(Condition::from_wmo(2, false), Some(Condition::PartlyCloudy));
+		assert_eq!(Condition::from_wmo(86, false), Some(Condition::Snow));
+		assert_eq!(Condition::from_wmo(4, false), None);
+	}
+}
@@ -1,9 +1,17 @@
-mod colours;
+pub mod canvas;
 pub mod common_types;
 pub mod drawing;
+pub mod error;
 mod from_args;
+mod from_json;
 pub mod generic_graph;
+pub mod icons;
 pub mod modules;
+pub mod sources;
+pub mod sun;
 pub mod text_box;
+pub mod theme;
+pub mod time;
+pub mod units;
 pub mod util;
 pub use image::RgbImage;
@@ -1,41 +1,353 @@
-use graph::modules::{
-	daily_temp, hourly_composite, hourly_pop, hourly_precipitation, hourly_temp, hourly_uvi,
-	hourly_wind, minutely_precipitation,
+use std::{
+	fs::File,
+	io::{BufWriter, Read},
 };
 
-use image::{codecs::png::PngEncoder, ColorType, ImageEncoder, ImageFormat};
+use ab_glyph::FontRef;
+use graph::{
+	canvas::{svg::SvgCanvas, Canvas},
+	error::Error,
+	generic_graph::{ChartOptions, DirectionStyle, Sectors},
+	modules::{
+		daily_precipitation, daily_temp, daily_temp_range, hourly_clouds,
+		hourly_composite::{self, Extras},
+		hourly_humidity, hourly_pop, hourly_precipitation, hourly_pressure, hourly_temp,
+		hourly_uvi, hourly_wind, minutely_precipitation, wind_rose,
+	},
+	sources::{one_call::OneCall, open_meteo::OpenMeteo},
+	sun::{Daylight, Twilight},
+	theme::Theme,
+	time::TimeZone,
+	units::{Precipitation, Speed, Temperature, Units},
+	RgbImage,
+};
 
 fn main() {
+	if let Err(error) = run() {
+		eprintln!("{error}");
+		std::process::exit(1);
+	}
+}
+
+fn run() -> Result<(), Error> {
 	let font_data: &[u8] = include_bytes!("../RobotoCondensed-Regular.ttf");
 	let font = ab_glyph::FontRef::try_from_slice(font_data).expect("Failed to read font");
-	let mut args = std::env::args();
-	let mut mode = args.nth(1).expect("No arguments used");
-	let to_file = mode.as_str() == "file";
-	if to_file {
-		mode = args.next().expect("No arguments beyond \"file\"");
-	}
-	let args: Vec<_> = args.collect();
-	let canvas = match mode.as_str() {
-		"daily_temp" => daily_temp::parse_and_create(&font, args),
-		"hourly_pop" => hourly_pop::parse_and_create(&font, args),
-		"hourly_precipitation" => hourly_precipitation::parse_and_create(&font, args),
-		"hourly_temp" => hourly_temp::parse_and_create(&font, args),
-		"hourly_uvi" => hourly_uvi::parse_and_create(&font, args),
-		"hourly_wind" => hourly_wind::parse_and_create(&font, args),
-		"hourly_composite" => hourly_composite::parse_and_create(&font, args),
-		"minutely_precipitation" => minutely_precipitation::parse_and_create(&font, args),
-		x => panic!("Unexpected first argument {x}"),
+	let mut args = std::env::args().skip(1).peekable();
+	let to_file = args.next_if_eq("file").is_some();
+	let svg = args.next_if_eq("svg").is_some();
+	let mut options = ChartOptions::default();
+	if let Some(scale) = args.next_if(|arg| {
+		arg.strip_suffix('x')
+			.is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+	}) {
+		options.scale = scale[..scale.len() - 1]
+			.parse()
+			.map_err(|_| Error::InvalidOption {
+				name: "scale",
+				value: scale,
+			})?;
+	}
+	if args.next_if_eq("light").is_some() {
+		options.theme = Theme::LIGHT;
+	} else if args.next_if_eq("colour_blind").is_some() {
+		options.theme = Theme::COLOUR_BLIND;
+	} else if args.next_if_eq("theme").is_some() {
+		let path = args.next().ok_or(Error::MissingArgument("theme"))?;
+		options.theme = Theme::from_reader(File::open(path)?)?;
+	} else {
+		args.next_if_eq("dark");
+	}
+	options.line_styles = args.next_if_eq("line_styles").is_some();
+	options.captions = args.next_if_eq("captions").is_some();
+	options.no_data = args.next_if_eq("no_data").is_some();
+	options.feels_like_area = args.next_if_eq("feels_like_area").is_some();
+	if args.next_if_eq("wind_arrows").is_some() {
+		options.wind_directions = DirectionStyle::Arrows;
+	} else if args.next_if_eq("wind_barbs").is_some() {
+		options.wind_directions = DirectionStyle::Barbs;
+	}
+	if args.next_if_eq("sectors").is_some() {
+		let count = args.next().ok_or(Error::MissingArgument("sectors"))?;
+		let sectors = count.parse().ok().and_then(Sectors::from_count);
+		options.wind_rose_sectors = sectors.ok_or(Error::InvalidOption {
+			name: "sectors",
+			value: count,
+		})?;
+	}
+	// Charts added to API composites on top of the original ones
+	let extras = Extras {
+		humidity: args.next_if_eq("with_humidity").is_some(),
+		clouds: args.next_if_eq("with_clouds").is_some(),
 	};
+	// Without coordinates, the location or sunrise and sunset times come from the API response.
+	let mut source_daylight = None;
+	if args.next_if_eq("daylight").is_some() {
+		let twilight = if args.next_if_eq("civil").is_some() {
+			Twilight::Civil
+		} else if args.next_if_eq("nautical").is_some() {
+			Twilight::Nautical
+		} else {
+			Twilight::None
+		};
+		if args.next_if_eq("sun_times").is_some() {
+			source_daylight = Some(SourceDaylight::SunTimes);
+		} else if let Some(latitude) = args.next_if(|arg| arg.parse::<f64>().is_ok()) {
+			let longitude = args.next().ok_or(Error::MissingArgument("longitude"))?;
+			options.daylight = Some(Daylight::Location {
+				latitude: parse_coordinate(latitude, "latitude")?,
+				longitude: parse_coordinate(longitude, "longitude")?,
+				twilight,
+			});
+		} else {
+			source_daylight = Some(SourceDaylight::Location(twilight));
+		}
+	}
+	// A unit system, then any individual units to change from it
+	loop {
+		let units = &mut options.units;
+		match args.peek().map(String::as_str) {
+			Some("metric") => *units = Units::METRIC,
+			Some("imperial") => *units = Units::IMPERIAL,
+			Some("celsius") => units.temperature = Temperature::Celsius,
+			Some("fahrenheit") => units.temperature = Temperature::Fahrenheit,
+			Some("ms") => units.speed = Speed::MetresPerSecond,
+			Some("kmh") => units.speed = Speed::KilometresPerHour,
+			Some("mph") => units.speed = Speed::MilesPerHour,
+			Some("knots") => units.speed = Speed::Knots,
+			Some("beaufort") => units.speed = Speed::Beaufort,
+			Some("mm") => units.precipitation = Precipitation::Millimetres,
+			Some("inches") => units.precipitation = Precipitation::Inches,
+			_ => break,
+		}
+		args.next();
+	}
+	if svg {
+		let canvas: SvgCanvas = render(&font, &options, source_daylight, extras, args)?;
+		output(canvas, to_file)
+	} else {
+		let canvas: RgbImage = render(&font, &options, source_daylight, extras, args)?;
+		output(canvas, to_file)
+	}
+}
+
+/// Daylight shading that is worked out from the API response.
+#[derive(Debug, Clone, Copy)]
+enum SourceDaylight {
+	Location(Twilight),
+	SunTimes,
+}
+
+fn render<C: Canvas>(
+	font: &FontRef<'static>,
+	options: &ChartOptions,
+	source_daylight: Option<SourceDaylight>,
+	extras: Extras,
+	mut args: impl Iterator<Item = String>,
+) -> Result<C, Error> {
+	let mode = args.next().ok_or(Error::MissingArgument("mode"))?;
+	let mut options = options.clone();
+	if source_daylight.is_some() && !matches!(mode.as_str(), "one_call" | "open_meteo") {
+		return Err(Error::MissingArgument("coordinates"));
+	}
+	match mode.as_str() {
+		"json" => {
+			let mode = args.next().ok_or(Error::MissingArgument("chart"))?;
+			from_json(font, &options, &mode, input_reader(args.next())?)
+		}
+		"one_call" => {
+			let mode = args.next().ok_or(Error::MissingArgument("chart"))?;
+			let response = OneCall::from_reader(input_reader(args.next())?)?;
+			let time_zone = match args.next() {
+				Some(time_zone) => parse_time_zone(time_zone)?,
+				None => TimeZone::Fixed(response.timezone_offset),
+			};
+			match source_daylight {
+				Some(SourceDaylight::Location(twilight)) => {
+					options.daylight = Some(response.daylight(twilight));
+				}
+				Some(SourceDaylight::SunTimes) => options.daylight = Some(response.sun_times()),
+				None => {}
+			}
+			from_one_call(font, &options, &mode, &response, time_zone, extras)
+		}
+		"open_meteo" => {
+			let mode = args.next().ok_or(Error::MissingArgument("chart"))?;
+			let mut response = OpenMeteo::from_reader(input_reader(args.next())?)?;
+			if let Some(time_zone) = args.next() {
+				response = response.with_time_zone(parse_time_zone(time_zone)?);
+			}
+			match source_daylight {
+				Some(SourceDaylight::Location(twilight)) => {
+					options.daylight = Some(response.daylight(twilight));
+				}
+				Some(SourceDaylight::SunTimes) => options.daylight = Some(response.sun_times()?),
+				None => {}
+			}
+			from_open_meteo(font, &options, &mode, &response, extras)
+		}
+		_ => from_args(font, &options, &mode, args.collect()),
+	}
+}
+
+fn parse_coordinate(value: String, name: &'static str) -> Result<f64, Error> {
+	value
+		.parse()
+		.map_err(|_| Error::InvalidOption { name, value })
+}
+
+/// Reads a timezone as either an offset from UTC in seconds or a POSIX `TZ` string with daylight saving time rules.
+fn parse_time_zone(time_zone: String) -> Result<TimeZone, Error> {
+	match time_zone.parse() {
+		Ok(offset) => Ok(TimeZone::Fixed(offset)),
+		Err(_) => TimeZone::from_posix(&time_zone),
+	}
+}
 
+/// Writes to `./image.<extension>` or to stdout.
+fn output<C: Canvas>(canvas: C, to_file: bool) -> Result<(), Error> {
 	if to_file {
-		canvas
-			.save_with_format("./image.png", ImageFormat::Png)
-			.expect("Failed to save image");
+		let file = File::create(format!("./image.{}", C::EXTENSION))?;
+		canvas.encode(BufWriter::new(file))
 	} else {
-		let (width, height) = (canvas.width(), canvas.height());
-		let encoder = PngEncoder::new(std::io::stdout());
-		encoder
-			.write_image(&canvas, width, height, ColorType::Rgb8.into())
-			.expect("Failed to write image to stdout");
+		canvas.encode(std::io::stdout().lock())
+	}
+}
+
+/// Reads from the given path, or from stdin if there is none.
+fn input_reader(path: Option<String>) -> Result<Box<dyn Read>, Error> {
+	Ok(match path {
+		Some(path) => Box::new(File::open(path)?),
+		None => Box::new(std::io::stdin()),
+	})
+}
+
+fn from_args<C: Canvas>(
+	font: &FontRef<'static>,
+	options: &ChartOptions,
+	mode: &str,
+	args: Vec<String>,
+) -> Result<C, Error> {
+	match mode {
+		"daily_precipitation" => daily_precipitation::parse_and_create(font, options, args),
+		"daily_temp" => daily_temp::parse_and_create(font, options, args),
+		"daily_temp_range" => daily_temp_range::parse_and_create(font, options, args),
+		"hourly_humidity" => hourly_humidity::parse_and_create(font, options, args),
+		"hourly_clouds" => hourly_clouds::parse_and_create(font, options, args),
+		"hourly_pop" => hourly_pop::parse_and_create(font, options, args),
+		"hourly_precipitation" => hourly_precipitation::parse_and_create(font, options, args),
+		"hourly_pressure" => hourly_pressure::parse_and_create(font, options, args),
+		"hourly_temp" => hourly_temp::parse_and_create(font, options, args),
+		"hourly_uvi" => hourly_uvi::parse_and_create(font, options, args),
+		"hourly_wind" => hourly_wind::parse_and_create(font, options, args),
+		"hourly_composite" => hourly_composite::parse_and_create(font, options, args),
+		"minutely_precipitation" => minutely_precipitation::parse_and_create(font, options, args),
+		"wind_rose" => wind_rose::parse_and_create(font, options, args),
+		x => Err(Error::UnknownMode(x.to_string())),
+	}
+}
+
+fn from_json<C: Canvas>(
+	font: &FontRef<'static>,
+	options: &ChartOptions,
+	mode: &str,
+	reader: impl Read,
+) -> Result<C, Error> {
+	match mode {
+		"daily_precipitation" => daily_precipitation::parse_json_and_create(font, options, reader),
+		"daily_temp" => daily_temp::parse_json_and_create(font, options, reader),
+		"daily_temp_range" => daily_temp_range::parse_json_and_create(font, options, reader),
+		"hourly_humidity" => hourly_humidity::parse_json_and_create(font, options, reader),
+		"hourly_clouds" => hourly_clouds::parse_json_and_create(font, options, reader),
+		"hourly_pop" => hourly_pop::parse_json_and_create(font, options, reader),
+		"hourly_precipitation" => {
+			hourly_precipitation::parse_json_and_create(font, options, reader)
+		}
+		"hourly_pressure" => hourly_pressure::parse_json_and_create(font, options, reader),
+		"hourly_temp" => hourly_temp::parse_json_and_create(font, options, reader),
+		"hourly_uvi" => hourly_uvi::parse_json_and_create(font, options, reader),
+		"hourly_wind" => hourly_wind::parse_json_and_create(font, options, reader),
+		"hourly_composite" => hourly_composite::parse_json_and_create(font, options, reader),
+		"minutely_precipitation" => {
+			minutely_precipitation::parse_json_and_create(font, options, reader)
+		}
+		"wind_rose" => wind_rose::parse_json_and_create(font, options, reader),
+		x => Err(Error::UnknownMode(x.to_string())),
+	}
+}
+
+fn from_one_call<C: Canvas>(
+	font: &FontRef<'static>,
+	options: &ChartOptions,
+	mode: &str,
+	response: &OneCall,
+	time_zone: TimeZone,
+	extras: Extras,
+) -> Result<C, Error> {
+	match mode {
+		"daily_precipitation" => {
+			daily_precipitation::create(font, options, response.daily_precipitation(time_zone))
+		}
+		"daily_temp" => daily_temp::create(font, options, response.daily_temps(time_zone)),
+		"daily_temp_range" => {
+			daily_temp_range::create(font, options, response.daily_temp_range(time_zone))
+		}
+		"hourly_humidity" => {
+			hourly_humidity::create(font, options, response.hourly_humidity(time_zone))
+		}
+		"hourly_clouds" => hourly_clouds::create(font, options, response.hourly_clouds(time_zone)),
+		"hourly_pop" => hourly_pop::create(font, options, response.hourly_pop(time_zone)),
+		"hourly_precipitation" => {
+			hourly_precipitation::create(font, options, response.hourly_precipitation(time_zone))
+		}
+		"hourly_pressure" => {
+			hourly_pressure::create(font, options, response.hourly_pressure(time_zone))
+		}
+		"hourly_temp" => hourly_temp::create(font, options, response.hourly_temps(time_zone)),
+		"hourly_uvi" => hourly_uvi::create(font, options, response.hourly_uvi(time_zone)),
+		"hourly_wind" => hourly_wind::create(font, options, response.hourly_wind(time_zone)),
+		"hourly_composite" => {
+			hourly_composite::create(font, options, response.hourly_composite(time_zone, extras))
+		}
+		"minutely_precipitation" => minutely_precipitation::create(
+			font,
+			options,
+			response.minutely_precipitation(time_zone),
+		),
+		"wind_rose" => wind_rose::create(font, options, response.hourly_wind(time_zone)),
+		x => Err(Error::UnknownMode(x.to_string())),
+	}
+}
+
+fn from_open_meteo<C: Canvas>(
+	font: &FontRef<'static>,
+	options: &ChartOptions,
+	mode: &str,
+	response: &OpenMeteo,
+	extras: Extras,
+) -> Result<C, Error> {
+	match mode {
+		"daily_precipitation" => {
+			daily_precipitation::create(font, options, response.daily_precipitation()?)
+		}
+		"daily_temp" => daily_temp::create(font, options, response.daily_temps()?),
+		"daily_temp_range" => daily_temp_range::create(font, options, response.daily_temp_range()?),
+		"hourly_humidity" => hourly_humidity::create(font, options, response.hourly_humidity()?),
+		"hourly_clouds" => hourly_clouds::create(font, options, response.hourly_clouds()?),
+		"hourly_pop" => hourly_pop::create(font, options, response.hourly_pop()?),
+		"hourly_precipitation" => {
+			hourly_precipitation::create(font, options, response.hourly_precipitation()?)
+		}
+		"hourly_pressure" => hourly_pressure::create(font, options, response.hourly_pressure()?),
+		"hourly_temp" => hourly_temp::create(font, options, response.hourly_temps()?),
+		"hourly_uvi" => hourly_uvi::create(font, options, response.hourly_uvi()?),
+		"hourly_wind" => hourly_wind::create(font, options, response.hourly_wind()?),
+		"hourly_composite" => {
+			hourly_composite::create(font, options, response.hourly_composite(extras)?)
+		}
+		"minutely_precipitation" => {
+			minutely_precipitation::create(font, options, response.minutely_precipitation()?)
+		}
+		"wind_rose" => wind_rose::create(font, options, response.hourly_wind()?),
+		x => Err(Error::UnknownMode(x.to_string())),
 	}
 }
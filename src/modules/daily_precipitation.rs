@@ -0,0 +1,262 @@
+use std::io::Read;
+
+use ab_glyph::FontRef;
+use serde::Deserialize;
+
+use crate::{
+	canvas::Canvas,
+	common_types::Range,
+	drawing::{MarkIntervals, Padding, Spacing},
+	error::Error,
+	from_args::{data_from_args, parse_arg, parse_optional_arg, FromArgs, InvalidArgument},
+	from_json::data_from_json,
+	generic_graph::{
+		padding_with_caption, AxisGridLabels, Chart, ChartOptions, HorizontalLines, NoData,
+		SecondaryLabels, SolidBars,
+	},
+	text_box::TextSegment,
+	util::next_multiple_of_hundredths,
+};
+
+const PADDING: Padding = Padding {
+	above: 7,
+	below: 19,
+	left: 21,
+	// Room for the probability labels
+	right: 27,
+};
+const HORIZONTAL_SPACING: u32 = 25;
+const FONT_SCALE: ab_glyph::PxScale = ab_glyph::PxScale { x: 14.0, y: 14.0 };
+const MAX_CHANCE: i32 = 10_000;
+
+/// Makes a graph showing daily rain and snow, with the chance of precipitation.
+///
+/// Arguments are in the format day, rain, snow, chance, repeat. Amounts are in mm * 100 and the chance is in percent, and each can be `-` when it isn't known.
+pub fn parse_and_create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	args: Vec<String>,
+) -> Result<C, Error> {
+	let data = data_from_args(args)?;
+	create(font, options, data)
+}
+
+/// Reads a JSON array of objects with `day`, `rain`, `snow` and `chance` fields. Amounts are in mm * 100 and the chance is in percent, and each may be `null` where it is missing.
+pub fn parse_json_and_create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	reader: impl Read,
+) -> Result<C, Error> {
+	let data = data_from_json(reader)?;
+	create(font, options, data)
+}
+
+/// Rain and snow totals as bars, with a line across each day's bar at its chance of precipitation, read against the axis on the right.
+pub fn create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	data: Vec<DailyPrecipitation>,
+) -> Result<C, Error> {
+	if data.is_empty() {
+		return Err(Error::NoData);
+	}
+	let unit = options.units.precipitation;
+	let axis = unit.daily_axis();
+	let spacing = Spacing {
+		horizontal: HORIZONTAL_SPACING,
+		vertical: axis.spacing,
+	};
+	// At least one label tall, so there is room to show the chance on a dry week
+	let max_chart_precipitation = next_multiple_of_hundredths(
+		data.iter()
+			.flat_map(|day| [day.rain, day.snow])
+			.flatten()
+			.map(|amount| unit.convert(amount as i32))
+			.max()
+			.unwrap_or(0)
+			.max(1),
+		axis.rounding,
+	);
+	let steps = (max_chart_precipitation / axis.label as i32) as u32;
+
+	let unit_text = format!(" ({}) and ", unit.symbol());
+	let caption = options.caption(
+		&[
+			TextSegment::new("Rain", options.theme.rain),
+			TextSegment::plain(" and ", &options.theme),
+			TextSegment::new("snow", options.theme.snow),
+			TextSegment::plain(&unit_text, &options.theme),
+			TextSegment::new("chance", options.theme.pop_overlay),
+			TextSegment::plain(" (%)", &options.theme),
+		],
+		font,
+		data.len(),
+		spacing,
+	);
+	// Bars take up a whole column each, so there is one more column than there are points.
+	let mut chart = Chart::new(
+		data.len() + 1,
+		max_chart_precipitation as u32,
+		spacing,
+		padding_with_caption(PADDING, caption.as_ref()),
+		options,
+	)?;
+
+	chart.draw(AxisGridLabels {
+		vertical_intervals: MarkIntervals::new(axis.line, axis.label)?,
+		horizontal_intervals: MarkIntervals::new(1, 1)?,
+		vertical_label_range: Range::new(0, max_chart_precipitation)?,
+		horizontal_labels: data.iter().map(|day| day.day),
+		horizontal_times: None,
+		horizontal_labels_centered: true,
+		font: font.clone(),
+		font_scale: FONT_SCALE,
+	});
+	chart.draw(SecondaryLabels {
+		range: Range::new(0, MAX_CHANCE)?,
+		steps,
+		colour: options.theme.pop_overlay,
+		font: font.clone(),
+		font_scale: FONT_SCALE,
+	});
+	if options.no_data {
+		chart.draw(NoData {
+			missing: data
+				.iter()
+				.map(|day| day.rain.is_none() || day.snow.is_none()),
+			bars: true,
+		});
+	}
+	chart.draw(SolidBars {
+		colour: options.theme.rain,
+		data: data
+			.iter()
+			.map(|day| day.rain.map(|rain| unit.convert(rain as i32))),
+		baseline: 0,
+		max: max_chart_precipitation,
+	});
+	chart.draw(SolidBars {
+		colour: options.theme.snow,
+		data: data
+			.iter()
+			.map(|day| day.snow.map(|snow| unit.convert(snow as i32))),
+		baseline: 0,
+		max: max_chart_precipitation,
+	});
+	// The chance is drawn in the precipitation's hundredths, so it shares the grid.
+	chart.draw(HorizontalLines {
+		colour: options.theme.pop_overlay,
+		data: data.iter().map(|day| {
+			day.chance
+				.map(|chance| chance as i32 * max_chart_precipitation / MAX_CHANCE)
+		}),
+		baseline: 0,
+		max: max_chart_precipitation,
+	});
+	if let Some(caption) = caption {
+		chart.draw(caption);
+	}
+	Ok(chart.into_canvas())
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(from = "DailyPrecipitationInput")]
+pub struct DailyPrecipitation {
+	/// Day of the month
+	pub(crate) day: u8,
+	/// Amount of rain in mm * 100
+	pub(crate) rain: Option<u32>,
+	/// Amount of snow in mm * 100
+	pub(crate) snow: Option<u32>,
+	/// Probability of precipitation * 100
+	pub(crate) chance: Option<u32>,
+}
+
+impl DailyPrecipitation {
+	/// Takes amounts in mm, and the probability of precipitation as a fraction (0-1), with `None` for any that are missing.
+	pub fn new(
+		day: u8,
+		rain: impl Into<Option<f32>>,
+		snow: impl Into<Option<f32>>,
+		pop: impl Into<Option<f32>>,
+	) -> Self {
+		let to_hundredths = |amount: f32| (amount * 100.0).round() as u32;
+		Self {
+			day,
+			rain: rain.into().map(to_hundredths),
+			snow: snow.into().map(to_hundredths),
+			chance: pop.into().map(|pop| (pop * 10_000.0).round() as u32),
+		}
+	}
+}
+
+impl FromArgs<4> for DailyPrecipitation {
+	fn from_args([day, rain, snow, chance]: [String; 4]) -> Result<Self, InvalidArgument> {
+		let day = parse_arg(day, "day")?;
+		let rain = parse_optional_arg(rain, "rain")?;
+		let snow = parse_optional_arg(snow, "snow")?;
+		let chance = parse_optional_arg(chance, "probability of precipitation")?;
+		Ok(DailyPrecipitationInput {
+			day,
+			rain,
+			snow,
+			chance,
+		}
+		.into())
+	}
+}
+
+/// The shape of [`DailyPrecipitation`] in input, with the chance in whole percent.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DailyPrecipitationInput {
+	/// Day of the month
+	day: u8,
+	/// Amount of rain in mm * 100
+	rain: Option<u32>,
+	/// Amount of snow in mm * 100
+	snow: Option<u32>,
+	/// Probability of precipitation in percent
+	chance: Option<u32>,
+}
+
+impl From<DailyPrecipitationInput> for DailyPrecipitation {
+	fn from(input: DailyPrecipitationInput) -> Self {
+		let DailyPrecipitationInput {
+			day,
+			rain,
+			snow,
+			chance,
+		} = input;
+		Self {
+			day,
+			rain,
+			snow,
+			chance: chance.map(|chance| chance.min(100) * 100),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn chance_in_percent() {
+		let from_json: Vec<DailyPrecipitation> = data_from_json(
+			r#"[{"day": 30, "rain": 1250, "snow": 0, "chance": 85}, {"day": 31, "rain": 0, "snow": 40, "chance": 120}]"#
+				.as_bytes(),
+		)
+		.unwrap();
+		let from_args: Vec<DailyPrecipitation> =
+			data_from_args(["30", "1250", "0", "85"].map(String::from).to_vec()).unwrap();
+		let from_new = DailyPrecipitation::new(30, 12.5, 0.0, 0.85);
+		for day in [from_json[0], from_args[0], from_new] {
+			assert_eq!(
+				(day.rain, day.snow, day.chance),
+				(Some(1250), Some(0), Some(8500))
+			);
+		}
+		assert_eq!(from_json[1].chance, Some(10_000));
+	}
+}
@@ -1,16 +1,23 @@
+use std::io::Read;
+
 use ab_glyph::FontRef;
-use image::RgbImage;
 use itertools::Itertools;
+use serde::Deserialize;
 
 use crate::{
-	colours,
+	canvas::Canvas,
 	common_types::Range,
-	drawing::{
-		draw_graph_lines, draw_outer_lines, fill_canvas, horizontal_lines_and_labels,
-		vertical_lines_and_labels, MarkIntervals, Padding, Spacing,
+	drawing::{LineStyle, MarkIntervals, Padding, Spacing, Stroke},
+	error::Error,
+	from_args::{data_from_args, parse_arg, parse_optional_arg, FromArgs, InvalidArgument},
+	from_json::data_from_json,
+	generic_graph::{
+		padding_with_caption, AxisGridLabels, Chart, ChartOptions, Icons, Line, NoData,
+		ICON_ROW_HEIGHT,
 	},
-	from_args::{data_from_args, FromArgs},
-	util::previous_and_next_multiple,
+	icons::Condition,
+	text_box::TextSegment,
+	util::previous_and_next_multiple_of_hundredths,
 };
 const PADDING: Padding = Padding {
 	above: 7,
@@ -18,107 +25,184 @@ const PADDING: Padding = Padding {
 	left: 21,
 	right: 9,
 };
-const SPACING: Spacing = Spacing {
-	horizontal: 25,
-	vertical: 3,
+const HORIZONTAL_SPACING: u32 = 25;
+/// Used when series are told apart by line style
+const STROKE: Stroke = Stroke {
+	width: 1.5,
+	anti_aliased: true,
+	style: LineStyle::Solid,
 };
 const FONT_SCALE: ab_glyph::PxScale = ab_glyph::PxScale { x: 14.0, y: 14.0 };
 
 /// Makes a graph showing daily min and max temp.
 ///
-/// Arguments are in the format day, temp min, temp max, repeat. Temperatures are in centidegrees Celsius.
+/// Arguments are in the format day, temp min, temp max, repeat. Temperatures are in centidegrees Celsius, and can be `-` when they aren't known.
 ///
 /// Example input values: `28 -555 -333 29 -222 111 30 -333 222 1 0 444 2 222 555 3 111 666 4 222 555 5 555 2222`.
-pub fn parse_and_create(font: &FontRef, args: Vec<String>) -> RgbImage {
-	let data = data_from_args(args);
-	create(font, data)
+pub fn parse_and_create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	args: Vec<String>,
+) -> Result<C, Error> {
+	let data = data_from_args(args)?;
+	create(font, options, data)
+}
+
+/// Reads a JSON array of objects with `day`, `temp_min` and `temp_max` fields, and optionally a `condition` to show as an icon. Temperatures are in centidegrees Celsius, and may be `null` where they are missing.
+pub fn parse_json_and_create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	reader: impl Read,
+) -> Result<C, Error> {
+	let data = data_from_json(reader)?;
+	create(font, options, data)
 }
 
-pub fn create(font: &FontRef, data: Vec<DailyTemps>) -> RgbImage {
+pub fn create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	data: Vec<DailyTemps>,
+) -> Result<C, Error> {
+	if data.is_empty() {
+		return Err(Error::NoData);
+	}
+	let unit = options.units.temperature;
+	let axis = unit.axis();
+	let spacing = Spacing {
+		horizontal: HORIZONTAL_SPACING,
+		vertical: axis.spacing,
+	};
 	let temp_range = data
 		.iter()
 		.flat_map(|day| [day.temp_min, day.temp_max])
+		.flatten()
+		.map(|temp| unit.convert(temp))
 		.minmax()
 		.into_option()
 		.unwrap_or((0, 0));
-	let chart_temp_range = previous_and_next_multiple(Range::new(temp_range.0, temp_range.1), 4);
-	let width = SPACING.horizontal * (data.len() - 1) as u32 + PADDING.horizontal();
-	let height = chart_temp_range.len() as u32 * SPACING.vertical / 100 + PADDING.vertical();
-	let mut canvas = RgbImage::new(width, height);
-	fill_canvas(&mut canvas, colours::BACKGROUND);
-	draw_outer_lines(&mut canvas, PADDING);
-	vertical_lines_and_labels(
-		&mut canvas,
-		data.iter().map(|day| day.day),
-		MarkIntervals::new(1, 1),
-		font,
-		FONT_SCALE,
-		PADDING,
-		SPACING.horizontal,
-		false,
-	);
-	horizontal_lines_and_labels(
-		&mut canvas,
-		chart_temp_range,
-		MarkIntervals::new(2, 4),
+	let chart_temp_range = previous_and_next_multiple_of_hundredths(
+		Range::new(temp_range.0, temp_range.1)?,
+		axis.rounding,
+	)?;
+
+	let unit_text = format!(" temperature ({})", unit.symbol());
+	let caption = options.caption(
+		&[
+			TextSegment::new("Minimum", options.theme.temp_min),
+			TextSegment::plain(" and ", &options.theme),
+			TextSegment::new("maximum", options.theme.temp_max),
+			TextSegment::plain(&unit_text, &options.theme),
+		],
 		font,
-		FONT_SCALE,
-		PADDING,
-		SPACING.vertical,
-	);
-	draw_graph_lines(
-		&mut canvas,
-		data.iter().map(|daily| daily.temp_min),
-		colours::TEMP_MIN,
-		chart_temp_range.end(),
-		PADDING,
-		SPACING,
+		data.len(),
+		spacing,
 	);
-	draw_graph_lines(
-		&mut canvas,
-		data.iter().map(|daily| daily.temp_max),
-		colours::TEMP_MAX,
-		chart_temp_range.end(),
-		PADDING,
-		SPACING,
-	);
-	canvas
+	let has_conditions = data.iter().any(|day| day.condition.is_some());
+	let padding = Padding {
+		above: PADDING.above + if has_conditions { ICON_ROW_HEIGHT } else { 0 },
+		..PADDING
+	};
+	let mut chart = Chart::new(
+		data.len(),
+		chart_temp_range.len() as u32,
+		spacing,
+		padding_with_caption(padding, caption.as_ref()),
+		options,
+	)?;
+
+	chart.draw(AxisGridLabels {
+		vertical_intervals: MarkIntervals::new(axis.line, axis.label)?,
+		horizontal_intervals: MarkIntervals::new(1, 1)?,
+		vertical_label_range: chart_temp_range,
+		horizontal_labels: data.iter().map(|day| day.day),
+		horizontal_times: None,
+		horizontal_labels_centered: false,
+		font: font.clone(),
+		font_scale: FONT_SCALE,
+	});
+	if options.no_data {
+		chart.draw(NoData {
+			missing: data
+				.iter()
+				.map(|day| day.temp_min.is_none() || day.temp_max.is_none()),
+			bars: false,
+		});
+	}
+	if has_conditions {
+		chart.draw(Icons {
+			data: data.iter().map(|day| day.condition),
+		});
+	}
+	chart.draw(Line {
+		stroke: options.stroke(STROKE.with_style(LineStyle::Dashed)),
+		colour: options.theme.temp_min,
+		data: data
+			.iter()
+			.map(|daily| daily.temp_min.map(|temp| unit.convert(temp))),
+		max: chart_temp_range.end(),
+	});
+	chart.draw(Line {
+		stroke: options.stroke(STROKE),
+		colour: options.theme.temp_max,
+		data: data
+			.iter()
+			.map(|daily| daily.temp_max.map(|temp| unit.convert(temp))),
+		max: chart_temp_range.end(),
+	});
+	if let Some(caption) = caption {
+		chart.draw(caption);
+	}
+	Ok(chart.into_canvas())
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct DailyTemps {
 	/// Day of the month
-	day: u8,
+	pub(crate) day: u8,
 	/// Minimum temperature in centidegrees Celsius
-	temp_min: i32,
+	pub(crate) temp_min: Option<i32>,
 	/// Maximum temperature in centidegrees Celsius
-	temp_max: i32,
+	pub(crate) temp_max: Option<i32>,
+	/// What the weather is doing, shown as an icon above the chart
+	#[serde(default)]
+	pub(crate) condition: Option<Condition>,
 }
 
 impl DailyTemps {
-	pub fn new(day: u8, temp_min: f32, temp_max: f32) -> Self {
-		let [temp_min, temp_max] = [temp_min, temp_max].map(|temp| (temp * 100.0).round() as i32);
+	/// Takes temperatures in °C, and `None` for any that are missing.
+	pub fn new(
+		day: u8,
+		temp_min: impl Into<Option<f32>>,
+		temp_max: impl Into<Option<f32>>,
+	) -> Self {
+		let centidegrees = |temp: f32| (temp * 100.0).round() as i32;
 		Self {
 			day,
-			temp_min,
-			temp_max,
+			temp_min: temp_min.into().map(centidegrees),
+			temp_max: temp_max.into().map(centidegrees),
+			condition: None,
+		}
+	}
+	/// Sets what the weather is doing, to show as an icon.
+	pub fn with_condition(self, condition: impl Into<Option<Condition>>) -> Self {
+		Self {
+			condition: condition.into(),
+			..self
 		}
 	}
 }
 
 impl FromArgs<3> for DailyTemps {
-	fn from_args([day, temp_min, temp_max]: [String; 3]) -> Self {
-		let day = day.parse().expect("Could not parse a day argument");
-		let temp_min = temp_min
-			.parse()
-			.expect("Could not parse a minimum temperature argument");
-		let temp_max = temp_max
-			.parse()
-			.expect("Could not parse a maximum temperature argument");
-		Self {
+	fn from_args([day, temp_min, temp_max]: [String; 3]) -> Result<Self, InvalidArgument> {
+		let day = parse_arg(day, "day")?;
+		let temp_min = parse_optional_arg(temp_min, "minimum temperature")?;
+		let temp_max = parse_optional_arg(temp_max, "maximum temperature")?;
+		Ok(Self {
 			day,
 			temp_min,
 			temp_max,
-		}
+			condition: None,
+		})
 	}
 }
@@ -0,0 +1,318 @@
+use std::io::Read;
+
+use ab_glyph::FontRef;
+use itertools::Itertools;
+use serde::Deserialize;
+
+use crate::{
+	canvas::Canvas,
+	common_types::{GradientPoint, MultiPointGradient, Range},
+	drawing::{MarkIntervals, Padding, Spacing},
+	error::Error,
+	from_args::{data_from_args, parse_arg, parse_optional_arg, FromArgs, InvalidArgument},
+	from_json::data_from_json,
+	generic_graph::{
+		padding_with_caption, AxisGridLabels, Chart, ChartOptions, RangeBars, Ticks, Whiskers,
+	},
+	text_box::TextSegment,
+	util::previous_and_next_multiple_of_hundredths,
+};
+
+const PADDING: Padding = Padding {
+	above: 7,
+	below: 19,
+	left: 21,
+	right: 3,
+};
+const HORIZONTAL_SPACING: u32 = 25;
+const FONT_SCALE: ab_glyph::PxScale = ab_glyph::PxScale { x: 14.0, y: 14.0 };
+
+/// Makes a graph showing each day's temperature range as a bar.
+///
+/// Arguments are in the format day, temp min, temp max, apparent temp min, apparent temp max, morning temp, day temp, evening temp, night temp, repeat. Temperatures are in centidegrees Celsius, and can be `-` when they aren't known.
+pub fn parse_and_create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	args: Vec<String>,
+) -> Result<C, Error> {
+	let data = data_from_args(args)?;
+	create(font, options, data)
+}
+
+/// Reads a JSON array of objects with `day`, `temp_min` and `temp_max` fields, and optionally `feels_like_min`, `feels_like_max`, `morning`, `daytime`, `evening` and `night`. Temperatures are in centidegrees Celsius.
+pub fn parse_json_and_create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	reader: impl Read,
+) -> Result<C, Error> {
+	let data = data_from_json(reader)?;
+	create(font, options, data)
+}
+
+/// A bar from the minimum to the maximum temperature for each day, coloured from cold at the bottom of the chart to warm at the top. Whiskers reach out to the apparent minimum and maximum where they are known, and ticks across the bar mark the morning, day, evening and night temperatures from left to right.
+pub fn create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	data: Vec<DailyTempRange>,
+) -> Result<C, Error> {
+	if data.is_empty() {
+		return Err(Error::NoData);
+	}
+	let unit = options.units.temperature;
+	let axis = unit.axis();
+	let spacing = Spacing {
+		horizontal: HORIZONTAL_SPACING,
+		vertical: axis.spacing,
+	};
+	let temp_range = data
+		.iter()
+		.flat_map(|day| {
+			[day.temp_min, day.temp_max]
+				.into_iter()
+				.flatten()
+				.chain(day.feels_like().into_iter().flatten())
+				.chain(day.parts().into_iter().flatten())
+		})
+		.map(|temp| unit.convert(temp))
+		.minmax()
+		.into_option()
+		.unwrap_or((0, 0));
+	// At least one step tall, so a week of one temperature still has a gradient
+	let chart_temp_range = previous_and_next_multiple_of_hundredths(
+		Range::new(temp_range.0, temp_range.1.max(temp_range.0 + 1))?,
+		axis.rounding,
+	)?;
+	let has_feels_like = data.iter().any(|day| day.feels_like().is_some());
+	let has_parts = data
+		.iter()
+		.any(|day| day.parts().iter().any(Option::is_some));
+
+	let mut caption_segments = vec![
+		TextSegment::new("Minimum", options.theme.temp_min),
+		TextSegment::plain(" to ", &options.theme),
+		TextSegment::new("maximum", options.theme.temp_max),
+		TextSegment::plain(" temperature", &options.theme),
+	];
+	if has_feels_like {
+		caption_segments.extend([
+			TextSegment::plain(", ", &options.theme),
+			TextSegment::new(
+				"apparent minimum and maximum",
+				options.theme.temp_feels_like,
+			),
+		]);
+	}
+	if has_parts {
+		caption_segments.push(TextSegment::plain(
+			" and morning, day, evening and night temperatures",
+			&options.theme,
+		));
+	}
+	let unit_text = format!(" ({})", unit.symbol());
+	caption_segments.push(TextSegment::plain(&unit_text, &options.theme));
+	let caption = options.caption(&caption_segments, font, data.len(), spacing);
+	// Bars take up a whole column each, so there is one more column than there are points.
+	let mut chart = Chart::new(
+		data.len() + 1,
+		chart_temp_range.len() as u32,
+		spacing,
+		padding_with_caption(PADDING, caption.as_ref()),
+		options,
+	)?;
+
+	chart.draw(AxisGridLabels {
+		vertical_intervals: MarkIntervals::new(axis.line, axis.label)?,
+		horizontal_intervals: MarkIntervals::new(1, 1)?,
+		vertical_label_range: chart_temp_range,
+		horizontal_labels: data.iter().map(|day| day.day),
+		horizontal_times: None,
+		horizontal_labels_centered: true,
+		font: font.clone(),
+		font_scale: FONT_SCALE,
+	});
+	let converted_range = |[start, end]: [i32; 2]| {
+		let [start, end] = [start, end].map(|temp| unit.convert(temp));
+		Range::new(start.min(end), start.max(end)).ok()
+	};
+	chart.draw(Whiskers {
+		colour: options.theme.temp_feels_like,
+		data: data
+			.iter()
+			.map(|day| day.feels_like().and_then(converted_range)),
+		max: chart_temp_range.end(),
+	});
+	let plot_height = chart_temp_range.len() as u32 * spacing.vertical / 100;
+	chart.draw(RangeBars {
+		gradient: MultiPointGradient::new(vec![
+			GradientPoint::from_rgb(PADDING.below, options.theme.temp_min.0),
+			GradientPoint::from_rgb(PADDING.below + plot_height, options.theme.temp_max.0),
+		])?,
+		data: data
+			.iter()
+			.map(|day| day.temp_range().and_then(converted_range)),
+		max: chart_temp_range.end(),
+	});
+	chart.draw(Ticks {
+		colour: options.theme.text,
+		data: data.iter().map(|day| {
+			day.parts()
+				.map(|temp| temp.map(|temp| unit.convert(temp)))
+				.to_vec()
+		}),
+		max: chart_temp_range.end(),
+	});
+	if let Some(caption) = caption {
+		chart.draw(caption);
+	}
+	Ok(chart.into_canvas())
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DailyTempRange {
+	/// Day of the month
+	pub(crate) day: u8,
+	/// Minimum temperature in centidegrees Celsius
+	pub(crate) temp_min: Option<i32>,
+	/// Maximum temperature in centidegrees Celsius
+	pub(crate) temp_max: Option<i32>,
+	/// Apparent minimum temperature in centidegrees Celsius
+	#[serde(default)]
+	pub(crate) feels_like_min: Option<i32>,
+	/// Apparent maximum temperature in centidegrees Celsius
+	#[serde(default)]
+	pub(crate) feels_like_max: Option<i32>,
+	/// Morning temperature in centidegrees Celsius
+	#[serde(default)]
+	pub(crate) morning: Option<i32>,
+	/// Daytime temperature in centidegrees Celsius
+	#[serde(default)]
+	pub(crate) daytime: Option<i32>,
+	/// Evening temperature in centidegrees Celsius
+	#[serde(default)]
+	pub(crate) evening: Option<i32>,
+	/// Night temperature in centidegrees Celsius
+	#[serde(default)]
+	pub(crate) night: Option<i32>,
+}
+
+impl DailyTempRange {
+	/// Takes temperatures in °C, and `None` for either where it is missing.
+	pub fn new(
+		day: u8,
+		temp_min: impl Into<Option<f32>>,
+		temp_max: impl Into<Option<f32>>,
+	) -> Self {
+		Self {
+			day,
+			temp_min: temp_min.into().map(centidegrees),
+			temp_max: temp_max.into().map(centidegrees),
+			feels_like_min: None,
+			feels_like_max: None,
+			morning: None,
+			daytime: None,
+			evening: None,
+			night: None,
+		}
+	}
+	/// Sets the apparent minimum and maximum temperatures in °C.
+	pub fn with_feels_like(self, min: f32, max: f32) -> Self {
+		Self {
+			feels_like_min: Some(centidegrees(min)),
+			feels_like_max: Some(centidegrees(max)),
+			..self
+		}
+	}
+	/// Sets the morning, day, evening and night temperatures in °C.
+	pub fn with_parts(self, [morning, daytime, evening, night]: [f32; 4]) -> Self {
+		Self {
+			morning: Some(centidegrees(morning)),
+			daytime: Some(centidegrees(daytime)),
+			evening: Some(centidegrees(evening)),
+			night: Some(centidegrees(night)),
+			..self
+		}
+	}
+	/// The minimum and maximum, when both are known.
+	fn temp_range(&self) -> Option<[i32; 2]> {
+		Some([self.temp_min?, self.temp_max?])
+	}
+	/// The apparent minimum and maximum, when both are known.
+	fn feels_like(&self) -> Option<[i32; 2]> {
+		Some([self.feels_like_min?, self.feels_like_max?])
+	}
+	/// The morning, day, evening and night temperatures, in the order they are drawn.
+	fn parts(&self) -> [Option<i32>; 4] {
+		[self.morning, self.daytime, self.evening, self.night]
+	}
+}
+
+fn centidegrees(temp: f32) -> i32 {
+	(temp * 100.0).round() as i32
+}
+
+impl FromArgs<9> for DailyTempRange {
+	fn from_args(
+		[day, temp_min, temp_max, feels_like_min, feels_like_max, morning, daytime, evening, night]: [String; 9],
+	) -> Result<Self, InvalidArgument> {
+		Ok(Self {
+			day: parse_arg(day, "day")?,
+			temp_min: parse_optional_arg(temp_min, "minimum temperature")?,
+			temp_max: parse_optional_arg(temp_max, "maximum temperature")?,
+			feels_like_min: parse_optional_arg(feels_like_min, "apparent minimum temperature")?,
+			feels_like_max: parse_optional_arg(feels_like_max, "apparent maximum temperature")?,
+			morning: parse_optional_arg(morning, "morning temperature")?,
+			daytime: parse_optional_arg(daytime, "day temperature")?,
+			evening: parse_optional_arg(evening, "evening temperature")?,
+			night: parse_optional_arg(night, "night temperature")?,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use image::RgbImage;
+
+	use super::*;
+
+	#[test]
+	fn optional_temperatures() {
+		let from_args: Vec<DailyTempRange> = data_from_args(
+			[
+				"1", "-250", "400", "-700", "300", "-100", "350", "200", "-200", "2", "0", "500",
+				"-", "-", "-", "-", "-", "-",
+			]
+			.map(String::from)
+			.to_vec(),
+		)
+		.unwrap();
+		let from_new = DailyTempRange::new(1, -2.5, 4.0)
+			.with_feels_like(-7.0, 3.0)
+			.with_parts([-1.0, 3.5, 2.0, -2.0]);
+		assert_eq!(from_args[0].feels_like(), Some([-700, 300]));
+		assert_eq!(from_args[0].feels_like(), from_new.feels_like());
+		assert_eq!(from_args[0].parts(), from_new.parts());
+		assert_eq!(from_args[1].feels_like(), None);
+		assert_eq!(from_args[1].parts(), [None; 4]);
+	}
+
+	#[test]
+	fn no_data() {
+		let font_data: &[u8] = include_bytes!("../../RobotoCondensed-Regular.ttf");
+		let font = FontRef::try_from_slice(font_data).unwrap();
+		assert!(matches!(
+			create::<RgbImage>(&font, &ChartOptions::default(), Vec::new()),
+			Err(Error::NoData)
+		));
+	}
+
+	#[test]
+	fn constant_temperature() {
+		let font_data: &[u8] = include_bytes!("../../RobotoCondensed-Regular.ttf");
+		let font = FontRef::try_from_slice(font_data).unwrap();
+		let data = (1..=7)
+			.map(|day| DailyTempRange::new(day, 0.0, 0.0))
+			.collect();
+		assert!(create::<RgbImage>(&font, &ChartOptions::default(), data).is_ok());
+	}
+}
@@ -0,0 +1,337 @@
+use std::io::Read;
+
+use ab_glyph::FontRef;
+use serde::Deserialize;
+
+use crate::{
+	canvas::Canvas,
+	common_types::Range,
+	drawing::{LineStyle, MarkIntervals, Padding, Spacing, Stroke},
+	error::Error,
+	from_args::{data_from_args, parse_arg, parse_optional_arg, FromArgs, InvalidArgument},
+	from_json::data_from_json,
+	generic_graph::{
+		padding_with_caption, AxisGridLabels, Chart, ChartOptions, Line, NightShading, NoData,
+		SecondaryLabels, ShadedRows, SolidBars,
+	},
+	text_box::TextSegment,
+	time::ZonedTime,
+	util::secondary_range,
+};
+
+const PADDING: Padding = Padding {
+	above: 7,
+	below: 19,
+	left: 21,
+	right: 3,
+};
+/// Room for the visibility labels
+const PADDING_RIGHT_WITH_VISIBILITY: u32 = 21;
+/// Room for the rows of cloud layers
+const LAYERS_HEIGHT: u32 = 3 * (LAYER_ROW_HEIGHT + 1) + 2;
+const LAYER_ROW_HEIGHT: u32 = 4;
+const SPACING: Spacing = Spacing {
+	horizontal: 8,
+	vertical: 1,
+};
+/// Used when series are told apart by line style
+const STROKE: Stroke = Stroke {
+	width: 1.5,
+	anti_aliased: true,
+	style: LineStyle::Solid,
+};
+const FONT_SCALE: ab_glyph::PxScale = ab_glyph::PxScale { x: 14.0, y: 14.0 };
+const MAX_COVER: i32 = 10_000;
+/// Cover is labelled every 20 %, and visibility is labelled on the same lines.
+const LABEL_INTERVAL: usize = 2000;
+
+pub fn parse_and_create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	args: Vec<String>,
+) -> Result<C, Error> {
+	let data = data_from_args(args)?;
+	create(font, options, data)
+}
+
+/// Reads a JSON array of objects with `hour` and `cover` fields, and optionally `low`, `mid` and `high` layers and `visibility`. Cover is in percent and visibility is in metres, and the total cover may be `null` where it is missing.
+pub fn parse_json_and_create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	reader: impl Read,
+) -> Result<C, Error> {
+	let data = data_from_json(reader)?;
+	create(font, options, data)
+}
+
+/// Total cloud cover as bars, with visibility against the axis on the right where there is any. When every hour has its layers, they are shaded in rows above the chart, with high cloud on top.
+pub fn create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	data: Vec<HourlyClouds>,
+) -> Result<C, Error> {
+	if data.is_empty() {
+		return Err(Error::NoData);
+	}
+	let layers: Option<Vec<[u32; 3]>> = data.iter().map(|hour| hour.layers).collect();
+	let max_visibility = data.iter().filter_map(|hour| hour.visibility).max();
+	let steps = (MAX_COVER / LABEL_INTERVAL as i32) as u32;
+	let chart_visibility_range = max_visibility
+		.map(|max| secondary_range(Range::new(0, max as i32 / 10)?, 100, steps))
+		.transpose()?;
+	// Visibility is drawn in cover's hundredths, so they share the grid.
+	let to_cover_scale =
+		|visibility: u32, range: Range<i32>| (visibility as i32 / 10) * MAX_COVER / range.len();
+
+	let mut caption_segments = vec![
+		TextSegment::new("Cloud cover", options.theme.clouds),
+		TextSegment::plain(" (%)", &options.theme),
+	];
+	if layers.is_some() {
+		caption_segments.push(TextSegment::plain(
+			", in high, middle and low layers above",
+			&options.theme,
+		));
+	}
+	if chart_visibility_range.is_some() {
+		caption_segments.extend([
+			TextSegment::plain(" and ", &options.theme),
+			TextSegment::new("visibility", options.theme.visibility),
+			TextSegment::plain(" (km)", &options.theme),
+		]);
+	}
+	let caption = options.caption(&caption_segments, font, data.len(), SPACING);
+	let padding = Padding {
+		above: PADDING.above + if layers.is_some() { LAYERS_HEIGHT } else { 0 },
+		right: if chart_visibility_range.is_some() {
+			PADDING_RIGHT_WITH_VISIBILITY
+		} else {
+			PADDING.right
+		},
+		..PADDING
+	};
+	// Bars take up a whole column each, so there is one more column than there are points.
+	let mut chart = Chart::new(
+		data.len() + 1,
+		MAX_COVER as u32,
+		SPACING,
+		padding_with_caption(padding, caption.as_ref()),
+		options,
+	)?;
+
+	let times: Option<Vec<ZonedTime>> = data.iter().map(|hour| hour.time).collect();
+	if let (Some(daylight), Some(times)) = (&options.daylight, &times) {
+		chart.draw(NightShading { daylight, times });
+	}
+	chart.draw(AxisGridLabels {
+		vertical_intervals: MarkIntervals::new(LABEL_INTERVAL / 2, LABEL_INTERVAL)?,
+		horizontal_intervals: MarkIntervals::new(1, 2)?,
+		vertical_label_range: Range::new(0, MAX_COVER)?,
+		horizontal_labels: data.iter().map(|hour| hour.hour),
+		horizontal_times: times,
+		horizontal_labels_centered: false,
+		font: font.clone(),
+		font_scale: FONT_SCALE,
+	});
+	if let Some(layers) = layers {
+		chart.draw(ShadedRows {
+			rows: (0..3)
+				.rev()
+				.map(|layer| layers.iter().map(|hour| Some(hour[layer] as i32)).collect())
+				.collect(),
+			colour: options.theme.clouds,
+			max: MAX_COVER,
+			row_height: LAYER_ROW_HEIGHT,
+		});
+	}
+	if options.no_data {
+		chart.draw(NoData {
+			missing: data.iter().map(|hour| hour.cover.is_none()),
+			bars: true,
+		});
+	}
+	chart.draw(SolidBars {
+		colour: options.theme.clouds,
+		data: data.iter().map(|hour| hour.cover.map(|cover| cover as i32)),
+		baseline: 0,
+		max: MAX_COVER,
+	});
+	if let Some(range) = chart_visibility_range {
+		chart.draw(SecondaryLabels {
+			range,
+			steps,
+			colour: options.theme.visibility,
+			font: font.clone(),
+			font_scale: FONT_SCALE,
+		});
+		chart.draw(Line {
+			stroke: options.stroke(STROKE),
+			colour: options.theme.visibility,
+			data: data.iter().map(|hour| {
+				hour.visibility
+					.map(|visibility| to_cover_scale(visibility, range))
+			}),
+			max: MAX_COVER,
+		});
+	}
+	if let Some(caption) = caption {
+		chart.draw(caption);
+	}
+	Ok(chart.into_canvas())
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(from = "HourlyCloudsInput")]
+pub struct HourlyClouds {
+	/// Hour of the day
+	hour: u8,
+	/// When the hour starts, which labels the time axis with days when every hour has one
+	time: Option<ZonedTime>,
+	/// Total cloud cover * 100
+	cover: Option<u32>,
+	/// Low, middle and high cloud cover * 100
+	layers: Option<[u32; 3]>,
+	/// Visibility in metres
+	visibility: Option<u32>,
+}
+
+impl HourlyClouds {
+	/// Takes cloud cover in percent and visibility in metres, and `None` for either where it is missing.
+	pub fn new(
+		hour: u8,
+		cover: impl Into<Option<f32>>,
+		visibility: impl Into<Option<f32>>,
+	) -> Self {
+		Self {
+			hour,
+			time: None,
+			cover: cover.into().map(percent),
+			layers: None,
+			visibility: visibility
+				.into()
+				.map(|visibility| visibility.round() as u32),
+		}
+	}
+	/// Sets the low, middle and high cloud cover in percent.
+	pub fn with_layers(self, low: f32, mid: f32, high: f32) -> Self {
+		Self {
+			layers: Some([low, mid, high].map(percent)),
+			..self
+		}
+	}
+	/// Sets when the hour starts, so the time axis can show days.
+	pub fn with_time(self, time: impl Into<Option<ZonedTime>>) -> Self {
+		Self {
+			time: time.into(),
+			..self
+		}
+	}
+}
+
+fn percent(cover: f32) -> u32 {
+	(cover.clamp(0.0, 100.0) * 100.0).round() as u32
+}
+
+impl FromArgs<6> for HourlyClouds {
+	fn from_args(
+		[hour, cover, low, mid, high, visibility]: [String; 6],
+	) -> Result<Self, InvalidArgument> {
+		let hour = parse_arg(hour, "hour")?;
+		let cover = parse_optional_arg(cover, "cloud cover")?;
+		let low = parse_optional_arg(low, "low cloud cover")?;
+		let mid = parse_optional_arg(mid, "middle cloud cover")?;
+		let high = parse_optional_arg(high, "high cloud cover")?;
+		let visibility = parse_optional_arg(visibility, "visibility")?;
+		Ok(HourlyCloudsInput {
+			hour,
+			time: None,
+			cover,
+			low,
+			mid,
+			high,
+			visibility,
+		}
+		.into())
+	}
+}
+
+/// The shape of [`HourlyClouds`] in input, with cover in whole percent and each layer optional.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HourlyCloudsInput {
+	/// Hour of the day
+	hour: u8,
+	/// When the hour starts
+	#[serde(default)]
+	time: Option<ZonedTime>,
+	/// Total cloud cover in percent
+	cover: Option<u8>,
+	/// Low cloud cover in percent
+	#[serde(default)]
+	low: Option<u8>,
+	/// Middle cloud cover in percent
+	#[serde(default)]
+	mid: Option<u8>,
+	/// High cloud cover in percent
+	#[serde(default)]
+	high: Option<u8>,
+	/// Visibility in metres
+	#[serde(default)]
+	visibility: Option<u32>,
+}
+
+impl From<HourlyCloudsInput> for HourlyClouds {
+	fn from(input: HourlyCloudsInput) -> Self {
+		let HourlyCloudsInput {
+			hour,
+			time,
+			cover,
+			low,
+			mid,
+			high,
+			visibility,
+		} = input;
+		let layers = match (low, mid, high) {
+			(Some(low), Some(mid), Some(high)) => {
+				Some([low, mid, high].map(|cover| cover.min(100) as u32 * 100))
+			}
+			_ => None,
+		};
+		Self {
+			hour,
+			time,
+			cover: cover.map(|cover| cover.min(100) as u32 * 100),
+			layers,
+			visibility,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn json_matches_args() {
+		let from_json: Vec<HourlyClouds> = data_from_json(
+			r#"[{"hour": 13, "cover": 80, "low": 10, "mid": 75, "high": 20, "visibility": 9000}, {"hour": 14, "cover": 100, "mid": 100}]"#
+				.as_bytes(),
+		)
+		.unwrap();
+		let from_args: Vec<HourlyClouds> = data_from_args(
+			[
+				"13", "80", "10", "75", "20", "9000", "14", "100", "-", "100", "-", "-",
+			]
+			.map(String::from)
+			.to_vec(),
+		)
+		.unwrap();
+		for (json, args) in from_json.iter().zip(&from_args) {
+			assert_eq!(json.cover, args.cover);
+			assert_eq!(json.layers, args.layers);
+			assert_eq!(json.visibility, args.visibility);
+		}
+		assert_eq!(from_json[0].layers, Some([1000, 7500, 2000]));
+		assert_eq!(from_json[1].layers, None);
+	}
+}
@@ -1,38 +1,134 @@
+use std::io::Read;
+
 use ab_glyph::FontRef;
-use image::RgbImage;
+use serde::Deserialize;
 
 use crate::{
-	modules::{hourly_pop, hourly_precipitation, hourly_temp, hourly_uvi, hourly_wind},
+	canvas::Canvas,
+	error::Error,
+	from_json::data_from_json,
+	generic_graph::ChartOptions,
+	modules::{
+		hourly_clouds::{self, HourlyClouds},
+		hourly_humidity::{self, HourlyHumidity},
+		hourly_pop::{self, HourlyPop},
+		hourly_precipitation::{self, HourlyPrecipitation},
+		hourly_temp::{self, HourlyTemps},
+		hourly_uvi::{self, HourlyUvi},
+		hourly_wind::{self, HourlyWind},
+	},
 	util::composite,
 };
 
-pub fn parse_and_create(font: &FontRef<'static>, args: Vec<String>) -> RgbImage {
+pub fn parse_and_create<C: Canvas>(
+	font: &FontRef<'static>,
+	options: &ChartOptions,
+	args: Vec<String>,
+) -> Result<C, Error> {
 	let mut component_args = args.into_iter();
-	let temp_args = component_args.next().expect("No temperature arguments");
+	let temp_args = component_args
+		.next()
+		.ok_or(Error::MissingArgument("temperature"))?;
 	let temp_args = temp_args.split(' ').map(String::from).collect::<Vec<_>>();
-	let temp_graph = hourly_temp::parse_and_create(font, temp_args);
+	let temp_graph = hourly_temp::parse_and_create(font, options, temp_args)?;
 	let pop_args = component_args
 		.next()
-		.expect("No probability of precipitation arguments");
+		.ok_or(Error::MissingArgument("probability of precipitation"))?;
 	let pop_args = pop_args.split(' ').map(String::from).collect::<Vec<_>>();
-	let pop_graph = hourly_pop::parse_and_create(font, pop_args);
-	let precipitation_args = component_args.next().expect("No precipitation arguments");
+	let pop_graph = hourly_pop::parse_and_create(font, options, pop_args)?;
+	let precipitation_args = component_args
+		.next()
+		.ok_or(Error::MissingArgument("precipitation"))?;
 	let precipitation_args = precipitation_args
 		.split(' ')
 		.map(String::from)
 		.collect::<Vec<_>>();
-	let precipitation_graph = hourly_precipitation::parse_and_create(font, precipitation_args);
-	let wind_args = component_args.next().expect("No wind arguments");
+	let precipitation_graph =
+		hourly_precipitation::parse_and_create(font, options, precipitation_args)?;
+	let wind_args = component_args
+		.next()
+		.ok_or(Error::MissingArgument("wind"))?;
 	let wind_args = wind_args.split(' ').map(String::from).collect::<Vec<_>>();
-	let wind_graph = hourly_wind::parse_and_create(font, wind_args);
-	let uvi_args = component_args.next().expect("No uvi arguments");
+	let wind_graph = hourly_wind::parse_and_create(font, options, wind_args)?;
+	let uvi_args = component_args
+		.next()
+		.ok_or(Error::MissingArgument("UV index"))?;
 	let uvi_args = uvi_args.split(' ').map(String::from).collect::<Vec<_>>();
-	let uvi_graph = hourly_uvi::parse_and_create(font, uvi_args);
-	composite(&[
-		temp_graph,
-		pop_graph,
-		precipitation_graph,
-		wind_graph,
-		uvi_graph,
-	])
+	let uvi_graph = hourly_uvi::parse_and_create(font, options, uvi_args)?;
+	let mut graphs = vec![temp_graph];
+	// Humidity and clouds are optional, so they come last in the arguments, where "-" leaves one out.
+	let mut optional_args = || component_args.next().filter(|args| args != "-");
+	if let Some(humidity_args) = optional_args() {
+		let humidity_args = humidity_args
+			.split(' ')
+			.map(String::from)
+			.collect::<Vec<_>>();
+		graphs.push(hourly_humidity::parse_and_create(
+			font,
+			options,
+			humidity_args,
+		)?);
+	}
+	graphs.extend([pop_graph, precipitation_graph]);
+	if let Some(clouds_args) = optional_args() {
+		let clouds_args = clouds_args.split(' ').map(String::from).collect::<Vec<_>>();
+		graphs.push(hourly_clouds::parse_and_create(font, options, clouds_args)?);
+	}
+	graphs.extend([wind_graph, uvi_graph]);
+	composite(graphs, options.theme.background)
+}
+
+/// Reads a JSON object with `temp`, `pop`, `precipitation`, `wind` and `uvi` fields, and optionally `humidity` and `clouds`, each holding the array the respective module takes.
+pub fn parse_json_and_create<C: Canvas>(
+	font: &FontRef<'static>,
+	options: &ChartOptions,
+	reader: impl Read,
+) -> Result<C, Error> {
+	let data = data_from_json(reader)?;
+	create(font, options, data)
+}
+
+pub fn create<C: Canvas>(
+	font: &FontRef<'static>,
+	options: &ChartOptions,
+	data: HourlyComposite,
+) -> Result<C, Error> {
+	let mut graphs = vec![hourly_temp::create(font, options, data.temp)?];
+	if let Some(humidity) = data.humidity {
+		graphs.push(hourly_humidity::create(font, options, humidity)?);
+	}
+	graphs.extend([
+		hourly_pop::create(font, options, data.pop)?,
+		hourly_precipitation::create(font, options, data.precipitation)?,
+	]);
+	if let Some(clouds) = data.clouds {
+		graphs.push(hourly_clouds::create(font, options, clouds)?);
+	}
+	graphs.extend([
+		hourly_wind::create(font, options, data.wind)?,
+		hourly_uvi::create(font, options, data.uvi)?,
+	]);
+	composite(graphs, options.theme.background)
+}
+
+/// Which of the optional charts an API response's composite includes. They are left out unless asked for, so composites keep the charts and size they always had.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Extras {
+	pub humidity: bool,
+	pub clouds: bool,
+}
+
+/// The data for every chart in the composite, from top to bottom.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HourlyComposite {
+	pub temp: Vec<HourlyTemps>,
+	#[serde(default)]
+	pub humidity: Option<Vec<HourlyHumidity>>,
+	pub pop: Vec<HourlyPop>,
+	pub precipitation: Vec<HourlyPrecipitation>,
+	#[serde(default)]
+	pub clouds: Option<Vec<HourlyClouds>>,
+	pub wind: Vec<HourlyWind>,
+	pub uvi: Vec<HourlyUvi>,
 }
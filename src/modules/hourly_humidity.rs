@@ -0,0 +1,291 @@
+use std::io::Read;
+
+use ab_glyph::FontRef;
+use itertools::Itertools;
+use serde::Deserialize;
+
+use crate::{
+	canvas::Canvas,
+	common_types::Range,
+	drawing::{LineStyle, MarkIntervals, Padding, Spacing, Stroke},
+	error::Error,
+	from_args::{data_from_args, parse_arg, parse_optional_arg, FromArgs, InvalidArgument},
+	from_json::data_from_json,
+	generic_graph::{
+		padding_with_caption, AxisGridLabels, Bands, Chart, ChartOptions, Line, NightShading,
+		NoData, SecondaryLabels,
+	},
+	text_box::TextSegment,
+	time::ZonedTime,
+	util::secondary_range,
+};
+
+const PADDING: Padding = Padding {
+	above: 7,
+	below: 19,
+	left: 21,
+	right: 21,
+};
+const SPACING: Spacing = Spacing {
+	horizontal: 8,
+	vertical: 1,
+};
+/// Used when series are told apart by line style
+const STROKE: Stroke = Stroke {
+	width: 1.5,
+	anti_aliased: true,
+	style: LineStyle::Solid,
+};
+const FONT_SCALE: ab_glyph::PxScale = ab_glyph::PxScale { x: 14.0, y: 14.0 };
+const MAX_HUMIDITY: i32 = 10_000;
+/// Humidity is labelled every 20 %, and the dew point is labelled on the same lines.
+const LABEL_INTERVAL: usize = 2000;
+/// Dew points in centidegrees Celsius where it starts to feel comfortable, muggy and oppressive
+const COMFORT_THRESHOLDS: [i32; 3] = [1000, 1600, 2100];
+
+pub fn parse_and_create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	args: Vec<String>,
+) -> Result<C, Error> {
+	let data = data_from_args(args)?;
+	create(font, options, data)
+}
+
+/// Reads a JSON array of objects with `hour`, `temp` and `humidity` fields. The temperature is in centidegrees Celsius and humidity is in percent. Values may be `null` where they are missing.
+pub fn parse_json_and_create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	reader: impl Read,
+) -> Result<C, Error> {
+	let data = data_from_json(reader)?;
+	create(font, options, data)
+}
+
+/// Relative humidity against the axis on the left, and the dew point against the axis on the right, over bands of how humid the dew point feels.
+pub fn create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	data: Vec<HourlyHumidity>,
+) -> Result<C, Error> {
+	if data.is_empty() {
+		return Err(Error::NoData);
+	}
+	if let Some(index) = data.iter().position(|hour| {
+		hour.humidity
+			.is_some_and(|humidity| humidity > MAX_HUMIDITY as u32)
+	}) {
+		return Err(Error::InvalidDatum {
+			index,
+			field: "humidity",
+		});
+	}
+	let unit = options.units.temperature;
+	let dew_point_range = data
+		.iter()
+		.filter_map(|hour| hour.dew_point)
+		.map(|dew_point| unit.convert(dew_point))
+		.minmax()
+		.into_option()
+		.unwrap_or((0, 0));
+	let steps = (MAX_HUMIDITY / LABEL_INTERVAL as i32) as u32;
+	let chart_dew_point_range = secondary_range(
+		Range::new(dew_point_range.0, dew_point_range.1)?,
+		unit.axis().line as i32,
+		steps,
+	)?;
+	// Dew points are drawn in humidity's hundredths, so they share its grid.
+	let to_humidity_scale = |dew_point: i32| {
+		(dew_point - chart_dew_point_range.start()) * MAX_HUMIDITY / chart_dew_point_range.len()
+	};
+
+	let unit_text = format!(" ({})", unit.symbol());
+	let caption = options.caption(
+		&[
+			TextSegment::new("Relative humidity", options.theme.humidity),
+			TextSegment::plain(" (%) and ", &options.theme),
+			TextSegment::new("dew point", options.theme.dew_point),
+			TextSegment::plain(&unit_text, &options.theme),
+		],
+		font,
+		data.len(),
+		SPACING,
+	);
+	let mut chart = Chart::new(
+		data.len(),
+		MAX_HUMIDITY as u32,
+		SPACING,
+		padding_with_caption(PADDING, caption.as_ref()),
+		options,
+	)?;
+
+	let theme = &options.theme;
+	let [comfortable, muggy, oppressive] =
+		COMFORT_THRESHOLDS.map(|threshold| to_humidity_scale(unit.convert(threshold)));
+	chart.draw(Bands {
+		bands: vec![
+			(Range::new(i32::MIN, comfortable)?, theme.dry),
+			(Range::new(comfortable, muggy)?, theme.comfortable),
+			(Range::new(muggy, oppressive)?, theme.muggy),
+			(Range::new(oppressive, i32::MAX)?, theme.oppressive),
+		],
+		max: MAX_HUMIDITY,
+	});
+	let times: Option<Vec<ZonedTime>> = data.iter().map(|hour| hour.time).collect();
+	if let (Some(daylight), Some(times)) = (&options.daylight, &times) {
+		chart.draw(NightShading { daylight, times });
+	}
+	chart.draw(AxisGridLabels {
+		vertical_intervals: MarkIntervals::new(LABEL_INTERVAL / 2, LABEL_INTERVAL)?,
+		horizontal_intervals: MarkIntervals::new(1, 2)?,
+		vertical_label_range: Range::new(0, MAX_HUMIDITY)?,
+		horizontal_labels: data.iter().map(|hour| hour.hour),
+		horizontal_times: times,
+		horizontal_labels_centered: false,
+		font: font.clone(),
+		font_scale: FONT_SCALE,
+	});
+	if options.no_data {
+		chart.draw(NoData {
+			missing: data.iter().map(|hour| hour.humidity.is_none()),
+			bars: false,
+		});
+	}
+	chart.draw(SecondaryLabels {
+		range: chart_dew_point_range,
+		steps,
+		colour: theme.dew_point,
+		font: font.clone(),
+		font_scale: FONT_SCALE,
+	});
+	chart.draw(Line {
+		stroke: options.stroke(STROKE.with_style(LineStyle::Dashed)),
+		colour: theme.dew_point,
+		data: data.iter().map(|hour| {
+			hour.dew_point
+				.map(|dew_point| to_humidity_scale(unit.convert(dew_point)))
+		}),
+		max: MAX_HUMIDITY,
+	});
+	chart.draw(Line {
+		stroke: options.stroke(STROKE),
+		colour: theme.humidity,
+		data: data
+			.iter()
+			.map(|hour| hour.humidity.map(|humidity| humidity as i32)),
+		max: MAX_HUMIDITY,
+	});
+	if let Some(caption) = caption {
+		chart.draw(caption);
+	}
+	Ok(chart.into_canvas())
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(from = "HourlyHumidityInput")]
+pub struct HourlyHumidity {
+	/// Hour of the day
+	hour: u8,
+	/// When the hour starts, which labels the time axis with days when every hour has one
+	time: Option<ZonedTime>,
+	/// Relative humidity * 100
+	humidity: Option<u32>,
+	/// Dew point in centidegrees Celsius, missing unless both the temperature and humidity are known
+	dew_point: Option<i32>,
+}
+
+impl HourlyHumidity {
+	/// Takes the temperature in °C and relative humidity in percent, and `None` for either where it is missing. Humidity over 100 % is rejected when the chart is created.
+	pub fn new(hour: u8, temp: impl Into<Option<f32>>, humidity: impl Into<Option<i32>>) -> Self {
+		HourlyHumidityInput {
+			hour,
+			time: None,
+			temp: temp.into().map(|temp| (temp * 100.0).round() as i32),
+			humidity: humidity
+				.into()
+				.map(|humidity| humidity.clamp(0, u8::MAX.into()) as u8),
+		}
+		.into()
+	}
+	/// Sets when the hour starts, so the time axis can show days.
+	pub fn with_time(self, time: impl Into<Option<ZonedTime>>) -> Self {
+		Self {
+			time: time.into(),
+			..self
+		}
+	}
+}
+
+impl FromArgs<3> for HourlyHumidity {
+	fn from_args([hour, temp, humidity]: [String; 3]) -> Result<Self, InvalidArgument> {
+		let hour = parse_arg(hour, "hour")?;
+		let temp = parse_optional_arg(temp, "temperature")?;
+		let humidity = parse_optional_arg(humidity, "humidity")?;
+		Ok(HourlyHumidityInput {
+			hour,
+			time: None,
+			temp,
+			humidity,
+		}
+		.into())
+	}
+}
+
+/// The shape of [`HourlyHumidity`] in input, before the dew point is derived.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HourlyHumidityInput {
+	/// Hour of the day
+	hour: u8,
+	/// When the hour starts
+	#[serde(default)]
+	time: Option<ZonedTime>,
+	/// Temperature in centidegrees Celsius
+	temp: Option<i32>,
+	/// Relative humidity in percent
+	humidity: Option<u8>,
+}
+
+impl From<HourlyHumidityInput> for HourlyHumidity {
+	fn from(input: HourlyHumidityInput) -> Self {
+		let HourlyHumidityInput {
+			hour,
+			time,
+			temp,
+			humidity,
+		} = input;
+		Self {
+			hour,
+			time,
+			humidity: humidity.map(|humidity| humidity as u32 * 100),
+			dew_point: temp.zip(humidity).map(|(temp, humidity)| {
+				(dew_point(temp as f32 / 100.0, humidity as f32) * 100.0).round() as i32
+			}),
+		}
+	}
+}
+
+/// Calculates the dew point in °C given the temperature in °C and relative humidity * 100 (0-100), with the [Magnus formula](https://en.wikipedia.org/wiki/Dew_point#Calculating_the_dew_point).
+fn dew_point(temp: f32, humidity: f32) -> f32 {
+	const B: f32 = 17.625;
+	const C: f32 = 243.04;
+	// Bone dry air has no dew point, so the driest is taken to be 1 %.
+	let gamma = (humidity.max(1.0) / 100.0).ln() + B * temp / (C + temp);
+	C * gamma / (B - gamma)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn dew_points() {
+		let tests = [(20, 50, 9), (30, 80, 26), (0, 100, 0), (-10, 60, -16)];
+		for (temp, humidity, expected) in tests {
+			assert_eq!(
+				dew_point(temp as f32, humidity as f32).round() as i32,
+				expected
+			);
+		}
+	}
+}
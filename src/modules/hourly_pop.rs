@@ -1,14 +1,20 @@
+use std::io::Read;
+
 use ab_glyph::FontRef;
-use image::RgbImage;
+use serde::Deserialize;
 
 use crate::{
-	colours,
+	canvas::Canvas,
 	common_types::Range,
-	drawing::{
-		draw_graph_bars, draw_outer_lines, fill_canvas, horizontal_lines_and_labels,
-		vertical_lines_and_labels, MarkIntervals, Padding, Spacing,
+	drawing::{MarkIntervals, Padding, Spacing},
+	error::Error,
+	from_args::{data_from_args, parse_arg, parse_optional_arg, FromArgs, InvalidArgument},
+	from_json::data_from_json,
+	generic_graph::{
+		padding_with_caption, AxisGridLabels, Chart, ChartOptions, NightShading, NoData, SolidBars,
 	},
-	from_args::{data_from_args, FromArgs},
+	text_box::TextSegment,
+	time::ZonedTime,
 };
 
 const PADDING: Padding = Padding {
@@ -23,62 +29,149 @@ const SPACING: Spacing = Spacing {
 };
 const FONT_SCALE: ab_glyph::PxScale = ab_glyph::PxScale { x: 14.0, y: 14.0 };
 
-pub fn parse_and_create(font: &FontRef, args: Vec<String>) -> RgbImage {
-	let data = data_from_args(args);
-	create(font, data)
+pub fn parse_and_create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	args: Vec<String>,
+) -> Result<C, Error> {
+	let data = data_from_args(args)?;
+	create(font, options, data)
+}
+
+/// Reads a JSON array of objects with `hour` and `chance` fields. The chance is in percent, and may be `null` where it is missing.
+pub fn parse_json_and_create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	reader: impl Read,
+) -> Result<C, Error> {
+	let data = data_from_json(reader)?;
+	create(font, options, data)
 }
 
-pub fn create(font: &FontRef, data: Vec<HourlyPop>) -> RgbImage {
+pub fn create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	data: Vec<HourlyPop>,
+) -> Result<C, Error> {
+	if data.is_empty() {
+		return Err(Error::NoData);
+	}
 	let max_chart_pop = 10_000;
-	let width = data.len() as u32 * SPACING.horizontal + PADDING.horizontal();
-	let height = max_chart_pop * SPACING.vertical / 100 + PADDING.vertical();
-	let mut canvas = RgbImage::new(width, height);
-	fill_canvas(&mut canvas, colours::BACKGROUND);
-	draw_outer_lines(&mut canvas, PADDING);
-	vertical_lines_and_labels(
-		&mut canvas,
-		data.iter().map(|datum| datum.hour),
-		MarkIntervals::new(1, 2),
-		font,
-		FONT_SCALE,
-		PADDING,
-		SPACING.horizontal,
-		false,
-	);
-	horizontal_lines_and_labels(
-		&mut canvas,
-		Range::new(0, max_chart_pop as i32),
-		MarkIntervals::new(10, 20),
+
+	let caption = options.caption(
+		&[
+			TextSegment::new("Chance of precipitation", options.theme.pop),
+			TextSegment::plain(" (%)", &options.theme),
+		],
 		font,
-		FONT_SCALE,
-		PADDING,
-		SPACING.vertical,
-	);
-	draw_graph_bars(
-		&mut canvas,
-		data.iter().map(|datum| datum.chance as i32),
-		colours::POP,
-		PADDING,
+		data.len(),
 		SPACING,
 	);
-	canvas
+	// Bars take up a whole column each, so there is one more column than there are points.
+	let mut chart = Chart::new(
+		data.len() + 1,
+		max_chart_pop,
+		SPACING,
+		padding_with_caption(PADDING, caption.as_ref()),
+		options,
+	)?;
+
+	let times: Option<Vec<ZonedTime>> = data.iter().map(|datum| datum.time).collect();
+	if let (Some(daylight), Some(times)) = (&options.daylight, &times) {
+		chart.draw(NightShading { daylight, times });
+	}
+	chart.draw(AxisGridLabels {
+		vertical_intervals: MarkIntervals::new(1000, 2000)?,
+		horizontal_intervals: MarkIntervals::new(1, 2)?,
+		vertical_label_range: Range::new(0, max_chart_pop as i32)?,
+		horizontal_labels: data.iter().map(|datum| datum.hour),
+		horizontal_times: times,
+		horizontal_labels_centered: false,
+		font: font.clone(),
+		font_scale: FONT_SCALE,
+	});
+	if options.no_data {
+		chart.draw(NoData {
+			missing: data.iter().map(|datum| datum.chance.is_none()),
+			bars: true,
+		});
+	}
+	chart.draw(SolidBars {
+		colour: options.theme.pop,
+		data: data
+			.iter()
+			.map(|datum| datum.chance.map(|chance| chance as i32)),
+		baseline: 0,
+		max: max_chart_pop as i32,
+	});
+	if let Some(caption) = caption {
+		chart.draw(caption);
+	}
+	Ok(chart.into_canvas())
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(from = "HourlyPopInput")]
 pub struct HourlyPop {
 	/// Hour of the day
 	hour: u8,
+	/// When the hour starts, which labels the time axis with days when every hour has one
+	time: Option<ZonedTime>,
 	/// Probability of precipitation * 100
-	chance: u32,
+	chance: Option<u32>,
+}
+
+impl HourlyPop {
+	/// Takes the probability of precipitation as a fraction (0-1), or `None` where it is missing.
+	pub fn new(hour: u8, pop: impl Into<Option<f32>>) -> Self {
+		let chance = pop.into().map(|pop| (pop * 10_000.0).round() as u32);
+		Self {
+			hour,
+			time: None,
+			chance,
+		}
+	}
+	/// Sets when the hour starts, so the time axis can show days.
+	pub fn with_time(self, time: impl Into<Option<ZonedTime>>) -> Self {
+		Self {
+			time: time.into(),
+			..self
+		}
+	}
 }
 
 impl FromArgs<2> for HourlyPop {
-	fn from_args([hour, chance]: [String; 2]) -> Self {
-		let hour = hour.parse().expect("Could not parse an hour argument");
-		let chance = chance
-			.parse::<u32>()
-			.expect("Could not parse a probability of precipitation argument")
-			* 100;
-		Self { hour, chance }
+	fn from_args([hour, chance]: [String; 2]) -> Result<Self, InvalidArgument> {
+		let hour = parse_arg(hour, "hour")?;
+		let chance = parse_optional_arg::<u32>(chance, "probability of precipitation")?;
+		Ok(HourlyPopInput {
+			hour,
+			time: None,
+			chance,
+		}
+		.into())
+	}
+}
+
+/// The shape of [`HourlyPop`] in input, with the chance in whole percent.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HourlyPopInput {
+	/// Hour of the day
+	hour: u8,
+	/// When the hour starts
+	#[serde(default)]
+	time: Option<ZonedTime>,
+	/// Probability of precipitation in percent
+	chance: Option<u32>,
+}
+
+impl From<HourlyPopInput> for HourlyPop {
+	fn from(HourlyPopInput { hour, time, chance }: HourlyPopInput) -> Self {
+		Self {
+			hour,
+			time,
+			chance: chance.map(|chance| chance * 100),
+		}
 	}
 }
@@ -1,13 +1,22 @@
+use std::io::Read;
+
 use ab_glyph::FontRef;
-use image::RgbImage;
+use serde::Deserialize;
 
 use crate::{
-	colours,
+	canvas::Canvas,
 	common_types::Range,
 	drawing::{MarkIntervals, Padding, Spacing},
-	from_args::{data_from_args, FromArgs},
-	generic_graph::{AxisGridLabels, Chart, SolidBars},
-	util::next_multiple,
+	error::Error,
+	from_args::{data_from_args, parse_arg, parse_optional_arg, FromArgs, InvalidArgument},
+	from_json::data_from_json,
+	generic_graph::{
+		padding_with_caption, AxisGridLabels, Chart, ChartOptions, NightShading, NoData,
+		StackedBars,
+	},
+	text_box::TextSegment,
+	time::ZonedTime,
+	util::next_multiple_of_hundredths,
 };
 
 const PADDING: Padding = Padding {
@@ -16,64 +25,160 @@ const PADDING: Padding = Padding {
 	left: 21,
 	right: 3,
 };
-const SPACING: Spacing = Spacing {
-	horizontal: 8,
-	vertical: 16,
-};
+const HORIZONTAL_SPACING: u32 = 8;
 const FONT_SCALE: ab_glyph::PxScale = ab_glyph::PxScale { x: 14.0, y: 14.0 };
 
-pub fn parse_and_create(font: &FontRef<'static>, args: Vec<String>) -> RgbImage {
-	let data = data_from_args(args);
-	create(font, data)
+pub fn parse_and_create<C: Canvas>(
+	font: &FontRef<'static>,
+	options: &ChartOptions,
+	args: Vec<String>,
+) -> Result<C, Error> {
+	let data = data_from_args(args)?;
+	create(font, options, data)
+}
+
+/// Reads a JSON array of objects with `hour`, `rain` and `snow` fields. Amounts are in mm * 100, and may be `null` where they are missing.
+pub fn parse_json_and_create<C: Canvas>(
+	font: &FontRef<'static>,
+	options: &ChartOptions,
+	reader: impl Read,
+) -> Result<C, Error> {
+	let data = data_from_json(reader)?;
+	create(font, options, data)
 }
 
-pub fn create(font: &FontRef<'static>, data: Vec<HourlyPrecipitation>) -> RgbImage {
-	let max_chart_precipitation = next_multiple(
+/// Rain and snow as bars, with snow stacked on top of rain so the height of each bar is the hour's total.
+pub fn create<C: Canvas>(
+	font: &FontRef<'static>,
+	options: &ChartOptions,
+	data: Vec<HourlyPrecipitation>,
+) -> Result<C, Error> {
+	if data.is_empty() {
+		return Err(Error::NoData);
+	}
+	let unit = options.units.precipitation;
+	let axis = unit.axis();
+	let spacing = Spacing {
+		horizontal: HORIZONTAL_SPACING,
+		vertical: axis.spacing,
+	};
+	// Snow is stacked on rain, so the chart goes up to the wettest hour's total.
+	let max_chart_precipitation = next_multiple_of_hundredths(
 		data.iter()
-			.flat_map(|hour| [hour.rain as i32, hour.snow as i32])
+			.map(|hour| {
+				[hour.rain, hour.snow]
+					.into_iter()
+					.flatten()
+					.map(|amount| unit.convert(amount as i32))
+					.sum::<i32>()
+			})
 			.max()
 			.unwrap_or(0),
-		1,
+		axis.rounding,
 	) as u32;
 
-	let mut chart = Chart::new(data.len(), max_chart_precipitation, SPACING, PADDING);
+	let unit_text = format!(" ({})", unit.symbol());
+	let caption = options.caption(
+		&[
+			TextSegment::new("Rain", options.theme.rain),
+			TextSegment::plain(" and ", &options.theme),
+			TextSegment::new("snow", options.theme.snow),
+			TextSegment::plain(&unit_text, &options.theme),
+		],
+		font,
+		data.len(),
+		spacing,
+	);
+	let mut chart = Chart::new(
+		data.len(),
+		max_chart_precipitation,
+		spacing,
+		padding_with_caption(PADDING, caption.as_ref()),
+		options,
+	)?;
 
+	let times: Option<Vec<ZonedTime>> = data.iter().map(|hour| hour.time).collect();
+	if let (Some(daylight), Some(times)) = (&options.daylight, &times) {
+		chart.draw(NightShading { daylight, times });
+	}
 	chart.draw(AxisGridLabels {
-		vertical_intervals: MarkIntervals::new(1, 1),
-		horizontal_intervals: MarkIntervals::new(1, 2),
-		vertical_label_range: Range::new(0, max_chart_precipitation as i32),
+		vertical_intervals: MarkIntervals::new(axis.line, axis.label)?,
+		horizontal_intervals: MarkIntervals::new(1, 2)?,
+		vertical_label_range: Range::new(0, max_chart_precipitation as i32)?,
 		horizontal_labels: data.iter().map(|hour| hour.hour),
+		horizontal_times: times,
 		horizontal_labels_centered: false,
 		font: font.clone(),
 		font_scale: FONT_SCALE,
 	});
-	chart.draw(SolidBars {
-		colour: colours::RAIN,
-		data: data.iter().map(|hour| hour.rain as i32),
-	});
-	chart.draw(SolidBars {
-		colour: colours::SNOW,
-		data: data.iter().map(|hour| hour.snow as i32),
+	if options.no_data {
+		chart.draw(NoData {
+			missing: data
+				.iter()
+				.map(|hour| hour.rain.is_none() || hour.snow.is_none()),
+			bars: true,
+		});
+	}
+	chart.draw(StackedBars {
+		colours: vec![options.theme.rain, options.theme.snow],
+		data: data.iter().map(|hour| {
+			[hour.rain, hour.snow]
+				.map(|amount| amount.map(|amount| unit.convert(amount as i32)))
+				.to_vec()
+		}),
+		max: max_chart_precipitation as i32,
 	});
 
-	chart.into_canvas()
+	if let Some(caption) = caption {
+		chart.draw(caption);
+	}
+	Ok(chart.into_canvas())
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct HourlyPrecipitation {
 	/// Hour of the day
-	hour: u8,
+	pub(crate) hour: u8,
+	/// When the hour starts, which labels the time axis with days when every hour has one
+	#[serde(default)]
+	pub(crate) time: Option<ZonedTime>,
 	/// Amount of rain in mm * 100
-	rain: u32,
+	pub(crate) rain: Option<u32>,
 	/// Amount of snow in mm * 100
-	snow: u32,
+	pub(crate) snow: Option<u32>,
+}
+
+impl HourlyPrecipitation {
+	/// Takes amounts in mm, and `None` for any that are missing.
+	pub fn new(hour: u8, rain: impl Into<Option<f32>>, snow: impl Into<Option<f32>>) -> Self {
+		let to_hundredths = |amount: f32| (amount * 100.0).round() as u32;
+		Self {
+			hour,
+			time: None,
+			rain: rain.into().map(to_hundredths),
+			snow: snow.into().map(to_hundredths),
+		}
+	}
+	/// Sets when the hour starts, so the time axis can show days.
+	pub fn with_time(self, time: impl Into<Option<ZonedTime>>) -> Self {
+		Self {
+			time: time.into(),
+			..self
+		}
+	}
 }
 
 impl FromArgs<3> for HourlyPrecipitation {
-	fn from_args([hour, rain, snow]: [String; 3]) -> Self {
-		let hour = hour.parse().expect("Could not parse an hour argument");
-		let rain = rain.parse().expect("Could not parse a rain argument");
-		let snow = snow.parse().expect("Could not parse a snow argument");
-		Self { hour, rain, snow }
+	fn from_args([hour, rain, snow]: [String; 3]) -> Result<Self, InvalidArgument> {
+		let hour = parse_arg(hour, "hour")?;
+		let rain = parse_optional_arg(rain, "rain")?;
+		let snow = parse_optional_arg(snow, "snow")?;
+		Ok(Self {
+			hour,
+			time: None,
+			rain,
+			snow,
+		})
 	}
 }
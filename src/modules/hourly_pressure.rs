@@ -0,0 +1,263 @@
+use std::io::Read;
+
+use ab_glyph::FontRef;
+use itertools::Itertools;
+use serde::Deserialize;
+
+use crate::{
+	canvas::Canvas,
+	common_types::Range,
+	drawing::{LineStyle, MarkIntervals, Padding, Spacing, Stroke},
+	error::Error,
+	from_args::{data_from_args, parse_arg, parse_optional_arg, FromArgs, InvalidArgument},
+	from_json::data_from_json,
+	generic_graph::{
+		padding_with_caption, AxisGridLabels, Chart, ChartOptions, Line, Marker, Markers,
+		NightShading, NoData,
+	},
+	text_box::TextSegment,
+	theme::Theme,
+	time::ZonedTime,
+	util::previous_and_next_multiple_of_hundredths,
+};
+
+const PADDING: Padding = Padding {
+	// Room for the tendency markers
+	above: 16,
+	below: 19,
+	left: 29,
+	right: 3,
+};
+const SPACING: Spacing = Spacing {
+	horizontal: 8,
+	vertical: 2,
+};
+/// Used when series are told apart by line style
+const STROKE: Stroke = Stroke {
+	width: 1.5,
+	anti_aliased: true,
+	style: LineStyle::Solid,
+};
+const FONT_SCALE: ab_glyph::PxScale = ab_glyph::PxScale { x: 14.0, y: 14.0 };
+/// 5 hPa
+const ROUNDING: i32 = 500;
+/// Added above and below the pressures before rounding, so a steady pressure still has a scale.
+const MARGIN: i32 = 250;
+/// Tendencies are measured over 3 hours, as in synoptic reports.
+const TENDENCY_HOURS: usize = 3;
+
+pub fn parse_and_create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	args: Vec<String>,
+) -> Result<C, Error> {
+	let data = data_from_args(args)?;
+	create(font, options, data)
+}
+
+/// Reads a JSON array of objects with `hour` and `pressure` fields. The pressure is at sea level in pascals (hundredths of a hectopascal), and may be `null` where it is missing.
+pub fn parse_json_and_create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	reader: impl Read,
+) -> Result<C, Error> {
+	let data = data_from_json(reader)?;
+	create(font, options, data)
+}
+
+/// Sea-level pressure in hPa, with a triangle above each hour where it has risen or fallen quickly over the last three hours.
+pub fn create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	data: Vec<HourlyPressure>,
+) -> Result<C, Error> {
+	if data.is_empty() {
+		return Err(Error::NoData);
+	}
+	let pressure_range = data
+		.iter()
+		.filter_map(|hour| hour.pressure)
+		.minmax()
+		.into_option()
+		.unwrap_or((0, 0));
+	let chart_pressure_range = previous_and_next_multiple_of_hundredths(
+		Range::new(pressure_range.0 - MARGIN, pressure_range.1 + MARGIN)?,
+		ROUNDING,
+	)?;
+
+	let caption = options.caption(
+		&[
+			TextSegment::new("Pressure", options.theme.pressure),
+			TextSegment::plain(" (hPa), ", &options.theme),
+			TextSegment::new("rising", options.theme.pressure_rising),
+			TextSegment::plain(" and ", &options.theme),
+			TextSegment::new("falling", options.theme.pressure_falling),
+			TextSegment::plain(" quickly", &options.theme),
+		],
+		font,
+		data.len(),
+		SPACING,
+	);
+	let mut chart = Chart::new(
+		data.len(),
+		chart_pressure_range.len() as u32,
+		SPACING,
+		padding_with_caption(PADDING, caption.as_ref()),
+		options,
+	)?;
+
+	let times: Option<Vec<ZonedTime>> = data.iter().map(|hour| hour.time).collect();
+	if let (Some(daylight), Some(times)) = (&options.daylight, &times) {
+		chart.draw(NightShading { daylight, times });
+	}
+	chart.draw(AxisGridLabels {
+		vertical_intervals: MarkIntervals::new(ROUNDING as usize, 2 * ROUNDING as usize)?,
+		horizontal_intervals: MarkIntervals::new(1, 2)?,
+		vertical_label_range: chart_pressure_range,
+		horizontal_labels: data.iter().map(|hour| hour.hour),
+		horizontal_times: times,
+		horizontal_labels_centered: false,
+		font: font.clone(),
+		font_scale: FONT_SCALE,
+	});
+	if options.no_data {
+		chart.draw(NoData {
+			missing: data.iter().map(|hour| hour.pressure.is_none()),
+			bars: false,
+		});
+	}
+	chart.draw(Markers {
+		data: (0..data.len()).map(|index| {
+			let earlier = data.get(index.checked_sub(TENDENCY_HOURS)?)?.pressure?;
+			Tendency::from_change(data[index].pressure? - earlier).marker(&options.theme)
+		}),
+	});
+	chart.draw(Line {
+		stroke: options.stroke(STROKE),
+		colour: options.theme.pressure,
+		data: data.iter().map(|hour| hour.pressure),
+		max: chart_pressure_range.end(),
+	});
+	if let Some(caption) = caption {
+		chart.draw(caption);
+	}
+	Ok(chart.into_canvas())
+}
+
+/// How fast the pressure changed over three hours, in the terms of marine forecasts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tendency {
+	/// Less than 0.1 hPa
+	Steady,
+	/// Up to 1.5 hPa
+	Slowly { rising: bool },
+	/// Up to 3.5 hPa
+	Changing { rising: bool },
+	/// Up to 6 hPa
+	Quickly { rising: bool },
+	/// More than 6 hPa
+	VeryRapidly { rising: bool },
+}
+
+impl Tendency {
+	/// Takes the change in pascals.
+	fn from_change(change: i32) -> Self {
+		let rising = change > 0;
+		match change.unsigned_abs() {
+			0..10 => Self::Steady,
+			10..=150 => Self::Slowly { rising },
+			151..=350 => Self::Changing { rising },
+			351..=600 => Self::Quickly { rising },
+			_ => Self::VeryRapidly { rising },
+		}
+	}
+	/// Only quick changes are flagged, since they come with fronts and strong winds.
+	fn marker(self, theme: &Theme) -> Option<Marker> {
+		let (rising, large) = match self {
+			Self::Quickly { rising } => (rising, false),
+			Self::VeryRapidly { rising } => (rising, true),
+			_ => return None,
+		};
+		Some(Marker {
+			up: rising,
+			large,
+			colour: if rising {
+				theme.pressure_rising
+			} else {
+				theme.pressure_falling
+			},
+		})
+	}
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HourlyPressure {
+	/// Hour of the day
+	hour: u8,
+	/// When the hour starts, which labels the time axis with days when every hour has one
+	#[serde(default)]
+	time: Option<ZonedTime>,
+	/// Sea-level pressure in Pa, which is hPa * 100
+	pressure: Option<i32>,
+}
+
+impl HourlyPressure {
+	/// Takes the pressure in hPa, or `None` where it is missing.
+	pub fn new(hour: u8, pressure: impl Into<Option<f32>>) -> Self {
+		Self {
+			hour,
+			time: None,
+			pressure: pressure
+				.into()
+				.map(|pressure| (pressure * 100.0).round() as i32),
+		}
+	}
+	/// Sets when the hour starts, so the time axis can show days.
+	pub fn with_time(self, time: impl Into<Option<ZonedTime>>) -> Self {
+		Self {
+			time: time.into(),
+			..self
+		}
+	}
+}
+
+impl FromArgs<2> for HourlyPressure {
+	fn from_args([hour, pressure]: [String; 2]) -> Result<Self, InvalidArgument> {
+		let hour = parse_arg(hour, "hour")?;
+		let pressure = parse_optional_arg(pressure, "pressure")?;
+		Ok(Self {
+			hour,
+			time: None,
+			pressure,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn tendencies() {
+		let tests = [
+			(-5, Tendency::Steady),
+			(10, Tendency::Slowly { rising: true }),
+			(150, Tendency::Slowly { rising: true }),
+			(151, Tendency::Changing { rising: true }),
+			(-155, Tendency::Changing { rising: false }),
+			(350, Tendency::Changing { rising: true }),
+			(-351, Tendency::Quickly { rising: false }),
+			(600, Tendency::Quickly { rising: true }),
+			(601, Tendency::VeryRapidly { rising: true }),
+		];
+		for (change, expected) in tests {
+			assert_eq!(Tendency::from_change(change), expected, "{change} Pa");
+		}
+		let theme = Theme::DARK;
+		assert!(Tendency::from_change(-200).marker(&theme).is_none());
+		let marker = Tendency::from_change(-700).marker(&theme).unwrap();
+		assert!(!marker.up && marker.large);
+		assert_eq!(marker.colour, theme.pressure_falling);
+	}
+}
@@ -1,16 +1,24 @@
+use std::io::Read;
+
 use ab_glyph::FontRef;
-use image::RgbImage;
 use itertools::Itertools;
+use serde::Deserialize;
 
 use crate::{
-	colours,
+	canvas::Canvas,
 	common_types::Range,
-	drawing::{
-		draw_graph_lines, draw_outer_lines, fill_canvas, horizontal_lines_and_labels,
-		vertical_lines_and_labels, MarkIntervals, Padding, Spacing,
+	drawing::{LineStyle, MarkIntervals, Padding, Spacing, Stroke},
+	error::Error,
+	from_args::{data_from_args, parse_arg, parse_optional_arg, FromArgs, InvalidArgument},
+	from_json::data_from_json,
+	generic_graph::{
+		padding_with_caption, Area, AxisGridLabels, Chart, ChartOptions, Icons, Line, NightShading,
+		NoData, ICON_ROW_HEIGHT,
 	},
-	from_args::{data_from_args, FromArgs},
-	util::previous_and_next_multiple,
+	icons::Condition,
+	text_box::TextSegment,
+	time::ZonedTime,
+	util::previous_and_next_multiple_of_hundredths,
 };
 
 const PADDING: Padding = Padding {
@@ -19,142 +27,279 @@ const PADDING: Padding = Padding {
 	left: 21,
 	right: 3,
 };
-const SPACING: Spacing = Spacing {
-	horizontal: 8,
-	vertical: 3,
+const HORIZONTAL_SPACING: u32 = 8;
+/// Used when series are told apart by line style
+const STROKE: Stroke = Stroke {
+	width: 1.5,
+	anti_aliased: true,
+	style: LineStyle::Solid,
 };
 const FONT_SCALE: ab_glyph::PxScale = ab_glyph::PxScale { x: 14.0, y: 14.0 };
+/// Faint enough that the grid and night shading show through
+const FEELS_LIKE_OPACITY: f32 = 0.25;
+
+pub fn parse_and_create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	args: Vec<String>,
+) -> Result<C, Error> {
+	let data = data_from_args(args)?;
+	create(font, options, data)
+}
 
-pub fn parse_and_create(font: &FontRef, args: Vec<String>) -> RgbImage {
-	let data = data_from_args(args);
-	create(font, data)
+/// Reads a JSON array of objects with `hour`, `temp`, `feels_like` and `humidity` fields, and optionally a `condition` to show as an icon. Temperatures are in centidegrees Celsius and humidity is in percent. Values may be `null` where they are missing.
+pub fn parse_json_and_create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	reader: impl Read,
+) -> Result<C, Error> {
+	let data = data_from_json(reader)?;
+	create(font, options, data)
 }
 
-pub fn create(font: &FontRef, data: Vec<HourlyTemps>) -> RgbImage {
+pub fn create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	data: Vec<HourlyTemps>,
+) -> Result<C, Error> {
+	if data.is_empty() {
+		return Err(Error::NoData);
+	}
+	let unit = options.units.temperature;
+	let axis = unit.axis();
+	let spacing = Spacing {
+		horizontal: HORIZONTAL_SPACING,
+		vertical: axis.spacing,
+	};
 	let temp_range = data
 		.iter()
 		.flat_map(|hour| [hour.temp, hour.feels_like, hour.wet_bulb])
+		.flatten()
+		.map(|temp| unit.convert(temp))
 		.minmax()
 		.into_option()
 		.unwrap_or((0, 0));
-	let chart_temp_range = previous_and_next_multiple(Range::new(temp_range.0, temp_range.1), 4);
-	let width = SPACING.horizontal * (data.len() - 1) as u32 + PADDING.horizontal();
-	let height = chart_temp_range.len() as u32 * SPACING.vertical / 100 + PADDING.vertical();
-	let mut canvas = RgbImage::new(width, height);
-	fill_canvas(&mut canvas, colours::BACKGROUND);
-	draw_outer_lines(&mut canvas, PADDING);
-	vertical_lines_and_labels(
-		&mut canvas,
-		data.iter().map(|hour| hour.hour),
-		MarkIntervals::new(1, 2),
-		font,
-		FONT_SCALE,
-		PADDING,
-		SPACING.horizontal,
-		false,
-	);
-	horizontal_lines_and_labels(
-		&mut canvas,
-		chart_temp_range,
-		MarkIntervals::new(2, 4),
+	let chart_temp_range = previous_and_next_multiple_of_hundredths(
+		Range::new(temp_range.0, temp_range.1)?,
+		axis.rounding,
+	)?;
+
+	let unit_text = format!(" ({})", unit.symbol());
+	let caption = options.caption(
+		&[
+			TextSegment::new("Temperature", options.theme.temp),
+			TextSegment::plain(", ", &options.theme),
+			TextSegment::new("feels like", options.theme.temp_feels_like),
+			TextSegment::plain(" and ", &options.theme),
+			TextSegment::new("wet bulb", options.theme.temp_wet_bulb),
+			TextSegment::plain(&unit_text, &options.theme),
+		],
 		font,
-		FONT_SCALE,
-		PADDING,
-		SPACING.vertical,
-	);
-	draw_graph_lines(
-		&mut canvas,
-		data.iter().map(|hour| hour.feels_like),
-		colours::TEMP_FEELS_LIKE,
-		chart_temp_range.end(),
-		PADDING,
-		SPACING,
-	);
-	draw_graph_lines(
-		&mut canvas,
-		data.iter().map(|hour| hour.wet_bulb),
-		colours::TEMP_WET_BULB,
-		chart_temp_range.end(),
-		PADDING,
-		SPACING,
+		data.len(),
+		spacing,
 	);
-	draw_graph_lines(
-		&mut canvas,
-		data.iter().map(|hour| hour.temp),
-		colours::TEMP,
-		chart_temp_range.end(),
-		PADDING,
-		SPACING,
-	);
-	/*for (index, wet_bulb) in data
-		.iter()
-		.enumerate()
-		.filter(|(_index, hour)| hour.wet_bulb_is_accurate)
-		.map(|(index, hour)| (index, hour.wet_bulb))
-	{
+	let has_conditions = data.iter().any(|hour| hour.condition.is_some());
+	let padding = Padding {
+		above: PADDING.above + if has_conditions { ICON_ROW_HEIGHT } else { 0 },
+		..PADDING
+	};
+	let mut chart = Chart::new(
+		data.len(),
+		chart_temp_range.len() as u32,
+		spacing,
+		padding_with_caption(padding, caption.as_ref()),
+		options,
+	)?;
 
-	}*/
-	canvas
+	let times: Option<Vec<ZonedTime>> = data.iter().map(|hour| hour.time).collect();
+	if let (Some(daylight), Some(times)) = (&options.daylight, &times) {
+		chart.draw(NightShading { daylight, times });
+	}
+	chart.draw(AxisGridLabels {
+		vertical_intervals: MarkIntervals::new(axis.line, axis.label)?,
+		horizontal_intervals: MarkIntervals::new(1, 2)?,
+		vertical_label_range: chart_temp_range,
+		horizontal_labels: data.iter().map(|hour| hour.hour),
+		horizontal_times: times,
+		horizontal_labels_centered: false,
+		font: font.clone(),
+		font_scale: FONT_SCALE,
+	});
+	if options.no_data {
+		chart.draw(NoData {
+			missing: data.iter().map(|hour| hour.temp.is_none()),
+			bars: false,
+		});
+	}
+	if has_conditions {
+		chart.draw(Icons {
+			data: data.iter().map(|hour| hour.condition),
+		});
+	}
+	// Shades how far the feels-like temperature is from the real one, under the lines.
+	if options.feels_like_area {
+		chart.draw(Area {
+			colour: options.theme.temp_feels_like,
+			opacity: FEELS_LIKE_OPACITY,
+			data: data
+				.iter()
+				.map(|hour| hour.feels_like.map(|temp| unit.convert(temp))),
+			lower: data
+				.iter()
+				.map(|hour| hour.temp.map(|temp| unit.convert(temp))),
+			max: chart_temp_range.end(),
+		});
+	}
+	chart.draw(Line {
+		stroke: options.stroke(STROKE.with_style(LineStyle::Dashed)),
+		colour: options.theme.temp_feels_like,
+		data: data
+			.iter()
+			.map(|hour| hour.feels_like.map(|temp| unit.convert(temp))),
+		max: chart_temp_range.end(),
+	});
+	chart.draw(Line {
+		stroke: options.stroke(STROKE.with_style(LineStyle::Dotted)),
+		colour: options.theme.temp_wet_bulb,
+		data: data
+			.iter()
+			.map(|hour| hour.wet_bulb.map(|temp| unit.convert(temp))),
+		max: chart_temp_range.end(),
+	});
+	chart.draw(Line {
+		stroke: options.stroke(STROKE),
+		colour: options.theme.temp,
+		data: data
+			.iter()
+			.map(|hour| hour.temp.map(|temp| unit.convert(temp))),
+		max: chart_temp_range.end(),
+	});
+	if let Some(caption) = caption {
+		chart.draw(caption);
+	}
+	Ok(chart.into_canvas())
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(from = "HourlyTempsInput")]
 pub struct HourlyTemps {
 	/// Hour of the day
 	hour: u8,
+	/// When the hour starts, which labels the time axis with days when every hour has one
+	time: Option<ZonedTime>,
 	/// Dry-bulb temperature in centidegrees Celsius
-	temp: i32,
+	temp: Option<i32>,
 	/// Feels-like temperature in centidegrees Celsius
-	feels_like: i32,
-	/// Wet-bulb temperature in centidegrees Celsius
-	wet_bulb: i32,
+	feels_like: Option<i32>,
+	/// Wet-bulb temperature in centidegrees Celsius, missing unless both the temperature and humidity are known
+	wet_bulb: Option<i32>,
 	/// Whether the wet-bulb temperature is accurate (if not, the input was outside the range the calculation was valid for)
 	_wet_bulb_is_accurate: bool,
+	/// What the weather is doing, shown as an icon above the chart
+	condition: Option<Condition>,
 }
 
 impl HourlyTemps {
-	pub fn new(hour: u8, temp: f32, feels_like: f32, humidity: i32) -> Self {
-		let temp = (temp * 100.0).round() as i32;
-		let feels_like = (feels_like * 100.0).round() as i32;
-		let wet_bulb = if humidity == 100 {
-			temp
-		} else {
-			(wet_bulb_temp(temp as f32 / 100.0, humidity as f32) * 100.0).round() as i32
-		};
-		let wet_bulb_is_accurate = (-2000..5000).contains(&temp) && humidity >= 5;
+	/// Takes temperatures in °C and relative humidity in percent, and `None` for any that are missing.
+	pub fn new(
+		hour: u8,
+		temp: impl Into<Option<f32>>,
+		feels_like: impl Into<Option<f32>>,
+		humidity: impl Into<Option<i32>>,
+	) -> Self {
+		let centidegrees = |temp: f32| (temp * 100.0).round() as i32;
+		HourlyTempsInput {
+			hour,
+			time: None,
+			temp: temp.into().map(centidegrees),
+			feels_like: feels_like.into().map(centidegrees),
+			humidity: humidity.into().map(|humidity| humidity.clamp(0, 100) as u8),
+			condition: None,
+		}
+		.into()
+	}
+	/// Sets when the hour starts, so the time axis can show days.
+	pub fn with_time(self, time: impl Into<Option<ZonedTime>>) -> Self {
 		Self {
+			time: time.into(),
+			..self
+		}
+	}
+	/// Sets what the weather is doing, to show as an icon.
+	pub fn with_condition(self, condition: impl Into<Option<Condition>>) -> Self {
+		Self {
+			condition: condition.into(),
+			..self
+		}
+	}
+}
+
+impl FromArgs<4> for HourlyTemps {
+	fn from_args([hour, temp, feels_like, humidity]: [String; 4]) -> Result<Self, InvalidArgument> {
+		let hour = parse_arg(hour, "hour")?;
+		let temp = parse_optional_arg(temp, "dry-bulb temperature")?;
+		let feels_like = parse_optional_arg(feels_like, "feels-like temperature")?;
+		let humidity = parse_optional_arg(humidity, "humidity")?;
+		Ok(HourlyTempsInput {
 			hour,
+			time: None,
 			temp,
 			feels_like,
-			wet_bulb,
-			_wet_bulb_is_accurate: wet_bulb_is_accurate,
+			humidity,
+			condition: None,
 		}
+		.into())
 	}
 }
 
-impl FromArgs<4> for HourlyTemps {
-	fn from_args([hour, temp, feels_like, humidity]: [String; 4]) -> Self {
-		let hour = hour.parse().expect("Could not parse an hour argument");
-		let temp = temp
-			.parse()
-			.expect("Could not parse a dry-bulb temperature argument");
-		let feels_like = feels_like
-			.parse()
-			.expect("Could not parse a feels-like temperature argument");
-		let humidity: u8 = humidity
-			.parse()
-			.expect("Could not parse a humidity argument");
-		let wet_bulb = if humidity == 100 {
-			temp
-		} else {
-			(wet_bulb_temp(temp as f32 / 100.0, humidity as f32) * 100.0).round() as i32
-		};
-		let wet_bulb_is_accurate = (-2000..5000).contains(&temp) && humidity >= 5;
+/// The shape of [`HourlyTemps`] in input, before the wet-bulb temperature is derived.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HourlyTempsInput {
+	/// Hour of the day
+	hour: u8,
+	/// When the hour starts
+	#[serde(default)]
+	time: Option<ZonedTime>,
+	/// Dry-bulb temperature in centidegrees Celsius
+	temp: Option<i32>,
+	/// Feels-like temperature in centidegrees Celsius
+	feels_like: Option<i32>,
+	/// Relative humidity in percent
+	humidity: Option<u8>,
+	/// What the weather is doing
+	#[serde(default)]
+	condition: Option<Condition>,
+}
+
+impl From<HourlyTempsInput> for HourlyTemps {
+	fn from(input: HourlyTempsInput) -> Self {
+		let HourlyTempsInput {
+			hour,
+			time,
+			temp,
+			feels_like,
+			humidity,
+			condition,
+		} = input;
+		let wet_bulb = temp.zip(humidity).map(|(temp, humidity)| {
+			if humidity == 100 {
+				temp
+			} else {
+				(wet_bulb_temp(temp as f32 / 100.0, humidity as f32) * 100.0).round() as i32
+			}
+		});
+		let wet_bulb_is_accurate = temp
+			.zip(humidity)
+			.is_some_and(|(temp, humidity)| (-2000..5000).contains(&temp) && humidity >= 5);
 		Self {
 			hour,
+			time,
 			temp,
 			feels_like,
 			wet_bulb,
 			_wet_bulb_is_accurate: wet_bulb_is_accurate,
+			condition,
 		}
 	}
 }
@@ -171,6 +316,8 @@ fn wet_bulb_temp(temp: f32, humidity: f32) -> f32 {
 
 #[cfg(test)]
 mod tests {
+	use image::RgbImage;
+
 	use super::*;
 
 	#[test]
@@ -190,4 +337,72 @@ mod tests {
 			assert_eq!(result, wet_bulb);
 		}
 	}
+	#[test]
+	fn json_matches_args() {
+		let from_json: Vec<HourlyTemps> = data_from_json(
+			r#"[{"hour": 13, "temp": 3000, "feels_like": 3200, "humidity": 50}]"#.as_bytes(),
+		)
+		.unwrap();
+		let from_args: Vec<HourlyTemps> =
+			data_from_args(["13", "3000", "3200", "50"].map(String::from).to_vec()).unwrap();
+		assert_eq!(from_json[0].wet_bulb, from_args[0].wet_bulb);
+		assert_eq!(from_json[0].feels_like, Some(3200));
+	}
+	#[test]
+	fn gap_in_line() {
+		let data: Vec<HourlyTemps> = data_from_json(
+			r#"[
+				{"hour": 1, "temp": 1000, "feels_like": null, "humidity": null},
+				{"hour": 2, "temp": 1000, "feels_like": null, "humidity": null},
+				{"hour": 3, "temp": null, "feels_like": null, "humidity": 50},
+				{"hour": 4, "temp": 1000, "feels_like": null, "humidity": null},
+				{"hour": 5, "temp": 1000, "feels_like": null, "humidity": null}
+			]"#
+			.as_bytes(),
+		)
+		.unwrap();
+		assert_eq!(data[2].temp, None);
+		assert_eq!(data[2].wet_bulb, None);
+		let font_data: &[u8] = include_bytes!("../../RobotoCondensed-Regular.ttf");
+		let font = FontRef::try_from_slice(font_data).unwrap();
+		let options = ChartOptions::default();
+		let image = create::<RgbImage>(&font, &options, data).unwrap();
+		let has_line =
+			|x: u32| (0..image.height()).any(|y| *image.get_pixel(x, y) == options.theme.temp);
+		let column = |index: u32| PADDING.left + index * HORIZONTAL_SPACING;
+		// Drawn between the first two hours, but not either side of the missing one
+		assert!(has_line(column(0) + HORIZONTAL_SPACING / 2));
+		assert!((column(1) + 2..column(3) - 1).all(|x| !has_line(x)));
+		assert!(has_line(column(3) + HORIZONTAL_SPACING / 2));
+	}
+	#[test]
+	fn argument_errors() {
+		let result = data_from_args::<HourlyTemps, 4>(
+			["13", "3000", "3200", "50", "14", "3100", "warm", "50"]
+				.map(String::from)
+				.to_vec(),
+		);
+		assert!(matches!(
+			result,
+			Err(Error::InvalidArgument {
+				index: 1,
+				name: "feels-like temperature",
+				..
+			})
+		));
+		let result = data_from_args::<HourlyTemps, 4>(["13", "3000"].map(String::from).to_vec());
+		assert!(matches!(
+			result,
+			Err(Error::ArgumentCount {
+				chunk_size: 4,
+				count: 2
+			})
+		));
+		let font_data: &[u8] = include_bytes!("../../RobotoCondensed-Regular.ttf");
+		let font = FontRef::try_from_slice(font_data).unwrap();
+		assert!(matches!(
+			create::<RgbImage>(&font, &ChartOptions::default(), Vec::new()),
+			Err(Error::NoData)
+		));
+	}
 }
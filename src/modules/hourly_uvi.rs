@@ -1,15 +1,23 @@
+use std::io::Read;
+
 use ab_glyph::FontRef;
-use image::RgbImage;
-use itertools::Itertools;
+use image::Rgb;
+use serde::Deserialize;
 
 use crate::{
-	colours,
+	canvas::Canvas,
 	common_types::{GradientPoint, MultiPointGradient, Range},
-	drawing::{
-		draw_graph_bars_with_gradient, draw_outer_lines, fill_canvas, horizontal_lines_and_labels,
-		vertical_lines_and_labels, MarkIntervals, Padding, Spacing,
+	drawing::{MarkIntervals, Padding, Spacing},
+	error::Error,
+	from_args::{data_from_args, parse_arg, parse_optional_arg, FromArgs, InvalidArgument},
+	from_json::data_from_json,
+	generic_graph::{
+		padding_with_caption, AxisGridLabels, Chart, ChartOptions, GradientBars, NightShading,
+		NoData,
 	},
-	util::next_multiple,
+	text_box::TextSegment,
+	time::ZonedTime,
+	util::next_multiple_of_hundredths,
 };
 
 const PADDING: Padding = Padding {
@@ -24,90 +32,132 @@ const SPACING: Spacing = Spacing {
 };
 const FONT_SCALE: ab_glyph::PxScale = ab_glyph::PxScale { x: 14.0, y: 14.0 };
 
-pub fn parse_and_create(font: &FontRef, args: Vec<String>) -> RgbImage {
-	let data = data_from_args(args);
-	create(font, data)
+pub fn parse_and_create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	args: Vec<String>,
+) -> Result<C, Error> {
+	let data = data_from_args(args)?;
+	create(font, options, data)
 }
 
-pub fn create(font: &FontRef, data: Vec<HourlyUvi>) -> RgbImage {
-	let max_chart_uvi = next_multiple(
-		data.iter().map(|hour| hour.uvi).max().unwrap_or(0) as i32,
-		1,
-	);
-	let width = data.len() as u32 * SPACING.horizontal + PADDING.horizontal();
-	let height = max_chart_uvi as u32 * SPACING.vertical / 100 + PADDING.vertical();
-	let mut canvas = RgbImage::new(width, height);
-	fill_canvas(&mut canvas, colours::BACKGROUND);
-	draw_outer_lines(&mut canvas, PADDING);
-	vertical_lines_and_labels(
-		&mut canvas,
-		data.iter().map(|datum| datum.hour),
-		MarkIntervals::new(1, 2),
-		font,
-		FONT_SCALE,
-		PADDING,
-		SPACING.horizontal,
-		true,
+/// Reads a JSON array of objects with `hour` and `uvi` fields. The UV index is multiplied by 100, and may be `null` where it is missing.
+pub fn parse_json_and_create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	reader: impl Read,
+) -> Result<C, Error> {
+	let data = data_from_json(reader)?;
+	create(font, options, data)
+}
+
+pub fn create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	data: Vec<HourlyUvi>,
+) -> Result<C, Error> {
+	if data.is_empty() {
+		return Err(Error::NoData);
+	}
+	let max_chart_uvi = next_multiple_of_hundredths(
+		data.iter().filter_map(|hour| hour.uvi).max().unwrap_or(0) as i32,
+		100,
 	);
-	horizontal_lines_and_labels(
-		&mut canvas,
-		Range::new(0, max_chart_uvi),
-		MarkIntervals::new(1, 1),
+
+	let caption = options.caption(
+		&[TextSegment::new("UV index", Rgb(options.theme.uvi_medium))],
 		font,
-		FONT_SCALE,
-		PADDING,
-		SPACING.vertical,
+		data.len(),
+		SPACING,
 	);
+	// Bars take up a whole column each, so there is one more column than there are points.
+	let mut chart = Chart::new(
+		data.len() + 1,
+		max_chart_uvi as u32,
+		SPACING,
+		padding_with_caption(PADDING, caption.as_ref()),
+		options,
+	)?;
+
+	let times: Option<Vec<ZonedTime>> = data.iter().map(|datum| datum.time).collect();
+	if let (Some(daylight), Some(times)) = (&options.daylight, &times) {
+		chart.draw(NightShading { daylight, times });
+	}
+	chart.draw(AxisGridLabels {
+		vertical_intervals: MarkIntervals::new(100, 100)?,
+		horizontal_intervals: MarkIntervals::new(1, 2)?,
+		vertical_label_range: Range::new(0, max_chart_uvi)?,
+		horizontal_labels: data.iter().map(|datum| datum.hour),
+		horizontal_times: times,
+		horizontal_labels_centered: true,
+		font: font.clone(),
+		font_scale: FONT_SCALE,
+	});
+	if options.no_data {
+		chart.draw(NoData {
+			missing: data.iter().map(|hour| hour.uvi.is_none()),
+			bars: true,
+		});
+	}
 	let gradient = MultiPointGradient::new(vec![
-		GradientPoint::from_rgb(PADDING.below, colours::UVI_LOW),
+		GradientPoint::from_rgb(PADDING.below, options.theme.uvi_low),
 		GradientPoint::from_rgb(
 			PADDING.below + SPACING.vertical * 9 / 2,
-			colours::UVI_MEDIUM,
+			options.theme.uvi_medium,
 		),
-		GradientPoint::from_rgb(PADDING.below + SPACING.vertical * 9, colours::UVI_HIGH),
-	]);
-	draw_graph_bars_with_gradient(
-		&mut canvas,
-		data.iter().map(|day| day.uvi as i32),
-		&gradient,
-		PADDING,
-		SPACING,
-	);
-	canvas
+		GradientPoint::from_rgb(PADDING.below + SPACING.vertical * 9, options.theme.uvi_high),
+	])?;
+	chart.draw(GradientBars {
+		gradient,
+		data: data.iter().map(|hour| hour.uvi.map(i32::from)),
+		baseline: 0,
+		max: max_chart_uvi,
+	});
+	if let Some(caption) = caption {
+		chart.draw(caption);
+	}
+	Ok(chart.into_canvas())
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct HourlyUvi {
 	/// Hour of the day
 	hour: u8,
+	/// When the hour starts, which labels the time axis with days when every hour has one
+	#[serde(default)]
+	time: Option<ZonedTime>,
 	/// UV index * 100
-	uvi: u16,
+	uvi: Option<u16>,
 }
 
 impl HourlyUvi {
-	pub fn new(hour: u8, uvi: f32) -> Self {
-		let uvi = (uvi * 100.0).round() as u16;
-		Self { hour, uvi }
+	/// Takes `None` where the UV index is missing.
+	pub fn new(hour: u8, uvi: impl Into<Option<f32>>) -> Self {
+		let uvi = uvi.into().map(|uvi| (uvi * 100.0).round() as u16);
+		Self {
+			hour,
+			time: None,
+			uvi,
+		}
 	}
-}
-
-impl HourlyUvi {
-	fn from_args(hour: String, uvi: String) -> Self {
-		let hour = hour.parse().expect("Could not parse an hour argument");
-		let uvi = uvi.parse().expect("Could not parse a UV index argument");
-		Self { hour, uvi }
+	/// Sets when the hour starts, so the time axis can show days.
+	pub fn with_time(self, time: impl Into<Option<ZonedTime>>) -> Self {
+		Self {
+			time: time.into(),
+			..self
+		}
 	}
 }
 
-fn data_from_args(args: Vec<String>) -> Vec<HourlyUvi> {
-	const CHUNK_SIZE: usize = 2;
-	let mut data = Vec::with_capacity(args.len() / CHUNK_SIZE);
-	for mut item in args.into_iter().chunks(CHUNK_SIZE).into_iter() {
-		let (hour, uvi) = item
-			.next_tuple()
-			.unwrap_or_else(|| panic!("Arguments could not be grouped into {CHUNK_SIZE}s"));
-		let uvi_datum = HourlyUvi::from_args(hour, uvi);
-		data.push(uvi_datum);
+impl FromArgs<2> for HourlyUvi {
+	fn from_args([hour, uvi]: [String; 2]) -> Result<Self, InvalidArgument> {
+		let hour = parse_arg(hour, "hour")?;
+		let uvi = parse_optional_arg(uvi, "UV index")?;
+		Ok(Self {
+			hour,
+			time: None,
+			uvi,
+		})
 	}
-	data
 }
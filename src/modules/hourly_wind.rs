@@ -1,15 +1,25 @@
+use std::io::Read;
+
 use ab_glyph::FontRef;
-use image::{Rgb, RgbImage};
+use image::Rgb;
+use imageproc::rect::Rect;
 use itertools::Itertools;
+use serde::Deserialize;
 
 use crate::{
-	colours,
+	canvas::Canvas,
 	common_types::{GradientPoint, MultiPointGradient, Point, Range},
-	drawing::{
-		draw_graph_bars_with_gradient, draw_line_segment, draw_outer_lines, fill_canvas,
-		horizontal_lines_and_labels, vertical_lines_and_labels, MarkIntervals, Padding, Spacing,
+	drawing::{draw_scaled_line, MarkIntervals, Padding, Pen, Spacing},
+	error::Error,
+	from_args::{data_from_args, parse_arg, parse_optional_arg, FromArgs, InvalidArgument},
+	from_json::data_from_json,
+	generic_graph::{
+		padding_with_caption, AxisGridLabels, Chart, ChartElement, ChartOptions, DirectionStyle,
+		GradientBars, NightShading, NoData,
 	},
-	util::next_multiple,
+	text_box::TextSegment,
+	time::ZonedTime,
+	util::next_multiple_of_hundredths,
 };
 
 const PADDING: Padding = Padding {
@@ -18,150 +28,215 @@ const PADDING: Padding = Padding {
 	left: 21,
 	right: 3,
 };
-const SPACING: Spacing = Spacing {
-	horizontal: 8,
-	vertical: 5,
-};
+const HORIZONTAL_SPACING: u32 = 8;
 const DIRECTION_GRAPH_HEIGHT: u32 = 13;
+/// Arrows are drawn longer the stronger the wind, up to the height of the direction graph at this speed in cm/s.
+const LONGEST_ARROW_SPEED: f32 = 1500.0;
+/// Barbs count the speed in 5 knot steps.
+const CENTIMETRES_PER_SECOND_PER_KNOT: f32 = 1852.0 / 36.0;
 const FONT_SCALE: ab_glyph::PxScale = ab_glyph::PxScale { x: 14.0, y: 14.0 };
 
-pub fn parse_and_create(font: &FontRef, args: Vec<String>) -> RgbImage {
-	let data = data_from_args(args);
-	create(font, data)
+pub fn parse_and_create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	args: Vec<String>,
+) -> Result<C, Error> {
+	let data = data_from_args(args)?;
+	create(font, options, data)
+}
+
+/// Reads a JSON array of objects with `hour`, `wind_speed`, `wind_gust` and `wind_direction` fields. Speeds are in cm/s and the direction is in degrees, where 0 is north and 90 is east. Values may be `null` where they are missing.
+pub fn parse_json_and_create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	reader: impl Read,
+) -> Result<C, Error> {
+	let data = data_from_json(reader)?;
+	create(font, options, data)
 }
 
-pub fn create(font: &FontRef, data: Vec<HourlyWind>) -> RgbImage {
-	let max_chart_speed = next_multiple(
+pub fn create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	data: Vec<HourlyWind>,
+) -> Result<C, Error> {
+	if data.is_empty() {
+		return Err(Error::NoData);
+	}
+	if let Some(index) = data
+		.iter()
+		.position(|hour| hour.wind_direction.is_some_and(|direction| direction > 360))
+	{
+		return Err(Error::InvalidDatum {
+			index,
+			field: "wind direction",
+		});
+	}
+	let unit = options.units.speed;
+	let axis = unit.axis();
+	let spacing = Spacing {
+		horizontal: HORIZONTAL_SPACING,
+		vertical: axis.spacing,
+	};
+	let max_chart_speed = next_multiple_of_hundredths(
 		data.iter()
 			.flat_map(|hour| [hour.wind_speed, hour.wind_gust])
+			.flatten()
+			.map(|speed| unit.convert(speed as i32))
 			.max()
-			.unwrap_or(0) as i32,
-		5,
-	);
-	let width = data.len() as u32 * SPACING.horizontal + PADDING.horizontal();
-	let height = max_chart_speed as u32 * SPACING.vertical / 100 + PADDING.vertical();
-	let mut canvas = RgbImage::new(width, height);
-	fill_canvas(&mut canvas, colours::BACKGROUND);
-	draw_outer_lines(&mut canvas, PADDING);
-	vertical_lines_and_labels(
-		&mut canvas,
-		data.iter().map(|data| data.hour),
-		MarkIntervals::new(1, 2),
-		font,
-		FONT_SCALE,
-		PADDING,
-		SPACING.horizontal,
-		true,
+			.unwrap_or(0),
+		axis.rounding,
 	);
-	horizontal_lines_and_labels(
-		&mut canvas,
-		Range::new(0, max_chart_speed),
-		MarkIntervals::new(5, 5),
+
+	let unit_text = format!(" ({}), with direction below", unit.symbol());
+	let caption = options.caption(
+		&[
+			TextSegment::new("Wind speed", Rgb(options.theme.wind_medium)),
+			TextSegment::plain(" and ", &options.theme),
+			TextSegment::new("gusts", Rgb(options.theme.gust_medium)),
+			TextSegment::plain(&unit_text, &options.theme),
+		],
 		font,
-		FONT_SCALE,
-		PADDING,
-		SPACING.vertical,
+		data.len(),
+		spacing,
 	);
+	// Bars take up a whole column each, so there is one more column than there are points.
+	let mut chart = Chart::new(
+		data.len() + 1,
+		max_chart_speed as u32,
+		spacing,
+		padding_with_caption(PADDING, caption.as_ref()),
+		options,
+	)?;
+
+	let times: Option<Vec<ZonedTime>> = data.iter().map(|hour| hour.time).collect();
+	if let (Some(daylight), Some(times)) = (&options.daylight, &times) {
+		chart.draw(NightShading { daylight, times });
+	}
+	chart.draw(AxisGridLabels {
+		vertical_intervals: MarkIntervals::new(axis.line, axis.label)?,
+		horizontal_intervals: MarkIntervals::new(1, 2)?,
+		vertical_label_range: Range::new(0, max_chart_speed)?,
+		horizontal_labels: data.iter().map(|data| data.hour),
+		horizontal_times: times,
+		horizontal_labels_centered: true,
+		font: font.clone(),
+		font_scale: FONT_SCALE,
+	});
+	if options.no_data {
+		chart.draw(NoData {
+			missing: data
+				.iter()
+				.map(|hour| hour.wind_speed.is_none() || hour.wind_gust.is_none()),
+			bars: true,
+		});
+	}
+	// The gradients change colour at the same speeds whatever the units.
+	let gradient_height =
+		|metres_per_second: i32| PADDING.below + axis.pixels(unit.convert(metres_per_second * 100));
 	let gradient = MultiPointGradient::new(vec![
-		GradientPoint::from_rgb(PADDING.below, colours::GUST_LOW),
-		GradientPoint::from_rgb(PADDING.below + SPACING.vertical * 7, colours::GUST_MEDIUM),
-		GradientPoint::from_rgb(PADDING.below + SPACING.vertical * 14, colours::GUST_HIGH),
-		GradientPoint::from_rgb(
-			PADDING.below + SPACING.vertical * 21,
-			colours::GUST_VERY_HIGH,
-		),
-	]);
-	draw_graph_bars_with_gradient(
-		&mut canvas,
-		data.iter().map(|hour| hour.wind_gust as i32),
-		&gradient,
-		PADDING,
-		SPACING,
-	);
+		GradientPoint::from_rgb(PADDING.below, options.theme.gust_low),
+		GradientPoint::from_rgb(gradient_height(7), options.theme.gust_medium),
+		GradientPoint::from_rgb(gradient_height(14), options.theme.gust_high),
+		GradientPoint::from_rgb(gradient_height(21), options.theme.gust_very_high),
+	])?;
+	chart.draw(GradientBars {
+		gradient,
+		data: data
+			.iter()
+			.map(|hour| hour.wind_gust.map(|gust| unit.convert(gust as i32))),
+		baseline: 0,
+		max: max_chart_speed,
+	});
 	let gradient = MultiPointGradient::new(vec![
-		GradientPoint::from_rgb(PADDING.below, colours::WIND_LOW),
-		GradientPoint::from_rgb(PADDING.below + SPACING.vertical * 7, colours::WIND_MEDIUM),
-		GradientPoint::from_rgb(PADDING.below + SPACING.vertical * 14, colours::WIND_HIGH),
-		GradientPoint::from_rgb(
-			PADDING.below + SPACING.vertical * 21,
-			colours::WIND_VERY_HIGH,
-		),
-	]);
-	draw_graph_bars_with_gradient(
-		&mut canvas,
-		data.iter().map(|hour| hour.wind_speed as i32),
-		&gradient,
-		PADDING,
-		SPACING,
-	);
-	let x = PADDING.left;
-	let x2 = width - PADDING.right - 1;
-	let y = height - DIRECTION_GRAPH_HEIGHT / 2 - 3;
-	for n in (0..3).step_by(2) {
-		let y = y - 1 + n;
-		draw_line_segment(
-			&mut canvas,
-			Point { x, y },
-			Point { x: x2, y },
-			Rgb([255, 255, 255]),
-		);
+		GradientPoint::from_rgb(PADDING.below, options.theme.wind_low),
+		GradientPoint::from_rgb(gradient_height(7), options.theme.wind_medium),
+		GradientPoint::from_rgb(gradient_height(14), options.theme.wind_high),
+		GradientPoint::from_rgb(gradient_height(21), options.theme.wind_very_high),
+	])?;
+	chart.draw(GradientBars {
+		gradient,
+		data: data
+			.iter()
+			.map(|hour| hour.wind_speed.map(|speed| unit.convert(speed as i32))),
+		baseline: 0,
+		max: max_chart_speed,
+	});
+	match options.wind_directions {
+		DirectionStyle::Stripe => chart.draw(WindDirections {
+			directions: data.iter().map(|hour| hour.wind_direction),
+		}),
+		style => chart.draw(WindSymbols {
+			data: data
+				.iter()
+				.map(|hour| hour.wind_direction.zip(hour.wind_speed)),
+			barbs: style == DirectionStyle::Barbs,
+		}),
+	}
+	if let Some(caption) = caption {
+		chart.draw(caption);
 	}
-	draw_wind_directions(&mut canvas, data.iter().map(|hour| hour.wind_direction));
-	canvas
+	Ok(chart.into_canvas())
 }
 
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct HourlyWind {
 	/// Hour of the day
-	hour: u8,
+	pub(crate) hour: u8,
+	/// When the hour starts, which labels the time axis with days when every hour has one
+	#[serde(default)]
+	pub(crate) time: Option<ZonedTime>,
 	/// Wind speed in cm/s
-	wind_speed: u16,
+	pub(crate) wind_speed: Option<u16>,
 	/// Wind gust speed in cm/s
-	wind_gust: u16,
+	pub(crate) wind_gust: Option<u16>,
 	/// Wind direction in degrees, where 0 is north and 90 is east
-	wind_direction: u16,
+	pub(crate) wind_direction: Option<u16>,
 }
 
 impl HourlyWind {
-	fn from_args(
-		hour: String,
-		wind_speed: String,
-		wind_gust: String,
-		wind_direction: String,
+	/// Takes speeds in m/s, and `None` for any value that is missing.
+	pub fn new(
+		hour: u8,
+		wind_speed: impl Into<Option<f32>>,
+		wind_gust: impl Into<Option<f32>>,
+		wind_direction: impl Into<Option<u16>>,
 	) -> Self {
-		let hour = hour.parse().expect("Could not parse an hour argument");
-		let wind_speed = wind_speed
-			.parse()
-			.expect("Could not parse a wind speed argument");
-		let wind_gust = wind_gust
-			.parse()
-			.expect("Could not parse a wind gust argument");
-		let wind_direction = wind_direction
-			.parse()
-			.expect("Could not parse a wind direction argument");
+		let to_centimetres = |speed: f32| (speed * 100.0).round() as u16;
 		Self {
 			hour,
-			wind_speed,
-			wind_gust,
-			wind_direction,
+			time: None,
+			wind_speed: wind_speed.into().map(to_centimetres),
+			wind_gust: wind_gust.into().map(to_centimetres),
+			wind_direction: wind_direction.into().map(|direction| direction % 360),
+		}
+	}
+	/// Sets when the hour starts, so the time axis can show days.
+	pub fn with_time(self, time: impl Into<Option<ZonedTime>>) -> Self {
+		Self {
+			time: time.into(),
+			..self
 		}
 	}
 }
 
-fn data_from_args(args: Vec<String>) -> Vec<HourlyWind> {
-	const CHUNK_SIZE: usize = 4;
-	let mut data = Vec::with_capacity(args.len() / CHUNK_SIZE);
-	for mut item in args.into_iter().chunks(CHUNK_SIZE).into_iter() {
-		let (hour, wind_speed, wind_gust, wind_direction) = item
-			.next_tuple()
-			.unwrap_or_else(|| panic!("Arguments could not be grouped into {CHUNK_SIZE}s"));
-		data.push(HourlyWind::from_args(
+impl FromArgs<4> for HourlyWind {
+	fn from_args(
+		[hour, wind_speed, wind_gust, wind_direction]: [String; 4],
+	) -> Result<Self, InvalidArgument> {
+		let hour = parse_arg(hour, "hour")?;
+		let wind_speed = parse_optional_arg(wind_speed, "wind speed")?;
+		let wind_gust = parse_optional_arg(wind_gust, "wind gust")?;
+		let wind_direction = parse_optional_arg(wind_direction, "wind direction")?;
+		Ok(Self {
 			hour,
+			time: None,
 			wind_speed,
 			wind_gust,
 			wind_direction,
-		));
+		})
 	}
-	data
 }
 
 struct AngleInterpolation {
@@ -272,29 +347,208 @@ impl Iterator for WindDirectionPixelColumn {
 	}
 }
 
-fn draw_wind_directions(canvas: &mut RgbImage, directions: impl IntoIterator<Item = u16>) {
-	let height = canvas.height();
-	for (hour_count, (start, end)) in directions.into_iter().tuple_windows().enumerate() {
-		for (x, direction) in
-			AngleInterpolation::new(start, end, SPACING.horizontal as u16).enumerate()
-		{
-			for (y, colour) in WindDirectionPixelColumn::new(direction).enumerate() {
+/// The strip below the chart showing wind direction, with a gap next to hours where it is missing.
+struct WindDirections<D>
+where
+	D: Iterator<Item = Option<u16>>,
+{
+	directions: D,
+}
+
+impl<D> ChartElement for WindDirections<D>
+where
+	D: Iterator<Item = Option<u16>>,
+{
+	fn draw<C: Canvas>(self, chart: &mut Chart<C>) {
+		let (width, height) = (chart.canvas.width(), chart.canvas.height());
+		let scale = chart.scale;
+		let x = chart.padding.left;
+		let x2 = width - chart.padding.right - scale;
+		let y = height - (DIRECTION_GRAPH_HEIGHT / 2 + 3) * scale;
+		for n in (0..3).step_by(2) {
+			let y = y - scale + n * scale;
+			draw_scaled_line(
+				&mut chart.canvas,
+				Point { x, y },
+				Point { x: x2, y },
+				chart.theme.text,
+				scale,
+			);
+		}
+		let spacing = chart.spacing.horizontal;
+		// Every pixel column of the strip, empty between hours where the direction is missing
+		let columns: Vec<Vec<Option<[u8; 3]>>> = self
+			.directions
+			.tuple_windows()
+			.flat_map(|(start, end)| match (start, end) {
+				(Some(start), Some(end)) => AngleInterpolation::new(start, end, spacing as u16)
+					.map(|direction| WindDirectionPixelColumn::new(direction).collect())
+					.collect(),
+				_ => vec![Vec::new(); spacing as usize],
+			})
+			.collect();
+		for y in 0..DIRECTION_GRAPH_HEIGHT as usize {
+			// Pixels of the same colour next to each other are filled together.
+			let mut x = chart.padding.left;
+			for (colour, run) in &columns
+				.iter()
+				.map(|column| column.get(y).copied().flatten())
+				.chunk_by(|colour| *colour)
+			{
+				let width = run.count() as u32;
 				if let Some(colour) = colour {
-					canvas.put_pixel(
-						hour_count as u32 * SPACING.horizontal + PADDING.left + x as u32,
-						height - 16 + y as u32,
+					chart.canvas.fill_rect(
+						Rect::at(x as i32, (height - (16 - y as u32) * scale) as i32)
+							.of_size(width, scale),
 						Rgb(colour),
 					);
 				}
+				x += width;
 			}
 		}
 	}
 }
 
+/// An arrow or barb below each hour's bar, in place of [`WindDirections`]. They are spaced out to every second hour when the columns are too narrow for one each, and left out where the direction or speed is missing.
+struct WindSymbols<D>
+where
+	D: Iterator<Item = Option<(u16, u16)>>,
+{
+	/// Direction in degrees and speed in cm/s
+	data: D,
+	barbs: bool,
+}
+
+impl<D> ChartElement for WindSymbols<D>
+where
+	D: Iterator<Item = Option<(u16, u16)>>,
+{
+	fn draw<C: Canvas>(self, chart: &mut Chart<C>) {
+		let scale = chart.scale;
+		let column = chart.spacing.horizontal / scale;
+		let interval = (DIRECTION_GRAPH_HEIGHT + 2).div_ceil(column) as usize;
+		let y = chart.canvas.height() - (DIRECTION_GRAPH_HEIGHT / 2 + 3) * scale;
+		let colour = chart.theme.text;
+		let background = chart.theme.background;
+		for (index, symbol) in self.data.enumerate().step_by(interval) {
+			let Some((direction, speed)) = symbol else {
+				continue;
+			};
+			let centre = Point {
+				x: chart.padding.left
+					+ index as u32 * chart.spacing.horizontal
+					+ chart.spacing.horizontal / 2,
+				y,
+			};
+			let pen = &mut Pen::new(&mut chart.canvas, centre, scale);
+			// Towards where the wind comes from, with y pointing down
+			let angle = (direction as f32).to_radians();
+			let upwind = (angle.sin(), -angle.cos());
+			if self.barbs {
+				draw_barb(pen, upwind, speed, colour, background);
+			} else {
+				draw_arrow(pen, upwind, speed, colour);
+			}
+		}
+	}
+}
+
+/// Along `direction` by `length`, then `across` to the right of it.
+fn offset((x, y): (f32, f32), direction: (f32, f32), length: f32, across: f32) -> (f32, f32) {
+	(
+		x + direction.0 * length - direction.1 * across,
+		y + direction.1 * length + direction.0 * across,
+	)
+}
+
+/// An arrow blowing downwind through the centre.
+fn draw_arrow<C: Canvas>(pen: &mut Pen<C>, upwind: (f32, f32), speed: u16, colour: Rgb<u8>) {
+	let length =
+		DIRECTION_GRAPH_HEIGHT as f32 * (0.4 + 0.6 * (speed as f32 / LONGEST_ARROW_SPEED).min(1.0));
+	let downwind = (-upwind.0, -upwind.1);
+	let tail = offset((0.0, 0.0), downwind, -length / 2.0, 0.0);
+	let head = offset((0.0, 0.0), downwind, length / 2.0, 0.0);
+	pen.line(&[tail, head], 1.2, colour);
+	pen.line(
+		&[
+			offset(head, downwind, -3.5, -2.5),
+			head,
+			offset(head, downwind, -3.5, 2.5),
+		],
+		1.2,
+		colour,
+	);
+}
+
+/// Speed in cm/s to the nearest 5 knots, which is as fine as barbs go.
+fn barb_knots(speed: u16) -> u32 {
+	(speed as f32 / CENTIMETRES_PER_SECOND_PER_KNOT / 5.0).round() as u32 * 5
+}
+
+/// A staff through the centre of the column pointing into the wind, with barbs on its far end: a pennant for every 50 knots, a full barb for every 10 and a half barb for 5. Calm is a circle.
+fn draw_barb<C: Canvas>(
+	pen: &mut Pen<C>,
+	upwind: (f32, f32),
+	speed: u16,
+	colour: Rgb<u8>,
+	background: Rgb<u8>,
+) {
+	let knots = barb_knots(speed);
+	if knots == 0 {
+		pen.dot(0.0, 0.0, 5.0, colour);
+		pen.dot(0.0, 0.0, 2.5, background);
+		return;
+	}
+	let half_length = (DIRECTION_GRAPH_HEIGHT as f32 - 1.0) / 2.0;
+	let station = offset((0.0, 0.0), upwind, -half_length, 0.0);
+	let end = offset((0.0, 0.0), upwind, half_length, 0.0);
+	pen.line(&[station, end], 1.2, colour);
+	// Barbs lean into the wind and stand out on the side to the right of the staff, as seen from the station, which is where the lower pressure is in the northern hemisphere.
+	let (pennants, tens, fives) = (knots / 50, knots % 50 / 10, knots % 10 / 5);
+	let mut along = half_length;
+	for _ in 0..pennants {
+		let base = offset((0.0, 0.0), upwind, along, 0.0);
+		pen.line(
+			&[
+				base,
+				offset(base, upwind, 0.0, 5.0),
+				offset(base, upwind, -2.5, 0.0),
+				base,
+			],
+			1.2,
+			colour,
+		);
+		along -= 3.0;
+	}
+	for _ in 0..tens {
+		let base = offset((0.0, 0.0), upwind, along, 0.0);
+		pen.line(&[base, offset(base, upwind, 1.5, 5.0)], 1.2, colour);
+		along -= 2.5;
+	}
+	if fives > 0 {
+		// Set in from the end when it is the only barb, so it isn't read as a full one
+		if along == half_length {
+			along -= 2.5;
+		}
+		let base = offset((0.0, 0.0), upwind, along, 0.0);
+		pen.line(&[base, offset(base, upwind, 0.75, 2.5)], 1.2, colour);
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 
+	#[test]
+	fn barb_rounding() {
+		assert_eq!(barb_knots(0), 0);
+		// 2 knots is calm, 3 knots gets a half barb
+		assert_eq!(barb_knots(103), 0);
+		assert_eq!(barb_knots(154), 5);
+		// 25 m/s is 48.6 knots
+		assert_eq!(barb_knots(2500), 50);
+	}
+
 	#[test]
 	fn interpolate_angle() {
 		assert_eq!(
@@ -1,15 +1,18 @@
+use std::io::Read;
+
 use ab_glyph::FontRef;
-use image::RgbImage;
-use itertools::Itertools;
+use serde::Deserialize;
 
 use crate::{
-	colours,
+	canvas::Canvas,
 	common_types::Range,
-	drawing::{
-		draw_graph_bars, draw_outer_lines, fill_canvas, horizontal_lines_and_labels,
-		vertical_lines_and_labels, MarkIntervals, Padding, Spacing,
-	},
-	util::next_multiple,
+	drawing::{MarkIntervals, Padding, Spacing},
+	error::Error,
+	from_args::{data_from_args, parse_arg, parse_optional_arg, FromArgs, InvalidArgument},
+	from_json::data_from_json,
+	generic_graph::{padding_with_caption, AxisGridLabels, Chart, ChartOptions, NoData, SolidBars},
+	text_box::TextSegment,
+	util::next_multiple_of_hundredths,
 };
 
 const PADDING: Padding = Padding {
@@ -18,74 +21,117 @@ const PADDING: Padding = Padding {
 	left: 21,
 	right: 19,
 };
-const SPACING: Spacing = Spacing {
-	horizontal: 6,
-	vertical: 16,
-};
+const HORIZONTAL_SPACING: u32 = 6;
 const FONT_SCALE: ab_glyph::PxScale = ab_glyph::PxScale { x: 14.0, y: 14.0 };
 
-pub fn parse_and_create(font: &FontRef, args: Vec<String>) -> RgbImage {
-	let data = data_from_args(args);
-	create(font, data)
+pub fn parse_and_create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	args: Vec<String>,
+) -> Result<C, Error> {
+	let data = data_from_args(args)?;
+	create(font, options, data)
+}
+
+/// Reads a JSON array of objects with `minute` and `precipitation` fields. Precipitation is in mm / h * 100, and may be `null` where it is missing.
+pub fn parse_json_and_create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	reader: impl Read,
+) -> Result<C, Error> {
+	let data = data_from_json(reader)?;
+	create(font, options, data)
 }
 
-pub fn create(font: &FontRef, data: Vec<MinutelyPrecipitation>) -> RgbImage {
-	let max_chart_precipitation = next_multiple(
+pub fn create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	data: Vec<MinutelyPrecipitation>,
+) -> Result<C, Error> {
+	if data.is_empty() {
+		return Err(Error::NoData);
+	}
+	let unit = options.units.precipitation;
+	let axis = unit.axis();
+	let spacing = Spacing {
+		horizontal: HORIZONTAL_SPACING,
+		vertical: axis.spacing,
+	};
+	let max_chart_precipitation = next_multiple_of_hundredths(
 		data.iter()
-			.map(|minute| minute.precipitation as i32)
+			.filter_map(|minute| minute.precipitation)
+			.map(|precipitation| unit.convert(precipitation as i32))
 			.max()
 			.unwrap_or(0),
-		1,
+		axis.rounding,
 	) as u32;
-	let width = data.len() as u32 * SPACING.horizontal + PADDING.horizontal();
-	let height = max_chart_precipitation * SPACING.vertical / 100 + PADDING.vertical();
-	let mut canvas = RgbImage::new(width, height);
-	fill_canvas(&mut canvas, colours::BACKGROUND);
-	draw_outer_lines(&mut canvas, PADDING);
-	vertical_lines_and_labels(
-		&mut canvas,
-		data.iter().map(|minute| minute.minute),
-		MarkIntervals::new(3, 3),
-		font,
-		FONT_SCALE,
-		PADDING,
-		SPACING.horizontal,
-		true,
-	);
-	horizontal_lines_and_labels(
-		&mut canvas,
-		Range::new(0, max_chart_precipitation as i32),
-		MarkIntervals::new(1, 1),
+
+	let unit_text = format!(" ({}/h)", unit.symbol());
+	let caption = options.caption(
+		&[
+			TextSegment::new("Precipitation", options.theme.rain),
+			TextSegment::plain(&unit_text, &options.theme),
+		],
 		font,
-		FONT_SCALE,
-		PADDING,
-		SPACING.vertical,
-	);
-	draw_graph_bars(
-		&mut canvas,
-		data.into_iter()
-			.map(|minutely| minutely.precipitation as i32),
-		colours::RAIN,
-		PADDING,
-		SPACING,
+		data.len(),
+		spacing,
 	);
-	canvas
+	// Bars take up a whole column each, so there is one more column than there are points.
+	let mut chart = Chart::new(
+		data.len() + 1,
+		max_chart_precipitation,
+		spacing,
+		padding_with_caption(PADDING, caption.as_ref()),
+		options,
+	)?;
+
+	chart.draw(AxisGridLabels {
+		vertical_intervals: MarkIntervals::new(axis.line, axis.label)?,
+		horizontal_intervals: MarkIntervals::new(3, 3)?,
+		vertical_label_range: Range::new(0, max_chart_precipitation as i32)?,
+		horizontal_labels: data.iter().map(|minute| minute.minute),
+		horizontal_times: None,
+		horizontal_labels_centered: true,
+		font: font.clone(),
+		font_scale: FONT_SCALE,
+	});
+	if options.no_data {
+		chart.draw(NoData {
+			missing: data.iter().map(|minute| minute.precipitation.is_none()),
+			bars: true,
+		});
+	}
+	chart.draw(SolidBars {
+		colour: options.theme.rain,
+		data: data.into_iter().map(|minutely| {
+			minutely
+				.precipitation
+				.map(|precipitation| unit.convert(precipitation as i32))
+		}),
+		baseline: 0,
+		max: max_chart_precipitation as i32,
+	});
+	if let Some(caption) = caption {
+		chart.draw(caption);
+	}
+	Ok(chart.into_canvas())
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct MinutelyPrecipitation {
 	/// Minute of the hour
 	minute: u8,
 	/// Precipitation in mm / h * 100
-	precipitation: u16,
+	precipitation: Option<u16>,
 }
 
 impl MinutelyPrecipitation {
-	fn from_args(minute: String, precipitation: String) -> Self {
-		let minute = minute.parse().expect("Could not parse a minute argument");
+	/// Takes precipitation in mm / h, or `None` where it is missing.
+	pub fn new(minute: u8, precipitation: impl Into<Option<f32>>) -> Self {
 		let precipitation = precipitation
-			.parse()
-			.expect("Could not parse a precipitation argument");
+			.into()
+			.map(|precipitation| (precipitation * 100.0).round() as u16);
 		Self {
 			minute,
 			precipitation,
@@ -93,15 +139,13 @@ impl MinutelyPrecipitation {
 	}
 }
 
-fn data_from_args(args: Vec<String>) -> Vec<MinutelyPrecipitation> {
-	const CHUNK_SIZE: usize = 2;
-	let mut data = Vec::with_capacity(args.len() / CHUNK_SIZE);
-	for mut item in args.into_iter().chunks(CHUNK_SIZE).into_iter() {
-		let (minute, precipitation) = item
-			.next_tuple()
-			.unwrap_or_else(|| panic!("Arguments could not be grouped into {CHUNK_SIZE}s"));
-		let precipitation_datum = MinutelyPrecipitation::from_args(minute, precipitation);
-		data.push(precipitation_datum);
+impl FromArgs<2> for MinutelyPrecipitation {
+	fn from_args([minute, precipitation]: [String; 2]) -> Result<Self, InvalidArgument> {
+		let minute = parse_arg(minute, "minute")?;
+		let precipitation = parse_optional_arg(precipitation, "precipitation")?;
+		Ok(Self {
+			minute,
+			precipitation,
+		})
 	}
-	data
 }
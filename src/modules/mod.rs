@@ -1,8 +1,14 @@
+pub mod daily_precipitation;
 pub mod daily_temp;
+pub mod daily_temp_range;
+pub mod hourly_clouds;
 pub mod hourly_composite;
+pub mod hourly_humidity;
 pub mod hourly_pop;
 pub mod hourly_precipitation;
+pub mod hourly_pressure;
 pub mod hourly_temp;
 pub mod hourly_uvi;
 pub mod hourly_wind;
 pub mod minutely_precipitation;
+pub mod wind_rose;
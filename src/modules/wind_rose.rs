@@ -0,0 +1,351 @@
+use std::io::Read;
+
+use ab_glyph::FontRef;
+use image::Rgb;
+use imageproc::rect::Rect;
+
+use crate::{
+	canvas::Canvas,
+	common_types::{GradientPoint, MultiPointGradient, Point},
+	drawing::{draw_compass_labels, draw_radial_grid, draw_sector, polar_point, Padding, Spacing},
+	error::Error,
+	from_args::data_from_args,
+	from_json::data_from_json,
+	generic_graph::{padding_with_caption, scale_font, Chart, ChartElement, ChartOptions, Sectors},
+	modules::hourly_wind::HourlyWind,
+	text_box::TextSegment,
+	units::Speed,
+};
+
+/// Room for the compass labels around the rose, and for the legend on the right.
+const PADDING: Padding = Padding {
+	above: 16,
+	below: 16,
+	left: 16,
+	right: 16 + LEGEND_WIDTH,
+};
+const LEGEND_WIDTH: u32 = 72;
+const LEGEND_ROW_HEIGHT: u32 = 16;
+const LEGEND_SWATCH_SIZE: u32 = 9;
+const RADIUS: u32 = 64;
+const FONT_SCALE: ab_glyph::PxScale = ab_glyph::PxScale { x: 14.0, y: 14.0 };
+/// The slowest speed of each class in cm/s. Anything slower is calm, and has no direction.
+const SPEED_CLASSES: [u16; 6] = [50, 300, 600, 900, 1400, 2100];
+/// How much of its share of the circle each sector fills, so there are gaps between them.
+const SECTOR_FILL: f32 = 0.8;
+/// Candidate percentages between the rings, of which the smallest that needs no more than [`MAX_RINGS`] is used.
+const RING_STEPS: [u32; 7] = [1, 2, 5, 10, 20, 25, 50];
+const MAX_RINGS: u32 = 4;
+/// Where the ring labels go, in degrees clockwise from north
+const RING_LABEL_BEARING: f32 = 112.5;
+
+/// Makes a wind rose from hourly wind, in the same format as [`hourly_wind`](super::hourly_wind): hour, wind speed, wind gust, wind direction, repeat. Gusts are not used.
+pub fn parse_and_create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	args: Vec<String>,
+) -> Result<C, Error> {
+	let data = data_from_args(args)?;
+	create(font, options, data)
+}
+
+/// Reads the same JSON as [`hourly_wind`](super::hourly_wind::parse_json_and_create).
+pub fn parse_json_and_create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	reader: impl Read,
+) -> Result<C, Error> {
+	let data = data_from_json(reader)?;
+	create(font, options, data)
+}
+
+/// How often the wind blew from each direction over the whole period, split into sectors by [`ChartOptions::wind_rose_sectors`]. Each sector is a stack of speed classes, slowest in the middle, coloured like the hourly wind bars, and reaches out to the share of hours it covers. Calm hours have no direction, so they are only counted in the legend.
+pub fn create<C: Canvas>(
+	font: &FontRef,
+	options: &ChartOptions,
+	data: Vec<HourlyWind>,
+) -> Result<C, Error> {
+	if data.is_empty() {
+		return Err(Error::NoData);
+	}
+	if let Some(index) = data
+		.iter()
+		.position(|hour| hour.wind_direction.is_some_and(|direction| direction > 360))
+	{
+		return Err(Error::InvalidDatum {
+			index,
+			field: "wind direction",
+		});
+	}
+	let frequencies = Frequencies::new(&data, options.wind_rose_sectors);
+	if frequencies.total == 0 {
+		return Err(Error::NoData);
+	}
+	let unit = options.units.speed;
+	// The same colours as the hourly wind bars at the slowest speed of each class
+	let gradient = MultiPointGradient::new(vec![
+		GradientPoint::from_rgb(0, options.theme.wind_low),
+		GradientPoint::from_rgb(700, options.theme.wind_medium),
+		GradientPoint::from_rgb(1400, options.theme.wind_high),
+		GradientPoint::from_rgb(2100, options.theme.wind_very_high),
+	])?;
+	let colours = SPEED_CLASSES.map(|start| Rgb(gradient.get_colour(start as u32)));
+	// Laid out as a chart of one column as wide as the rose, and as tall as it is wide.
+	let spacing = Spacing {
+		horizontal: 2 * RADIUS,
+		vertical: 1,
+	};
+
+	let unit_text = format!(" ({})", unit.symbol());
+	let caption = options.caption(
+		&[
+			TextSegment::plain("Share of hours from each direction, by ", &options.theme),
+			TextSegment::new("wind speed", Rgb(options.theme.wind_medium)),
+			TextSegment::plain(&unit_text, &options.theme),
+		],
+		font,
+		1,
+		spacing,
+	);
+	let mut chart = Chart::new(
+		2,
+		2 * RADIUS * 100,
+		spacing,
+		padding_with_caption(PADDING, caption.as_ref()),
+		options,
+	)?;
+
+	chart.draw(Rose {
+		frequencies: &frequencies,
+		colours,
+		font: font.clone(),
+	});
+	chart.draw(Legend {
+		frequencies: &frequencies,
+		colours,
+		unit,
+		font: font.clone(),
+	});
+	if let Some(caption) = caption {
+		chart.draw(caption);
+	}
+	Ok(chart.into_canvas())
+}
+
+/// How many hours the wind blew from each sector in each speed class, and how many were calm. Hours without a speed, or without a direction when it wasn't calm, aren't counted.
+struct Frequencies {
+	/// By sector clockwise from north, then by speed class from slowest
+	sectors: Vec<[u32; SPEED_CLASSES.len()]>,
+	calm: u32,
+	total: u32,
+}
+
+impl Frequencies {
+	fn new(data: &[HourlyWind], sectors: Sectors) -> Self {
+		let count = sectors.count();
+		let mut frequencies = Self {
+			sectors: vec![[0; SPEED_CLASSES.len()]; count as usize],
+			calm: 0,
+			total: 0,
+		};
+		for hour in data {
+			let Some(speed) = hour.wind_speed else {
+				continue;
+			};
+			let Some(class) = SPEED_CLASSES.iter().rposition(|&start| speed >= start) else {
+				frequencies.calm += 1;
+				frequencies.total += 1;
+				continue;
+			};
+			let Some(direction) = hour.wind_direction else {
+				continue;
+			};
+			// To the nearest sector, with north in the first
+			let sector = (direction as u32 * count + 180) / 360 % count;
+			frequencies.sectors[sector as usize][class] += 1;
+			frequencies.total += 1;
+		}
+		frequencies
+	}
+	/// `hours` as a percentage of all of them.
+	fn percent(&self, hours: u32) -> f32 {
+		hours as f32 * 100.0 / self.total as f32
+	}
+}
+
+/// The percentage between rings, and how many rings it takes to reach `max`.
+fn rings(max: f32) -> (u32, u32) {
+	let step = RING_STEPS
+		.into_iter()
+		.find(|&step| (max / step as f32).ceil() as u32 <= MAX_RINGS)
+		.unwrap_or(100);
+	(step, ((max / step as f32).ceil() as u32).max(1))
+}
+
+/// The sectors of the rose, over a grid of rings labelled in percent and spokes every 45 degrees, with the compass points around it.
+struct Rose<'d, 'f> {
+	frequencies: &'d Frequencies,
+	colours: [Rgb<u8>; SPEED_CLASSES.len()],
+	font: FontRef<'f>,
+}
+
+impl ChartElement for Rose<'_, '_> {
+	fn draw<C: Canvas>(self, chart: &mut Chart<C>) {
+		let scale = chart.scale;
+		let radius = (RADIUS * scale) as f32;
+		let centre = Point {
+			x: chart.padding.left as f32 + radius,
+			y: chart.padding.above as f32 + radius,
+		};
+		let font_scale = scale_font(FONT_SCALE, scale);
+		let most = self
+			.frequencies
+			.sectors
+			.iter()
+			.map(|classes| self.frequencies.percent(classes.iter().sum()))
+			.fold(0.0, f32::max);
+		let (step, ring_count) = rings(most);
+		let outer_percent = (step * ring_count) as f32;
+		let ring_radii: Vec<_> = (1..=ring_count)
+			.map(|ring| radius * ring as f32 / ring_count as f32)
+			.collect();
+		draw_radial_grid(
+			&mut chart.canvas,
+			centre,
+			&ring_radii,
+			8,
+			chart.theme.grid_lines,
+			scale,
+		);
+
+		let sector_width = 360.0 / self.frequencies.sectors.len() as f32;
+		for (index, classes) in self.frequencies.sectors.iter().enumerate() {
+			let bearing = index as f32 * sector_width;
+			let (start, end) = (
+				bearing - sector_width * SECTOR_FILL / 2.0,
+				bearing + sector_width * SECTOR_FILL / 2.0,
+			);
+			let mut inner = 0.0;
+			for (&hours, &colour) in classes.iter().zip(&self.colours) {
+				if hours == 0 {
+					continue;
+				}
+				let outer = inner + radius * self.frequencies.percent(hours) / outer_percent;
+				draw_sector(&mut chart.canvas, centre, inner, outer, start, end, colour);
+				inner = outer;
+			}
+		}
+
+		for (ring, &ring_radius) in ring_radii.iter().enumerate() {
+			let point = polar_point(centre, ring_radius, RING_LABEL_BEARING);
+			chart.canvas.draw_text(
+				&format!("{}%", step * (ring as u32 + 1)),
+				chart.theme.text,
+				point.x as i32 + scale as i32,
+				point.y as i32,
+				&self.font,
+				font_scale,
+			);
+		}
+		draw_compass_labels(
+			&mut chart.canvas,
+			centre,
+			radius + (PADDING.left / 2) as f32 * scale as f32,
+			&self.font,
+			font_scale,
+			chart.theme.text,
+		);
+	}
+}
+
+/// The speed classes to the right of the rose, fastest at the top like the stacks they colour, with the share of calm hours below.
+struct Legend<'d, 'f> {
+	frequencies: &'d Frequencies,
+	colours: [Rgb<u8>; SPEED_CLASSES.len()],
+	unit: Speed,
+	font: FontRef<'f>,
+}
+
+impl ChartElement for Legend<'_, '_> {
+	fn draw<C: Canvas>(self, chart: &mut Chart<C>) {
+		let scale = chart.scale;
+		let font_scale = scale_font(FONT_SCALE, scale);
+		let left = chart.canvas.width() - chart.padding.right + PADDING.left * scale;
+		let row_height = LEGEND_ROW_HEIGHT * scale;
+		let swatch = LEGEND_SWATCH_SIZE * scale;
+		let text_left = left + swatch + 4 * scale;
+		let mut top = chart.padding.above;
+		let mut row = |canvas: &mut C, text: &str| {
+			let (_text_width, text_height) =
+				imageproc::drawing::text_size(font_scale, &self.font, text);
+			canvas.draw_text(
+				text,
+				chart.theme.text,
+				text_left as i32,
+				(top + row_height / 2) as i32 - text_height as i32 / 2,
+				&self.font,
+				font_scale,
+			);
+			top += row_height;
+			top - row_height
+		};
+
+		row(&mut chart.canvas, self.unit.symbol());
+		let speed_text = |speed: u16| {
+			let converted = self.unit.convert(speed as i32);
+			if converted % 100 == 0 {
+				format!("{}", converted / 100)
+			} else {
+				format!("{:.1}", converted as f32 / 100.0)
+			}
+		};
+		for (class, &start) in SPEED_CLASSES.iter().enumerate().rev() {
+			let text = match SPEED_CLASSES.get(class + 1) {
+				Some(&end) => format!("{}–{}", speed_text(start), speed_text(end)),
+				None => format!("{}+", speed_text(start)),
+			};
+			let row_top = row(&mut chart.canvas, &text);
+			chart.canvas.fill_rect(
+				Rect::at(left as i32, (row_top + (row_height - swatch) / 2) as i32)
+					.of_size(swatch, swatch),
+				self.colours[class],
+			);
+		}
+		let calm = self.frequencies.percent(self.frequencies.calm).round();
+		row(&mut chart.canvas, &format!("Calm {calm}%"));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn frequencies() {
+		let data = [
+			HourlyWind::new(0, 0.3, None, 90),
+			HourlyWind::new(1, 2.0, None, 355),
+			HourlyWind::new(2, 5.0, None, 12),
+			HourlyWind::new(3, 25.0, None, 11),
+			HourlyWind::new(4, None, None, 11),
+			HourlyWind::new(5, 25.0, None, None),
+		];
+		let frequencies = Frequencies::new(&data, Sectors::Sixteen);
+		assert_eq!(frequencies.calm, 1);
+		assert_eq!(frequencies.sectors[0], [1, 0, 0, 0, 0, 1]);
+		// North north east starts at 11.25 degrees
+		assert_eq!(frequencies.sectors[1], [0, 1, 0, 0, 0, 0]);
+		assert_eq!(frequencies.percent(frequencies.calm), 25.0);
+		let frequencies = Frequencies::new(&data, Sectors::Eight);
+		assert_eq!(frequencies.sectors[0], [1, 1, 0, 0, 0, 1]);
+	}
+
+	#[test]
+	fn ring_steps() {
+		assert_eq!(rings(3.5), (1, 4));
+		assert_eq!(rings(4.5), (2, 3));
+		assert_eq!(rings(37.0), (10, 4));
+		assert_eq!(rings(0.0), (1, 1));
+		assert_eq!(rings(100.0), (25, 4));
+	}
+}
@@ -0,0 +1,79 @@
+//! Adapters that turn weather API responses into the data the modules take.
+
+pub mod one_call;
+pub mod open_meteo;
+
+use crate::time::civil_from_days;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+const SECONDS_PER_HOUR: i64 = 60 * 60;
+
+/// Hour of the day of a Unix timestamp, for a timezone offset in seconds.
+pub(crate) fn hour_of_day(timestamp: i64, timezone_offset: i32) -> u8 {
+	((timestamp + timezone_offset as i64).rem_euclid(SECONDS_PER_DAY) / 3600) as u8
+}
+
+/// Minute of the hour of a Unix timestamp, for a timezone offset in seconds.
+pub(crate) fn minute_of_hour(timestamp: i64, timezone_offset: i32) -> u8 {
+	((timestamp + timezone_offset as i64).rem_euclid(3600) / 60) as u8
+}
+
+/// Puts `left_out` in place of every hour missing between the timestamps of `data`, which is in order, so the charts show a gap there instead of drawing the hours either side next to each other.
+pub(crate) fn fill_left_out_hours<T>(
+	data: impl IntoIterator<Item = T>,
+	timestamp: impl Fn(&T) -> i64,
+	left_out: impl Fn(i64) -> T,
+) -> Vec<T> {
+	let mut filled = Vec::new();
+	let mut next = None;
+	for datum in data {
+		let timestamp = timestamp(&datum);
+		if let Some(mut next) = next {
+			// Within half an hour is taken to be the expected hour.
+			while timestamp - next >= SECONDS_PER_HOUR / 2 {
+				filled.push(left_out(next));
+				next += SECONDS_PER_HOUR;
+			}
+		}
+		next = Some(timestamp + SECONDS_PER_HOUR);
+		filled.push(datum);
+	}
+	filled
+}
+
+/// Day of the month of a Unix timestamp, for a timezone offset in seconds.
+pub(crate) fn day_of_month(timestamp: i64, timezone_offset: i32) -> u8 {
+	let days = (timestamp + timezone_offset as i64).div_euclid(SECONDS_PER_DAY);
+	civil_from_days(days).2
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn dates() {
+		assert_eq!(civil_from_days(0), (1970, 1, 1));
+		assert_eq!(civil_from_days(-1), (1969, 12, 31));
+		assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+		// 2024-03-31 01:30 UTC is already the next day at UTC+3.
+		assert_eq!(day_of_month(1_711_848_600, 3 * 3600), 31);
+		assert_eq!(day_of_month(1_711_848_600 + 22 * 3600, 3 * 3600), 1);
+		assert_eq!(hour_of_day(1_711_848_600, -3600), 0);
+		assert_eq!(minute_of_hour(1_711_848_600, 0), 30);
+	}
+
+	#[test]
+	fn left_out_hours() {
+		let filled = fill_left_out_hours(
+			[0, 3600, 4 * 3600, 5 * 3600 + 60],
+			|&time| time,
+			|time| -time,
+		);
+		assert_eq!(
+			filled,
+			[0, 3600, -2 * 3600, -3 * 3600, 4 * 3600, 5 * 3600 + 60]
+		);
+		assert!(fill_left_out_hours(Vec::<i64>::new(), |&time| time, |time| time).is_empty());
+	}
+}
@@ -0,0 +1,420 @@
+//! [One Call API 3.0](https://openweathermap.org/api/one-call-3) responses, requested with `units=metric`.
+
+use std::io::Read;
+
+use serde::Deserialize;
+
+use crate::{
+	error::Error,
+	from_json::data_from_json,
+	icons::Condition,
+	modules::{
+		daily_precipitation::DailyPrecipitation,
+		daily_temp::DailyTemps,
+		daily_temp_range::DailyTempRange,
+		hourly_clouds::HourlyClouds,
+		hourly_composite::{Extras, HourlyComposite},
+		hourly_humidity::HourlyHumidity,
+		hourly_pop::HourlyPop,
+		hourly_precipitation::HourlyPrecipitation,
+		hourly_pressure::HourlyPressure,
+		hourly_temp::HourlyTemps,
+		hourly_uvi::HourlyUvi,
+		hourly_wind::HourlyWind,
+		minutely_precipitation::MinutelyPrecipitation,
+	},
+	sources::{day_of_month, fill_left_out_hours, minute_of_hour},
+	sun::{Daylight, Twilight},
+	time::TimeZone,
+};
+
+/// The parts of a One Call response the charts use. Any section left out of the request with `exclude` is treated as empty.
+#[derive(Debug, Clone, Deserialize)]
+pub struct OneCall {
+	/// Degrees north
+	pub lat: f64,
+	/// Degrees east
+	pub lon: f64,
+	/// Shift in seconds from UTC for the requested location
+	pub timezone_offset: i32,
+	#[serde(default)]
+	minutely: Vec<Minutely>,
+	#[serde(default)]
+	hourly: Vec<Hourly>,
+	#[serde(default)]
+	daily: Vec<Daily>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Minutely {
+	dt: i64,
+	/// Precipitation in mm / h
+	precipitation: f32,
+}
+
+/// An hour of the forecast, where any value can be missing. Hours left out of the response are filled in with the default, which is missing everything.
+#[derive(Debug, Clone, Default, Deserialize)]
+struct Hourly {
+	dt: i64,
+	/// Temperature in °C
+	temp: Option<f32>,
+	/// Feels-like temperature in °C
+	feels_like: Option<f32>,
+	/// Sea-level pressure in hPa
+	pressure: Option<f32>,
+	/// Relative humidity in percent
+	humidity: Option<i32>,
+	uvi: Option<f32>,
+	/// Cloud cover in percent
+	clouds: Option<f32>,
+	/// Visibility in metres, up to 10 km
+	visibility: Option<f32>,
+	/// Wind speed in m/s
+	wind_speed: Option<f32>,
+	/// Wind gust speed in m/s, missing if there are no gusts
+	wind_gust: Option<f32>,
+	/// Wind direction in degrees, where 0 is north and 90 is east
+	wind_deg: Option<u16>,
+	/// Probability of precipitation (0-1)
+	pop: Option<f32>,
+	/// Left out of the response when there is none
+	#[serde(default = "LastHour::zero")]
+	rain: Option<LastHour>,
+	/// Left out of the response when there is none
+	#[serde(default = "LastHour::zero")]
+	snow: Option<LastHour>,
+	#[serde(default)]
+	weather: Vec<Weather>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct LastHour {
+	/// Amount in mm
+	#[serde(rename = "1h")]
+	amount: f32,
+}
+
+impl LastHour {
+	/// What a left out `rain` or `snow` means, as opposed to a missing hour.
+	fn zero() -> Option<Self> {
+		Some(Self { amount: 0.0 })
+	}
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Daily {
+	dt: i64,
+	/// Unix timestamp, 0 on days the sun doesn't rise
+	#[serde(default)]
+	sunrise: i64,
+	/// Unix timestamp, 0 on days the sun doesn't set
+	#[serde(default)]
+	sunset: i64,
+	temp: DailyTemperatures,
+	feels_like: Option<PartsOfDay>,
+	/// Probability of precipitation (0-1)
+	#[serde(default)]
+	pop: f32,
+	/// Amount of rain in mm
+	rain: Option<f32>,
+	/// Amount of snow in mm
+	snow: Option<f32>,
+	#[serde(default)]
+	weather: Vec<Weather>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Weather {
+	/// Condition code
+	id: u16,
+	/// Icon name, which ends in `n` at night
+	#[serde(default)]
+	icon: String,
+}
+
+/// The condition of the first, main, weather entry.
+fn condition(weather: &[Weather]) -> Option<Condition> {
+	let main = weather.first()?;
+	Condition::from_open_weather(main.id, main.icon.ends_with('n'))
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct DailyTemperatures {
+	/// Minimum temperature in °C
+	min: f32,
+	/// Maximum temperature in °C
+	max: f32,
+	#[serde(flatten)]
+	parts: Option<PartsOfDay>,
+}
+
+/// Temperatures in °C through the day
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct PartsOfDay {
+	morn: f32,
+	day: f32,
+	eve: f32,
+	night: f32,
+}
+
+impl PartsOfDay {
+	fn in_order(self) -> [f32; 4] {
+		[self.morn, self.day, self.eve, self.night]
+	}
+}
+
+impl OneCall {
+	/// Reads a response as saved from the API.
+	pub fn from_reader(reader: impl Read) -> Result<Self, Error> {
+		data_from_json(reader)
+	}
+	/// Every hour from the first to the last, including any left out of the response.
+	fn hourly(&self) -> Vec<Hourly> {
+		fill_left_out_hours(
+			self.hourly.iter().cloned(),
+			|hour| hour.dt,
+			|dt| Hourly {
+				dt,
+				..Hourly::default()
+			},
+		)
+	}
+	pub fn minutely_precipitation(&self, time_zone: TimeZone) -> Vec<MinutelyPrecipitation> {
+		self.minutely
+			.iter()
+			.map(|minute| {
+				MinutelyPrecipitation::new(
+					minute_of_hour(minute.dt, time_zone.offset_at(minute.dt)),
+					minute.precipitation,
+				)
+			})
+			.collect()
+	}
+	pub fn hourly_temps(&self, time_zone: TimeZone) -> Vec<HourlyTemps> {
+		self.hourly()
+			.iter()
+			.map(|hour| {
+				let time = time_zone.at(hour.dt);
+				HourlyTemps::new(time.hour(), hour.temp, hour.feels_like, hour.humidity)
+					.with_time(time)
+					.with_condition(condition(&hour.weather))
+			})
+			.collect()
+	}
+	pub fn hourly_humidity(&self, time_zone: TimeZone) -> Vec<HourlyHumidity> {
+		self.hourly()
+			.iter()
+			.map(|hour| {
+				let time = time_zone.at(hour.dt);
+				HourlyHumidity::new(time.hour(), hour.temp, hour.humidity).with_time(time)
+			})
+			.collect()
+	}
+	pub fn hourly_pop(&self, time_zone: TimeZone) -> Vec<HourlyPop> {
+		self.hourly()
+			.iter()
+			.map(|hour| {
+				let time = time_zone.at(hour.dt);
+				HourlyPop::new(time.hour(), hour.pop).with_time(time)
+			})
+			.collect()
+	}
+	pub fn hourly_precipitation(&self, time_zone: TimeZone) -> Vec<HourlyPrecipitation> {
+		self.hourly()
+			.iter()
+			.map(|hour| {
+				let time = time_zone.at(hour.dt);
+				let [rain, snow] =
+					[hour.rain, hour.snow].map(|amount| amount.map(|last| last.amount));
+				HourlyPrecipitation::new(time.hour(), rain, snow).with_time(time)
+			})
+			.collect()
+	}
+	pub fn hourly_pressure(&self, time_zone: TimeZone) -> Vec<HourlyPressure> {
+		self.hourly()
+			.iter()
+			.map(|hour| {
+				let time = time_zone.at(hour.dt);
+				HourlyPressure::new(time.hour(), hour.pressure).with_time(time)
+			})
+			.collect()
+	}
+	pub fn hourly_wind(&self, time_zone: TimeZone) -> Vec<HourlyWind> {
+		self.hourly()
+			.iter()
+			.map(|hour| {
+				let time = time_zone.at(hour.dt);
+				HourlyWind::new(time.hour(), hour.wind_speed, hour.wind_gust, hour.wind_deg)
+					.with_time(time)
+			})
+			.collect()
+	}
+	pub fn hourly_uvi(&self, time_zone: TimeZone) -> Vec<HourlyUvi> {
+		self.hourly()
+			.iter()
+			.map(|hour| {
+				let time = time_zone.at(hour.dt);
+				HourlyUvi::new(time.hour(), hour.uvi).with_time(time)
+			})
+			.collect()
+	}
+	pub fn hourly_clouds(&self, time_zone: TimeZone) -> Vec<HourlyClouds> {
+		self.hourly()
+			.iter()
+			.map(|hour| {
+				let time = time_zone.at(hour.dt);
+				HourlyClouds::new(time.hour(), hour.clouds, hour.visibility).with_time(time)
+			})
+			.collect()
+	}
+	/// With humidity and clouds only where `extras` asks for them.
+	pub fn hourly_composite(&self, time_zone: TimeZone, extras: Extras) -> HourlyComposite {
+		HourlyComposite {
+			temp: self.hourly_temps(time_zone),
+			humidity: extras.humidity.then(|| self.hourly_humidity(time_zone)),
+			pop: self.hourly_pop(time_zone),
+			precipitation: self.hourly_precipitation(time_zone),
+			clouds: extras.clouds.then(|| self.hourly_clouds(time_zone)),
+			wind: self.hourly_wind(time_zone),
+			uvi: self.hourly_uvi(time_zone),
+		}
+	}
+	pub fn daily_temps(&self, time_zone: TimeZone) -> Vec<DailyTemps> {
+		self.daily
+			.iter()
+			.map(|day| {
+				DailyTemps::new(
+					day_of_month(day.dt, time_zone.offset_at(day.dt)),
+					day.temp.min,
+					day.temp.max,
+				)
+				.with_condition(condition(&day.weather))
+			})
+			.collect()
+	}
+	/// One Call doesn't give apparent minimums and maximums, so they are taken from the apparent temperatures through the day.
+	pub fn daily_temp_range(&self, time_zone: TimeZone) -> Vec<DailyTempRange> {
+		self.daily
+			.iter()
+			.map(|day| {
+				let mut range = DailyTempRange::new(
+					day_of_month(day.dt, time_zone.offset_at(day.dt)),
+					day.temp.min,
+					day.temp.max,
+				);
+				if let Some(parts) = day.temp.parts {
+					range = range.with_parts(parts.in_order());
+				}
+				if let Some(feels_like) = day.feels_like {
+					let parts = feels_like.in_order();
+					range = range.with_feels_like(
+						parts.into_iter().fold(f32::INFINITY, f32::min),
+						parts.into_iter().fold(f32::NEG_INFINITY, f32::max),
+					);
+				}
+				range
+			})
+			.collect()
+	}
+	pub fn daily_precipitation(&self, time_zone: TimeZone) -> Vec<DailyPrecipitation> {
+		self.daily
+			.iter()
+			.map(|day| {
+				DailyPrecipitation::new(
+					day_of_month(day.dt, time_zone.offset_at(day.dt)),
+					day.rain.unwrap_or(0.0),
+					day.snow.unwrap_or(0.0),
+					day.pop,
+				)
+			})
+			.collect()
+	}
+	/// Daylight calculated for the requested location.
+	pub fn daylight(&self, twilight: Twilight) -> Daylight {
+		Daylight::Location {
+			latitude: self.lat,
+			longitude: self.lon,
+			twilight,
+		}
+	}
+	/// Daylight between each day's sunrise and sunset.
+	pub fn sun_times(&self) -> Daylight {
+		Daylight::SunTimes(
+			self.daily
+				.iter()
+				.map(|day| (day.sunrise, day.sunset))
+				.collect(),
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn hours_and_units() {
+		let response = OneCall::from_reader(
+			r#"{
+				"lat": 52.1,
+				"lon": 5.1,
+				"timezone": "Europe/Amsterdam",
+				"timezone_offset": 3600,
+				"hourly": [
+					{"dt": 1711926000, "temp": 8.5, "wind_speed": 4.5, "wind_gust": 9.2, "wind_deg": 180, "rain": {"1h": 1.5}},
+					{"dt": 1711929600, "temp": 8.0, "wind_speed": 3.0, "wind_deg": 200, "snow": {"1h": 0.25}},
+					{"dt": 1711936800, "temp": 7.0, "wind_speed": 2.0, "wind_gust": 3.5, "wind_deg": 360, "rain": null}
+				],
+				"daily": [
+					{"dt": 1711926000, "temp": {"min": -1.5, "max": 10.0}, "rain": 2.5}
+				]
+			}"#
+			.as_bytes(),
+		)
+		.unwrap();
+		let time_zone = TimeZone::Fixed(response.timezone_offset);
+		let wind = response.hourly_wind(time_zone);
+		// 23:00Z is midnight at UTC+1, and the hour left out at 01:00Z is filled in as missing.
+		assert_eq!(
+			wind.iter()
+				.map(|hour| (
+					hour.hour,
+					hour.wind_speed,
+					hour.wind_gust,
+					hour.wind_direction
+				))
+				.collect::<Vec<_>>(),
+			vec![
+				(0, Some(450), Some(920), Some(180)),
+				(1, Some(300), None, Some(200)),
+				(2, None, None, None),
+				(3, Some(200), Some(350), Some(0)),
+			]
+		);
+		let precipitation = response.hourly_precipitation(time_zone);
+		assert_eq!(
+			precipitation
+				.iter()
+				.map(|hour| (hour.rain, hour.snow))
+				.collect::<Vec<_>>(),
+			vec![
+				(Some(150), Some(0)),
+				(Some(0), Some(25)),
+				(None, None),
+				(None, Some(0)),
+			]
+		);
+		// 2024-03-31T23:00Z is already April 1st at UTC+1.
+		let daily_temps = response.daily_temps(time_zone);
+		assert_eq!(daily_temps[0].day, 1);
+		assert_eq!(
+			(daily_temps[0].temp_min, daily_temps[0].temp_max),
+			(Some(-150), Some(1000))
+		);
+		let daily_precipitation = response.daily_precipitation(time_zone);
+		assert_eq!(
+			(daily_precipitation[0].rain, daily_precipitation[0].snow),
+			(Some(250), Some(0))
+		);
+	}
+}
@@ -0,0 +1,583 @@
+//! [Open-Meteo forecast](https://open-meteo.com/en/docs) responses, requested with the default units.
+//!
+//! Open-Meteo returns each variable as its own array, so every datum is assembled from the same index across the arrays. Missing values (`null`) are left missing in the charts, but a missing array is an error.
+
+use std::io::Read;
+
+use serde::Deserialize;
+
+use crate::{
+	error::Error,
+	from_json::data_from_json,
+	icons::Condition,
+	modules::{
+		daily_precipitation::DailyPrecipitation,
+		daily_temp::DailyTemps,
+		daily_temp_range::DailyTempRange,
+		hourly_clouds::HourlyClouds,
+		hourly_composite::{Extras, HourlyComposite},
+		hourly_humidity::HourlyHumidity,
+		hourly_pop::HourlyPop,
+		hourly_precipitation::HourlyPrecipitation,
+		hourly_pressure::HourlyPressure,
+		hourly_temp::HourlyTemps,
+		hourly_uvi::HourlyUvi,
+		hourly_wind::HourlyWind,
+		minutely_precipitation::MinutelyPrecipitation,
+	},
+	sources::{day_of_month, hour_of_day, minute_of_hour},
+	sun::{Daylight, Twilight},
+	time::{TimeZone, ZonedTime},
+};
+
+/// How many mm of water one cm of fresh snow is taken to hold.
+const SNOW_CM_TO_MM: f32 = 10.0 / 7.0;
+const KM_PER_HOUR_TO_M_PER_SECOND: f32 = 1.0 / 3.6;
+
+type Column = Option<Vec<Option<f32>>>;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OpenMeteo {
+	/// Degrees north
+	pub latitude: f64,
+	/// Degrees east
+	pub longitude: f64,
+	/// Shift in seconds from UTC for the requested timezone
+	pub utc_offset_seconds: i32,
+	/// Overrides `utc_offset_seconds` for Unix timestamps, which can then be shown with daylight saving time.
+	#[serde(skip)]
+	time_zone: Option<TimeZone>,
+	hourly: Option<Hourly>,
+	daily: Option<Daily>,
+	minutely_15: Option<Minutely15>,
+}
+
+/// A time as either the default local ISO 8601 string or a Unix timestamp (`timeformat=unixtime`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum Time {
+	Iso8601(String),
+	Unix(i64),
+}
+
+impl Time {
+	/// Gets a number out of the ISO 8601 string or calculates it from the Unix timestamp.
+	fn part(
+		&self,
+		iso_range: std::ops::Range<usize>,
+		from_unix: fn(i64, i32) -> u8,
+		time_zone: TimeZone,
+	) -> Result<u8, Error> {
+		match self {
+			Self::Iso8601(time) => time
+				.get(iso_range)
+				.and_then(|part| part.parse().ok())
+				.ok_or_else(|| Error::InvalidTime(time.clone())),
+			Self::Unix(timestamp) => Ok(from_unix(*timestamp, time_zone.offset_at(*timestamp))),
+		}
+	}
+	/// The Unix timestamp, which ISO 8601 strings can't give without knowing their offset.
+	fn timestamp(&self) -> Result<i64, Error> {
+		match self {
+			Self::Iso8601(time) => Err(Error::InvalidTime(time.clone())),
+			Self::Unix(timestamp) => Ok(*timestamp),
+		}
+	}
+	/// Only Unix timestamps say when they are, since ISO 8601 strings come without an offset.
+	fn zoned(&self, time_zone: TimeZone) -> Option<ZonedTime> {
+		match self {
+			Self::Iso8601(_) => None,
+			Self::Unix(timestamp) => Some(time_zone.at(*timestamp)),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Hourly {
+	time: Vec<Time>,
+	/// °C
+	temperature_2m: Column,
+	/// °C
+	apparent_temperature: Column,
+	/// Percent
+	relative_humidity_2m: Column,
+	/// Sea-level pressure in hPa
+	pressure_msl: Column,
+	/// Percent
+	cloud_cover: Column,
+	/// Percent
+	cloud_cover_low: Column,
+	/// Percent
+	cloud_cover_mid: Column,
+	/// Percent
+	cloud_cover_high: Column,
+	/// Metres
+	visibility: Column,
+	/// km/h
+	wind_speed_10m: Column,
+	/// km/h
+	wind_gusts_10m: Column,
+	/// Degrees, where 0 is north and 90 is east
+	wind_direction_10m: Column,
+	uv_index: Column,
+	/// Percent
+	precipitation_probability: Column,
+	/// mm
+	rain: Column,
+	/// cm
+	snowfall: Column,
+	/// WMO code
+	weather_code: Column,
+	/// 1 in daylight, 0 at night
+	is_day: Column,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Daily {
+	time: Vec<Time>,
+	/// °C
+	temperature_2m_min: Column,
+	/// °C
+	temperature_2m_max: Column,
+	/// °C
+	apparent_temperature_min: Column,
+	/// °C
+	apparent_temperature_max: Column,
+	/// mm
+	rain_sum: Column,
+	/// cm
+	snowfall_sum: Column,
+	/// Percent
+	precipitation_probability_max: Column,
+	/// WMO code
+	weather_code: Column,
+	sunrise: Option<Vec<Time>>,
+	sunset: Option<Vec<Time>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Minutely15 {
+	time: Vec<Time>,
+	/// mm over the preceding 15 minutes
+	precipitation: Column,
+}
+
+/// Gets the values of a column, failing if it was not requested.
+fn column<'c>(
+	column: &'c Column,
+	section: &'static str,
+	name: &'static str,
+) -> Result<Values<'c>, Error> {
+	let values = column
+		.as_deref()
+		.ok_or(Error::MissingField { section, name })?;
+	Ok(Values(values))
+}
+
+/// Gets the values of a column that can be left out.
+fn optional_column(column: &Column) -> Option<Values<'_>> {
+	column.as_deref().map(Values)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Values<'c>(&'c [Option<f32>]);
+
+impl Values<'_> {
+	/// `None` where the value is `null` or the column is too short.
+	fn get_optional(&self, index: usize) -> Option<f32> {
+		self.0.get(index).copied().flatten()
+	}
+}
+
+/// The condition from the `weather_code` column, if it was requested. Without an `is_day` column, it is taken to be day.
+fn condition(
+	weather_code: Option<Values>,
+	is_day: Option<Values>,
+	index: usize,
+) -> Option<Condition> {
+	let code = weather_code?.get_optional(index)?;
+	let night = is_day.and_then(|is_day| is_day.get_optional(index)) == Some(0.0);
+	Condition::from_wmo(code as u8, night)
+}
+
+impl OpenMeteo {
+	/// Reads a response as saved from the API.
+	pub fn from_reader(reader: impl Read) -> Result<Self, Error> {
+		data_from_json(reader)
+	}
+	fn hourly(&self) -> Result<&Hourly, Error> {
+		self.hourly.as_ref().ok_or(Error::MissingField {
+			section: "top level",
+			name: "hourly",
+		})
+	}
+	/// Sets the timezone to show Unix timestamps in, instead of the response's fixed offset.
+	pub fn with_time_zone(self, time_zone: TimeZone) -> Self {
+		Self {
+			time_zone: Some(time_zone),
+			..self
+		}
+	}
+	fn time_zone(&self) -> TimeZone {
+		self.time_zone
+			.unwrap_or(TimeZone::Fixed(self.utc_offset_seconds))
+	}
+	/// The index, hour of the day and, for Unix timestamps, time of each hour.
+	fn hours(&self) -> Result<Vec<(usize, u8, Option<ZonedTime>)>, Error> {
+		let time_zone = self.time_zone();
+		self.hourly()?
+			.time
+			.iter()
+			.enumerate()
+			.map(|(index, time)| {
+				let hour = time.part(11..13, hour_of_day, time_zone)?;
+				Ok((index, hour, time.zoned(time_zone)))
+			})
+			.collect()
+	}
+	pub fn minutely_precipitation(&self) -> Result<Vec<MinutelyPrecipitation>, Error> {
+		let minutely = self.minutely_15.as_ref().ok_or(Error::MissingField {
+			section: "top level",
+			name: "minutely_15",
+		})?;
+		let precipitation = column(&minutely.precipitation, "minutely_15", "precipitation")?;
+		minutely
+			.time
+			.iter()
+			.enumerate()
+			.map(|(index, time)| {
+				Ok(MinutelyPrecipitation::new(
+					time.part(14..16, minute_of_hour, self.time_zone())?,
+					precipitation
+						.get_optional(index)
+						.map(|precipitation| precipitation * 4.0),
+				))
+			})
+			.collect()
+	}
+	pub fn hourly_temps(&self) -> Result<Vec<HourlyTemps>, Error> {
+		let hourly = self.hourly()?;
+		let temp = column(&hourly.temperature_2m, "hourly", "temperature_2m")?;
+		let feels_like = column(
+			&hourly.apparent_temperature,
+			"hourly",
+			"apparent_temperature",
+		)?;
+		let humidity = column(
+			&hourly.relative_humidity_2m,
+			"hourly",
+			"relative_humidity_2m",
+		)?;
+		let weather_code = optional_column(&hourly.weather_code);
+		let is_day = optional_column(&hourly.is_day);
+		Ok(self
+			.hours()?
+			.into_iter()
+			.map(|(index, hour, time)| {
+				HourlyTemps::new(
+					hour,
+					temp.get_optional(index),
+					feels_like.get_optional(index),
+					humidity
+						.get_optional(index)
+						.map(|humidity| humidity.round() as i32),
+				)
+				.with_time(time)
+				.with_condition(condition(weather_code, is_day, index))
+			})
+			.collect())
+	}
+	pub fn hourly_humidity(&self) -> Result<Vec<HourlyHumidity>, Error> {
+		let hourly = self.hourly()?;
+		let temp = column(&hourly.temperature_2m, "hourly", "temperature_2m")?;
+		let humidity = column(
+			&hourly.relative_humidity_2m,
+			"hourly",
+			"relative_humidity_2m",
+		)?;
+		Ok(self
+			.hours()?
+			.into_iter()
+			.map(|(index, hour, time)| {
+				HourlyHumidity::new(
+					hour,
+					temp.get_optional(index),
+					humidity
+						.get_optional(index)
+						.map(|humidity| humidity.round() as i32),
+				)
+				.with_time(time)
+			})
+			.collect())
+	}
+	pub fn hourly_pop(&self) -> Result<Vec<HourlyPop>, Error> {
+		let hourly = self.hourly()?;
+		let pop = column(
+			&hourly.precipitation_probability,
+			"hourly",
+			"precipitation_probability",
+		)?;
+		Ok(self
+			.hours()?
+			.into_iter()
+			.map(|(index, hour, time)| {
+				HourlyPop::new(hour, pop.get_optional(index).map(|pop| pop / 100.0)).with_time(time)
+			})
+			.collect())
+	}
+	pub fn hourly_precipitation(&self) -> Result<Vec<HourlyPrecipitation>, Error> {
+		let hourly = self.hourly()?;
+		let rain = column(&hourly.rain, "hourly", "rain")?;
+		let snowfall = column(&hourly.snowfall, "hourly", "snowfall")?;
+		Ok(self
+			.hours()?
+			.into_iter()
+			.map(|(index, hour, time)| {
+				HourlyPrecipitation::new(
+					hour,
+					rain.get_optional(index),
+					snowfall
+						.get_optional(index)
+						.map(|snowfall| snowfall * SNOW_CM_TO_MM),
+				)
+				.with_time(time)
+			})
+			.collect())
+	}
+	pub fn hourly_pressure(&self) -> Result<Vec<HourlyPressure>, Error> {
+		let pressure = column(&self.hourly()?.pressure_msl, "hourly", "pressure_msl")?;
+		Ok(self
+			.hours()?
+			.into_iter()
+			.map(|(index, hour, time)| {
+				HourlyPressure::new(hour, pressure.get_optional(index)).with_time(time)
+			})
+			.collect())
+	}
+	pub fn hourly_wind(&self) -> Result<Vec<HourlyWind>, Error> {
+		let hourly = self.hourly()?;
+		let speed = column(&hourly.wind_speed_10m, "hourly", "wind_speed_10m")?;
+		let gust = column(&hourly.wind_gusts_10m, "hourly", "wind_gusts_10m")?;
+		let direction = column(&hourly.wind_direction_10m, "hourly", "wind_direction_10m")?;
+		Ok(self
+			.hours()?
+			.into_iter()
+			.map(|(index, hour, time)| {
+				HourlyWind::new(
+					hour,
+					speed
+						.get_optional(index)
+						.map(|speed| speed * KM_PER_HOUR_TO_M_PER_SECOND),
+					gust.get_optional(index)
+						.map(|gust| gust * KM_PER_HOUR_TO_M_PER_SECOND),
+					direction
+						.get_optional(index)
+						.map(|direction| direction.round() as u16),
+				)
+				.with_time(time)
+			})
+			.collect())
+	}
+	pub fn hourly_uvi(&self) -> Result<Vec<HourlyUvi>, Error> {
+		let uvi = column(&self.hourly()?.uv_index, "hourly", "uv_index")?;
+		Ok(self
+			.hours()?
+			.into_iter()
+			.map(|(index, hour, time)| {
+				HourlyUvi::new(hour, uvi.get_optional(index)).with_time(time)
+			})
+			.collect())
+	}
+	/// Layers and visibility are only shown if they were requested.
+	pub fn hourly_clouds(&self) -> Result<Vec<HourlyClouds>, Error> {
+		let hourly = self.hourly()?;
+		let cover = column(&hourly.cloud_cover, "hourly", "cloud_cover")?;
+		let layers = [
+			&hourly.cloud_cover_low,
+			&hourly.cloud_cover_mid,
+			&hourly.cloud_cover_high,
+		]
+		.map(|layer| layer.as_deref().map(Values));
+		let visibility = hourly.visibility.as_deref().map(Values);
+		Ok(self
+			.hours()?
+			.into_iter()
+			.map(|(index, hour, time)| {
+				let clouds = HourlyClouds::new(
+					hour,
+					cover.get_optional(index),
+					visibility.and_then(|visibility| visibility.get_optional(index)),
+				)
+				.with_time(time);
+				match layers.map(|layer| layer?.get_optional(index)) {
+					[Some(low), Some(mid), Some(high)] => clouds.with_layers(low, mid, high),
+					_ => clouds,
+				}
+			})
+			.collect())
+	}
+	/// With humidity and clouds only where `extras` asks for them.
+	pub fn hourly_composite(&self, extras: Extras) -> Result<HourlyComposite, Error> {
+		Ok(HourlyComposite {
+			temp: self.hourly_temps()?,
+			humidity: extras
+				.humidity
+				.then(|| self.hourly_humidity())
+				.transpose()?,
+			pop: self.hourly_pop()?,
+			precipitation: self.hourly_precipitation()?,
+			clouds: extras.clouds.then(|| self.hourly_clouds()).transpose()?,
+			wind: self.hourly_wind()?,
+			uvi: self.hourly_uvi()?,
+		})
+	}
+	fn daily(&self) -> Result<&Daily, Error> {
+		self.daily.as_ref().ok_or(Error::MissingField {
+			section: "top level",
+			name: "daily",
+		})
+	}
+	pub fn daily_temps(&self) -> Result<Vec<DailyTemps>, Error> {
+		let daily = self.daily()?;
+		let temp_min = column(&daily.temperature_2m_min, "daily", "temperature_2m_min")?;
+		let temp_max = column(&daily.temperature_2m_max, "daily", "temperature_2m_max")?;
+		let weather_code = optional_column(&daily.weather_code);
+		daily
+			.time
+			.iter()
+			.enumerate()
+			.map(|(index, time)| {
+				Ok(DailyTemps::new(
+					time.part(8..10, day_of_month, self.time_zone())?,
+					temp_min.get_optional(index),
+					temp_max.get_optional(index),
+				)
+				.with_condition(condition(weather_code, None, index)))
+			})
+			.collect()
+	}
+	/// Open-Meteo has no temperatures for parts of the day, and the apparent temperatures are only shown when `apparent_temperature_min` and `apparent_temperature_max` were requested.
+	pub fn daily_temp_range(&self) -> Result<Vec<DailyTempRange>, Error> {
+		let daily = self.daily()?;
+		let temp_min = column(&daily.temperature_2m_min, "daily", "temperature_2m_min")?;
+		let temp_max = column(&daily.temperature_2m_max, "daily", "temperature_2m_max")?;
+		let feels_like = optional_column(&daily.apparent_temperature_min)
+			.zip(optional_column(&daily.apparent_temperature_max));
+		daily
+			.time
+			.iter()
+			.enumerate()
+			.map(|(index, time)| {
+				let range = DailyTempRange::new(
+					time.part(8..10, day_of_month, self.time_zone())?,
+					temp_min.get_optional(index),
+					temp_max.get_optional(index),
+				);
+				let feels_like = feels_like
+					.and_then(|(min, max)| min.get_optional(index).zip(max.get_optional(index)));
+				Ok(match feels_like {
+					Some((min, max)) => range.with_feels_like(min, max),
+					None => range,
+				})
+			})
+			.collect()
+	}
+	pub fn daily_precipitation(&self) -> Result<Vec<DailyPrecipitation>, Error> {
+		let daily = self.daily()?;
+		let rain = column(&daily.rain_sum, "daily", "rain_sum")?;
+		let snowfall = column(&daily.snowfall_sum, "daily", "snowfall_sum")?;
+		let pop = column(
+			&daily.precipitation_probability_max,
+			"daily",
+			"precipitation_probability_max",
+		)?;
+		daily
+			.time
+			.iter()
+			.enumerate()
+			.map(|(index, time)| {
+				Ok(DailyPrecipitation::new(
+					time.part(8..10, day_of_month, self.time_zone())?,
+					rain.get_optional(index),
+					snowfall
+						.get_optional(index)
+						.map(|snowfall| snowfall * SNOW_CM_TO_MM),
+					pop.get_optional(index).map(|pop| pop / 100.0),
+				))
+			})
+			.collect()
+	}
+	/// Daylight calculated for the requested location.
+	pub fn daylight(&self, twilight: Twilight) -> Daylight {
+		Daylight::Location {
+			latitude: self.latitude,
+			longitude: self.longitude,
+			twilight,
+		}
+	}
+	/// Daylight between each day's `sunrise` and `sunset`, which need to be requested with `timeformat=unixtime`.
+	pub fn sun_times(&self) -> Result<Daylight, Error> {
+		let daily = self.daily()?;
+		let missing = |name| Error::MissingField {
+			section: "daily",
+			name,
+		};
+		let sunrise = daily.sunrise.as_ref().ok_or_else(|| missing("sunrise"))?;
+		let sunset = daily.sunset.as_ref().ok_or_else(|| missing("sunset"))?;
+		sunrise
+			.iter()
+			.zip(sunset)
+			.map(|(sunrise, sunset)| Ok((sunrise.timestamp()?, sunset.timestamp()?)))
+			.collect::<Result<_, _>>()
+			.map(Daylight::SunTimes)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn columns_and_units() {
+		let response = OpenMeteo::from_reader(
+			r#"{
+				"latitude": 52.1,
+				"longitude": 5.1,
+				"utc_offset_seconds": 7200,
+				"hourly": {
+					"time": ["2024-03-31T22:00", "2024-03-31T23:00"],
+					"wind_speed_10m": [36.0, null],
+					"wind_gusts_10m": [72.0, null],
+					"wind_direction_10m": [270, 90],
+					"rain": [1.5, 0.0],
+					"snowfall": [0.0, 0.7]
+				},
+				"daily": {
+					"time": [1711922400],
+					"temperature_2m_min": [-1.5],
+					"temperature_2m_max": [10.0],
+					"sunrise": [1711948620],
+					"sunset": [1711995180]
+				}
+			}"#
+			.as_bytes(),
+		)
+		.unwrap();
+		let wind = response.hourly_wind().unwrap();
+		assert_eq!(
+			wind.iter()
+				.map(|hour| (hour.hour, hour.wind_speed, hour.wind_gust))
+				.collect::<Vec<_>>(),
+			vec![(22, Some(1000), Some(2000)), (23, None, None)]
+		);
+		let precipitation = response.hourly_precipitation().unwrap();
+		assert_eq!(precipitation[0].rain, Some(150));
+		assert_eq!(precipitation[1].snow, Some(100));
+		// 2024-03-31T22:00Z is already April 1st at UTC+2.
+		assert_eq!(response.daily_temps().unwrap()[0].day, 1);
+		assert_eq!(
+			response.sun_times().unwrap(),
+			Daylight::SunTimes(vec![(1711948620, 1711995180)])
+		);
+	}
+}
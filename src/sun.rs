@@ -0,0 +1,127 @@
+//! Where the sun is, for shading charts by daylight.
+
+/// How high the centre of the sun is at sunrise and sunset, in degrees, allowing for refraction and its radius.
+const SUNRISE_ALTITUDE: f64 = -0.833;
+/// 2000-01-01 12:00 UTC, which the orbital elements below count from
+const J2000: i64 = 946_728_000;
+const SECONDS_PER_DAY: f64 = 24.0 * 60.0 * 60.0;
+
+/// What the daylight shading behind a chart is worked out from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Daylight {
+	/// Where the forecast is for, so the sun's altitude can be calculated
+	Location {
+		/// Degrees north
+		latitude: f64,
+		/// Degrees east
+		longitude: f64,
+		twilight: Twilight,
+	},
+	/// Sunrise and sunset Unix timestamps, with night between each sunset and the next sunrise
+	SunTimes(Vec<(i64, i64)>),
+}
+
+/// How far into the night the shading fades in, rather than switching at sunset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Twilight {
+	#[default]
+	None,
+	/// Until the sun is 6° below the horizon
+	Civil,
+	/// Until the sun is 12° below the horizon
+	Nautical,
+}
+
+impl Twilight {
+	/// The altitude in degrees where it is fully night.
+	fn night_altitude(self) -> f64 {
+		match self {
+			Self::None => SUNRISE_ALTITUDE,
+			Self::Civil => -6.0,
+			Self::Nautical => -12.0,
+		}
+	}
+}
+
+impl Daylight {
+	/// How dark it is at a Unix timestamp, from 0 in daylight to 1 at night.
+	pub fn darkness(&self, timestamp: i64) -> f32 {
+		match self {
+			Self::Location {
+				latitude,
+				longitude,
+				twilight,
+			} => {
+				let altitude = altitude(timestamp, *latitude, *longitude);
+				if altitude >= SUNRISE_ALTITUDE {
+					0.0
+				} else if altitude <= twilight.night_altitude() {
+					1.0
+				} else {
+					((SUNRISE_ALTITUDE - altitude) / (SUNRISE_ALTITUDE - twilight.night_altitude()))
+						as f32
+				}
+			}
+			Self::SunTimes(times) => {
+				let is_day = times
+					.iter()
+					.any(|&(sunrise, sunset)| (sunrise..sunset).contains(&timestamp));
+				if is_day {
+					0.0
+				} else {
+					1.0
+				}
+			}
+		}
+	}
+}
+
+/// The altitude of the sun above the horizon in degrees, at a Unix timestamp and a location in degrees north and east.
+///
+/// This uses the [low precision formulas from the Astronomical Almanac](https://aa.usno.navy.mil/faq/sun_approx), which are within a minute or so of sunrise and sunset for centuries either side of 2000.
+pub fn altitude(timestamp: i64, latitude: f64, longitude: f64) -> f64 {
+	let days = (timestamp - J2000) as f64 / SECONDS_PER_DAY;
+	let mean_anomaly = (357.529 + 0.985_600_28 * days).to_radians();
+	let mean_longitude = 280.459 + 0.985_647_36 * days;
+	let ecliptic_longitude =
+		(mean_longitude + 1.915 * mean_anomaly.sin() + 0.020 * (2.0 * mean_anomaly).sin())
+			.to_radians();
+	let obliquity = (23.439 - 0.000_000_36 * days).to_radians();
+	let right_ascension =
+		(obliquity.cos() * ecliptic_longitude.sin()).atan2(ecliptic_longitude.cos());
+	let declination = (obliquity.sin() * ecliptic_longitude.sin()).asin();
+	let sidereal_degrees = 280.460_618_37 + 360.985_647_366_29 * days;
+	let hour_angle = (sidereal_degrees + longitude).to_radians() - right_ascension;
+	let latitude = latitude.to_radians();
+	(latitude.sin() * declination.sin() + latitude.cos() * declination.cos() * hour_angle.cos())
+		.asin()
+		.to_degrees()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn sun_altitude() {
+		// Around solar noon and midnight in Utrecht on 2024-06-21
+		assert!((altitude(1_718_970_000, 52.1, 5.1) - 61.3).abs() < 0.5);
+		assert!((altitude(1_719_013_200, 52.1, 5.1) + 14.4).abs() < 0.5);
+		// Sunrise there was at 03:18 UTC.
+		assert!((altitude(1_718_939_880, 52.1, 5.1) - SUNRISE_ALTITUDE).abs() < 0.3);
+
+		let utrecht = Daylight::Location {
+			latitude: 52.1,
+			longitude: 5.1,
+			twilight: Twilight::Nautical,
+		};
+		assert_eq!(utrecht.darkness(1_718_970_000), 0.0);
+		// Half an hour before sunrise is in civil twilight.
+		let dawn = utrecht.darkness(1_718_938_080);
+		assert!(0.0 < dawn && dawn < 0.5);
+		assert_eq!(
+			Daylight::SunTimes(vec![(100, 200)]).darkness(250),
+			Daylight::SunTimes(vec![]).darkness(150)
+		);
+	}
+}
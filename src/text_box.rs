@@ -2,7 +2,11 @@ use ab_glyph::{FontRef, PxScale};
 use image::Rgb;
 use imageproc::drawing;
 
-use crate::generic_graph::ChartElement;
+use crate::{
+	canvas::Canvas,
+	generic_graph::{scale_font, ChartElement},
+	theme::Theme,
+};
 
 #[derive(Debug, Clone, Copy)]
 pub struct TextSegment<'s> {
@@ -14,8 +18,9 @@ impl<'s> TextSegment<'s> {
 	pub fn new(text: &'s str, color: Rgb<u8>) -> Self {
 		Self { text, color }
 	}
-	pub fn white(text: &'s str) -> Self {
-		let color = Rgb([255, 255, 255]);
+	/// In the theme's text colour.
+	pub fn plain(text: &'s str, theme: &Theme) -> Self {
+		let color = theme.text;
 		Self { text, color }
 	}
 }
@@ -48,12 +53,10 @@ impl<'s> LineWriter<'s> {
 		let trim = |line: &mut [TextSegment]| {
 			let first = &mut line.first_mut().unwrap().text;
 			if let Some(stripped) = first.strip_prefix(' ') {
-				println!("Trimmed start off \"{stripped}\"");
 				*first = stripped;
 			}
 			let last = &mut line.last_mut().unwrap().text;
 			if let Some(stripped) = last.strip_suffix(' ') {
-				println!("Trimmed end off \"{stripped}\"");
 				*last = stripped;
 			}
 		};
@@ -209,25 +212,26 @@ impl<'f, 's> TextBox<'f, 's> {
 }
 
 impl ChartElement for TextBox<'_, '_> {
-	fn draw(self, chart: &mut crate::generic_graph::Chart) {
-		let mut cursor_y = self.line_distance as i32;
+	fn draw<C: Canvas>(self, chart: &mut crate::generic_graph::Chart<C>) {
+		let font_scale = scale_font(self.font_scale, chart.scale);
+		let line_distance = (self.line_distance * chart.scale) as i32;
+		let mut cursor_y = line_distance;
 		for line in self.lines {
 			let mut cursor_x = chart.padding.left as i32;
 			for segment in line {
-				imageproc::drawing::draw_text_mut(
-					&mut chart.canvas,
+				chart.canvas.draw_text(
+					segment.text,
 					segment.color,
 					cursor_x,
 					cursor_y,
-					self.font_scale,
 					&self.font,
-					segment.text,
+					font_scale,
 				);
 				let (text_width, _text_height) =
-					imageproc::drawing::text_size(self.font_scale, &self.font, segment.text);
+					imageproc::drawing::text_size(font_scale, &self.font, segment.text);
 				cursor_x += text_width as i32;
 			}
-			cursor_y += self.font_scale.y as i32 + self.line_distance as i32;
+			cursor_y += font_scale.y as i32 + line_distance;
 		}
 	}
 }
@@ -236,7 +240,7 @@ impl ChartElement for TextBox<'_, '_> {
 mod tests {
 	use crate::{
 		drawing::{Padding, Spacing},
-		generic_graph::Chart,
+		generic_graph::{Chart, ChartOptions},
 	};
 
 	use super::*;
@@ -248,11 +252,11 @@ mod tests {
 
 		let segments = [
 			TextSegment::new("Minimum", Rgb([0, 148, 255])),
-			TextSegment::white(", "),
+			TextSegment::plain(", ", &Theme::DARK),
 			TextSegment::new("maximum", Rgb([255, 0, 0])),
-			TextSegment::white(" and "),
+			TextSegment::plain(" and ", &Theme::DARK),
 			TextSegment::new("apparent minimum and maximum", Rgb([0, 170, 33])),
-			TextSegment::white(" temperatures (°C)"),
+			TextSegment::plain(" temperatures (°C)", &Theme::DARK),
 		];
 		let text_box = TextBox::new(&segments, font, PxScale { x: 15.0, y: 15.0 }, 151, 5);
 		for line in text_box.lines {
@@ -266,14 +270,14 @@ mod tests {
 
 		let segments = [
 			TextSegment::new("Minimum", Rgb([0, 148, 255])),
-			TextSegment::white(", "),
+			TextSegment::plain(", ", &Theme::DARK),
 			TextSegment::new("maximum", Rgb([255, 0, 0])),
-			TextSegment::white(" and "),
+			TextSegment::plain(" and ", &Theme::DARK),
 			TextSegment::new("apparent minimum and maximum", Rgb([0, 170, 33])),
-			TextSegment::white(" temperatures (°C)"),
+			TextSegment::plain(" temperatures (°C)", &Theme::DARK),
 		];
 		let text_box = TextBox::new(&segments, font, PxScale { x: 15.0, y: 15.0 }, 151, 5);
-		let mut chart = Chart::new(
+		let mut chart: Chart = Chart::new(
 			7,
 			0,
 			Spacing {
@@ -286,10 +290,12 @@ mod tests {
 				left: 21,
 				right: 9,
 			},
-		);
+			&ChartOptions::default(),
+		)
+		.unwrap();
 		chart.draw(text_box);
 		let canvas = chart.into_canvas();
-		let _ = canvas.save("./test.png");
+		let _ = canvas.save(std::env::temp_dir().join("test.png"));
 	}
 	#[test]
 	fn big_test() {
@@ -303,14 +309,14 @@ mod tests {
 		println!("char_width: {char_width}");
 
 		let segments = [
-			TextSegment::white("The quick "),
+			TextSegment::plain("The quick ", &Theme::DARK),
 			TextSegment::new("brown", Rgb([150, 75, 0])),
-			TextSegment::white(" fox jumped over the "),
+			TextSegment::plain(" fox jumped over the ", &Theme::DARK),
 			TextSegment::new("l", Rgb([200, 200, 255])),
 			TextSegment::new("a", Rgb([100, 100, 255])),
 			TextSegment::new("z", Rgb([200, 200, 255])),
 			TextSegment::new("y", Rgb([100, 100, 255])),
-			TextSegment::white(" dog."),
+			TextSegment::plain(" dog.", &Theme::DARK),
 		];
 
 		for i in 1..=45 {
@@ -320,7 +326,7 @@ mod tests {
 			println!("{i}");
 			let width = (i as f32 * char_width as f32) as u32;
 			let text_box = TextBox::new(&segments, font.clone(), scale, width, 0);
-			let mut chart = Chart::new(
+			let mut chart: Chart = Chart::new(
 				2,
 				0,
 				Spacing {
@@ -333,7 +339,9 @@ mod tests {
 					left: 0,
 					right: 0,
 				},
-			);
+				&ChartOptions::default(),
+			)
+			.unwrap();
 			chart.draw(text_box);
 			let canvas = chart.into_canvas();
 			if image::open(format!("./test-{i}.png"))
@@ -342,7 +350,7 @@ mod tests {
 				.unwrap() != &canvas
 			{
 				println!("{i} changed.");
-				let _ = canvas.save(format!("./test-{i}-new.png"));
+				let _ = canvas.save(std::env::temp_dir().join(format!("test-{i}-new.png")));
 			}
 		}
 	}
@@ -371,7 +379,7 @@ mod tests {
 		for i in (1..=10).chain([test_text.len()]) {
 			let width = (i as f32 * char_width as f32) as u32;
 			let text_box = TextBox::new(&segments, font.clone(), scale, width, 0);
-			let mut chart = Chart::new(
+			let mut chart: Chart = Chart::new(
 				2,
 				0,
 				Spacing {
@@ -384,10 +392,12 @@ mod tests {
 					left: 0,
 					right: 0,
 				},
-			);
+				&ChartOptions::default(),
+			)
+			.unwrap();
 			chart.draw(text_box);
 			let canvas = chart.into_canvas();
-			let _ = canvas.save(format!("./test2-{i}.png"));
+			let _ = canvas.save(std::env::temp_dir().join(format!("test2-{i}.png")));
 		}
 	}
 }
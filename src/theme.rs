@@ -0,0 +1,331 @@
+//! The colours charts are drawn with.
+
+use std::io::Read;
+
+use image::Rgb;
+use serde::{de, Deserialize, Deserializer};
+
+use crate::{error::Error, from_json::data_from_json};
+
+/// Every colour used by the charts. Colours in gradients are plain channel arrays, since they are converted to Oklab for mixing.
+///
+/// Custom themes are read from JSON, where each colour is either a `"#rrggbb"` string or an `[r, g, b]` array. Any colours left out are taken from [`Theme::DARK`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+	#[serde(deserialize_with = "rgb")]
+	pub background: Rgb<u8>,
+	/// The axes and the zero line
+	#[serde(deserialize_with = "rgb")]
+	pub main_lines: Rgb<u8>,
+	#[serde(deserialize_with = "rgb")]
+	pub grid_lines: Rgb<u8>,
+	/// Grid lines that have a label
+	#[serde(deserialize_with = "rgb")]
+	pub brighter_grid_lines: Rgb<u8>,
+	#[serde(deserialize_with = "rgb")]
+	pub text: Rgb<u8>,
+	/// What the background is shaded towards at night
+	#[serde(deserialize_with = "rgb")]
+	pub night: Rgb<u8>,
+
+	#[serde(deserialize_with = "rgb")]
+	pub temp_min: Rgb<u8>,
+	#[serde(deserialize_with = "rgb")]
+	pub temp_max: Rgb<u8>,
+	#[serde(deserialize_with = "rgb")]
+	pub temp: Rgb<u8>,
+	#[serde(deserialize_with = "rgb")]
+	pub temp_feels_like: Rgb<u8>,
+	#[serde(deserialize_with = "rgb")]
+	pub temp_wet_bulb: Rgb<u8>,
+
+	#[serde(deserialize_with = "rgb")]
+	pub rain: Rgb<u8>,
+	#[serde(deserialize_with = "rgb")]
+	pub snow: Rgb<u8>,
+	#[serde(deserialize_with = "rgb")]
+	pub pop: Rgb<u8>,
+	/// Probability of precipitation drawn over rain and snow bars, so it needs to stand out from both
+	#[serde(deserialize_with = "rgb")]
+	pub pop_overlay: Rgb<u8>,
+
+	#[serde(deserialize_with = "rgb")]
+	pub humidity: Rgb<u8>,
+	#[serde(deserialize_with = "rgb")]
+	pub dew_point: Rgb<u8>,
+	/// Background for dew points below 10 °C
+	#[serde(deserialize_with = "rgb")]
+	pub dry: Rgb<u8>,
+	/// Background for dew points from 10 °C to 16 °C
+	#[serde(deserialize_with = "rgb")]
+	pub comfortable: Rgb<u8>,
+	/// Background for dew points from 16 °C to 21 °C
+	#[serde(deserialize_with = "rgb")]
+	pub muggy: Rgb<u8>,
+	/// Background for dew points above 21 °C
+	#[serde(deserialize_with = "rgb")]
+	pub oppressive: Rgb<u8>,
+
+	#[serde(deserialize_with = "rgb")]
+	pub clouds: Rgb<u8>,
+	#[serde(deserialize_with = "rgb")]
+	pub visibility: Rgb<u8>,
+
+	#[serde(deserialize_with = "rgb")]
+	pub pressure: Rgb<u8>,
+	#[serde(deserialize_with = "rgb")]
+	pub pressure_rising: Rgb<u8>,
+	#[serde(deserialize_with = "rgb")]
+	pub pressure_falling: Rgb<u8>,
+
+	/// Weather icons also use `clouds`, `rain` and `snow`
+	#[serde(deserialize_with = "rgb")]
+	pub sun: Rgb<u8>,
+	#[serde(deserialize_with = "rgb")]
+	pub moon: Rgb<u8>,
+	#[serde(deserialize_with = "rgb")]
+	pub lightning: Rgb<u8>,
+
+	#[serde(deserialize_with = "channels")]
+	pub uvi_low: [u8; 3],
+	#[serde(deserialize_with = "channels")]
+	pub uvi_medium: [u8; 3],
+	#[serde(deserialize_with = "channels")]
+	pub uvi_high: [u8; 3],
+
+	#[serde(deserialize_with = "channels")]
+	pub gust_low: [u8; 3],
+	#[serde(deserialize_with = "channels")]
+	pub gust_medium: [u8; 3],
+	#[serde(deserialize_with = "channels")]
+	pub gust_high: [u8; 3],
+	#[serde(deserialize_with = "channels")]
+	pub gust_very_high: [u8; 3],
+	#[serde(deserialize_with = "channels")]
+	pub wind_low: [u8; 3],
+	#[serde(deserialize_with = "channels")]
+	pub wind_medium: [u8; 3],
+	#[serde(deserialize_with = "channels")]
+	pub wind_high: [u8; 3],
+	#[serde(deserialize_with = "channels")]
+	pub wind_very_high: [u8; 3],
+}
+
+impl Theme {
+	/// Bright colours on black.
+	pub const DARK: Self = Self {
+		background: Rgb([0, 0, 0]),
+		main_lines: Rgb([127, 127, 127]),
+		grid_lines: Rgb([63, 63, 63]),
+		brighter_grid_lines: Rgb([95, 95, 95]),
+		text: Rgb([255, 255, 255]),
+		night: Rgb([18, 22, 44]),
+
+		temp_min: Rgb([0, 148, 255]),
+		temp_max: Rgb([255, 0, 0]),
+		temp: Rgb([255, 0, 0]),
+		temp_feels_like: Rgb([0, 255, 33]),
+		temp_wet_bulb: Rgb([0, 148, 255]),
+
+		rain: Rgb([0, 148, 255]),
+		snow: Rgb([216, 239, 255]),
+		pop: Rgb([0, 148, 255]),
+		pop_overlay: Rgb([255, 216, 0]),
+
+		humidity: Rgb([0, 209, 209]),
+		dew_point: Rgb([255, 148, 0]),
+		dry: Rgb([36, 28, 14]),
+		comfortable: Rgb([12, 32, 16]),
+		muggy: Rgb([36, 36, 8]),
+		oppressive: Rgb([44, 14, 14]),
+
+		clouds: Rgb([170, 170, 170]),
+		visibility: Rgb([255, 216, 0]),
+
+		pressure: Rgb([198, 120, 255]),
+		pressure_rising: Rgb([0, 255, 33]),
+		pressure_falling: Rgb([255, 0, 33]),
+
+		sun: Rgb([255, 200, 0]),
+		moon: Rgb([216, 224, 240]),
+		lightning: Rgb([255, 236, 0]),
+
+		uvi_low: [0, 255, 33],
+		uvi_medium: [255, 255, 33],
+		uvi_high: [255, 0, 33],
+
+		gust_low: [70, 119, 67],
+		gust_medium: [118, 118, 62],
+		gust_high: [122, 67, 62],
+		gust_very_high: [103, 78, 122],
+		wind_low: [0, 255, 33],
+		wind_medium: [255, 255, 33],
+		wind_high: [255, 0, 33],
+		wind_very_high: [188, 66, 255],
+	};
+	/// Darker colours on white, for light mode clients.
+	pub const LIGHT: Self = Self {
+		background: Rgb([255, 255, 255]),
+		main_lines: Rgb([96, 96, 96]),
+		grid_lines: Rgb([224, 224, 224]),
+		brighter_grid_lines: Rgb([184, 184, 184]),
+		text: Rgb([0, 0, 0]),
+		night: Rgb([222, 228, 242]),
+
+		temp_min: Rgb([0, 107, 201]),
+		temp_max: Rgb([214, 0, 0]),
+		temp: Rgb([214, 0, 0]),
+		temp_feels_like: Rgb([0, 158, 24]),
+		temp_wet_bulb: Rgb([0, 107, 201]),
+
+		rain: Rgb([0, 107, 201]),
+		snow: Rgb([125, 160, 204]),
+		pop: Rgb([0, 107, 201]),
+		pop_overlay: Rgb([204, 102, 0]),
+
+		humidity: Rgb([0, 140, 140]),
+		dew_point: Rgb([204, 102, 0]),
+		dry: Rgb([247, 239, 222]),
+		comfortable: Rgb([228, 245, 229]),
+		muggy: Rgb([250, 246, 212]),
+		oppressive: Rgb([252, 226, 226]),
+
+		clouds: Rgb([140, 140, 140]),
+		visibility: Rgb([191, 143, 0]),
+
+		pressure: Rgb([128, 51, 179]),
+		pressure_rising: Rgb([0, 158, 24]),
+		pressure_falling: Rgb([214, 0, 24]),
+
+		sun: Rgb([235, 160, 0]),
+		moon: Rgb([110, 120, 150]),
+		lightning: Rgb([214, 150, 0]),
+
+		uvi_low: [0, 181, 24],
+		uvi_medium: [230, 184, 0],
+		uvi_high: [214, 0, 24],
+
+		gust_low: [176, 214, 173],
+		gust_medium: [222, 222, 160],
+		gust_high: [230, 176, 171],
+		gust_very_high: [204, 186, 230],
+		wind_low: [0, 181, 24],
+		wind_medium: [230, 184, 0],
+		wind_high: [214, 0, 24],
+		wind_very_high: [148, 33, 214],
+	};
+
+	/// Based on the [Okabe–Ito palette](https://jfly.uni-koeln.de/color/), so that series can be told apart with any common colour vision deficiency. The gradients run from blue through yellow to vermillion, changing in lightness as well as hue.
+	pub const COLOUR_BLIND: Self = Self {
+		background: Rgb([0, 0, 0]),
+		main_lines: Rgb([127, 127, 127]),
+		grid_lines: Rgb([63, 63, 63]),
+		brighter_grid_lines: Rgb([95, 95, 95]),
+		text: Rgb([255, 255, 255]),
+		night: Rgb([18, 22, 44]),
+
+		temp_min: Rgb([86, 180, 233]),
+		temp_max: Rgb([230, 159, 0]),
+		temp: Rgb([230, 159, 0]),
+		temp_feels_like: Rgb([204, 121, 167]),
+		temp_wet_bulb: Rgb([86, 180, 233]),
+
+		rain: Rgb([86, 180, 233]),
+		snow: Rgb([240, 240, 240]),
+		pop: Rgb([86, 180, 233]),
+		pop_overlay: Rgb([230, 159, 0]),
+
+		humidity: Rgb([0, 158, 115]),
+		dew_point: Rgb([230, 159, 0]),
+		dry: Rgb([10, 28, 40]),
+		comfortable: Rgb([24, 24, 24]),
+		muggy: Rgb([40, 38, 12]),
+		oppressive: Rgb([48, 24, 0]),
+
+		clouds: Rgb([170, 170, 170]),
+		visibility: Rgb([240, 228, 66]),
+
+		pressure: Rgb([204, 121, 167]),
+		pressure_rising: Rgb([86, 180, 233]),
+		pressure_falling: Rgb([213, 94, 0]),
+
+		sun: Rgb([230, 159, 0]),
+		moon: Rgb([216, 224, 240]),
+		lightning: Rgb([240, 228, 66]),
+
+		uvi_low: [0, 114, 178],
+		uvi_medium: [240, 228, 66],
+		uvi_high: [213, 94, 0],
+
+		gust_low: [0, 57, 89],
+		gust_medium: [120, 114, 33],
+		gust_high: [107, 47, 0],
+		gust_very_high: [102, 60, 84],
+		wind_low: [0, 114, 178],
+		wind_medium: [240, 228, 66],
+		wind_high: [213, 94, 0],
+		wind_very_high: [204, 121, 167],
+	};
+
+	/// Reads a custom theme from JSON.
+	pub fn from_reader(reader: impl Read) -> Result<Self, Error> {
+		data_from_json(reader)
+	}
+}
+
+impl Default for Theme {
+	fn default() -> Self {
+		Self::DARK
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ColourInput {
+	Hex(String),
+	Channels([u8; 3]),
+}
+
+fn channels<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 3], D::Error> {
+	match ColourInput::deserialize(deserializer)? {
+		ColourInput::Channels(channels) => Ok(channels),
+		ColourInput::Hex(hex) => {
+			let invalid = || de::Error::invalid_value(de::Unexpected::Str(&hex), &"#rrggbb");
+			let digits = hex.strip_prefix('#').filter(|digits| digits.len() == 6);
+			let digits = digits.ok_or_else(invalid)?;
+			let mut channels = [0; 3];
+			for (index, channel) in channels.iter_mut().enumerate() {
+				*channel = digits
+					.get(index * 2..index * 2 + 2)
+					.and_then(|channel| u8::from_str_radix(channel, 16).ok())
+					.ok_or_else(invalid)?;
+			}
+			Ok(channels)
+		}
+	}
+}
+
+fn rgb<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Rgb<u8>, D::Error> {
+	channels(deserializer).map(Rgb)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn custom_theme() {
+		let theme = Theme::from_reader(
+			r##"{"background": "#fafafa", "text": [32, 32, 32], "uvi_high": "#C00000"}"##
+				.as_bytes(),
+		)
+		.unwrap();
+		assert_eq!(theme.background, Rgb([250, 250, 250]));
+		assert_eq!(theme.text, Rgb([32, 32, 32]));
+		assert_eq!(theme.uvi_high, [192, 0, 0]);
+		assert_eq!(theme.rain, Theme::DARK.rain);
+		assert!(Theme::from_reader(r#"{"text": "white"}"#.as_bytes()).is_err());
+		assert!(Theme::from_reader(r#"{"txt": [0, 0, 0]}"#.as_bytes()).is_err());
+	}
+}
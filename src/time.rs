@@ -0,0 +1,291 @@
+//! Timestamps and the timezones they are shown in.
+//!
+//! Timezones are either a fixed offset from UTC, as weather APIs report them, or the daylight saving time rules of a [POSIX `TZ` string](https://pubs.opengroup.org/onlinepubs/9699919799/basedefs/V1_chap08.html#tag_08_03), so that a forecast spanning a change is labelled correctly.
+
+use serde::Deserialize;
+
+use crate::error::Error;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+/// A moment, along with the offset from UTC in effect at it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ZonedTime {
+	/// Unix timestamp
+	pub timestamp: i64,
+	/// Shift in seconds from UTC
+	pub offset: i32,
+}
+
+impl ZonedTime {
+	fn local_seconds(&self) -> i64 {
+		self.timestamp + self.offset as i64
+	}
+	/// Days since 1970-01-01 in local time.
+	pub(crate) fn day(&self) -> i64 {
+		self.local_seconds().div_euclid(SECONDS_PER_DAY)
+	}
+	pub(crate) fn hour(&self) -> u8 {
+		(self.local_seconds().rem_euclid(SECONDS_PER_DAY) / 3600) as u8
+	}
+	/// The weekday and day of the month, like "Tue 14".
+	pub(crate) fn day_label(&self) -> String {
+		let weekday = WEEKDAYS[weekday(self.day()) as usize];
+		format!("{weekday} {}", civil_from_days(self.day()).2)
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeZone {
+	/// Shift in seconds from UTC
+	Fixed(i32),
+	/// Switches between two offsets every year.
+	DaylightSaving {
+		standard: i32,
+		daylight: i32,
+		/// When daylight saving time starts, in local standard time
+		start: Transition,
+		/// When daylight saving time ends, in local daylight saving time
+		end: Transition,
+	},
+}
+
+/// A yearly change between standard and daylight saving time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transition {
+	month: u8,
+	/// 1 to 4, or 5 for the last one in the month
+	week: u8,
+	/// 0 is Sunday
+	weekday: u8,
+	/// Seconds after local midnight
+	time: i32,
+}
+
+impl Transition {
+	/// The moment of the transition in `year`, for the offset in effect just before it.
+	fn timestamp(&self, year: i64, offset: i32) -> i64 {
+		let first = days_from_civil(year, self.month, 1);
+		let mut day = first + (self.weekday as i64 - weekday(first) as i64).rem_euclid(7);
+		day += 7 * (self.week as i64 - 1);
+		let next_month = match self.month {
+			12 => days_from_civil(year + 1, 1, 1),
+			month => days_from_civil(year, month + 1, 1),
+		};
+		while day >= next_month {
+			day -= 7;
+		}
+		day * SECONDS_PER_DAY + self.time as i64 - offset as i64
+	}
+}
+
+impl TimeZone {
+	pub const UTC: Self = Self::Fixed(0);
+
+	/// Reads a POSIX `TZ` string like `CET-1CEST,M3.5.0,M10.5.0/3`. Note that POSIX offsets are west of UTC, so CET is written as -1. Only `Mm.w.d` transition dates are supported.
+	pub fn from_posix(tz: &str) -> Result<Self, Error> {
+		let invalid = || Error::InvalidTimeZone(tz.to_string());
+		let mut rest = tz;
+		skip_name(&mut rest).ok_or_else(invalid)?;
+		let standard = -parse_time(&mut rest).ok_or_else(invalid)?;
+		if rest.is_empty() {
+			return Ok(Self::Fixed(standard));
+		}
+		skip_name(&mut rest).ok_or_else(invalid)?;
+		let daylight = if rest.starts_with(',') {
+			standard + 3600
+		} else {
+			-parse_time(&mut rest).ok_or_else(invalid)?
+		};
+		let mut transition = || {
+			rest = rest.strip_prefix(",M")?;
+			let mut numbers = [0; 3];
+			for (index, number) in numbers.iter_mut().enumerate() {
+				if index > 0 {
+					rest = rest.strip_prefix('.')?;
+				}
+				*number = parse_number(&mut rest)?;
+			}
+			let time = match rest.strip_prefix('/') {
+				Some(time) => {
+					rest = time;
+					parse_time(&mut rest)?
+				}
+				None => 2 * 3600,
+			};
+			let [month, week, weekday] = numbers.map(|number| number as u8);
+			((1..=12).contains(&month) && (1..=5).contains(&week) && weekday < 7).then_some(
+				Transition {
+					month,
+					week,
+					weekday,
+					time,
+				},
+			)
+		};
+		let start = transition().ok_or_else(invalid)?;
+		let end = transition().ok_or_else(invalid)?;
+		if !rest.is_empty() {
+			return Err(invalid());
+		}
+		Ok(Self::DaylightSaving {
+			standard,
+			daylight,
+			start,
+			end,
+		})
+	}
+	/// The offset from UTC in seconds at a Unix timestamp.
+	pub fn offset_at(&self, timestamp: i64) -> i32 {
+		match *self {
+			Self::Fixed(offset) => offset,
+			Self::DaylightSaving {
+				standard,
+				daylight,
+				start,
+				end,
+			} => {
+				let year =
+					civil_from_days((timestamp + standard as i64).div_euclid(SECONDS_PER_DAY)).0;
+				let start = start.timestamp(year, standard);
+				let end = end.timestamp(year, daylight);
+				// In the southern hemisphere daylight saving time spans the new year.
+				let is_daylight = if start < end {
+					(start..end).contains(&timestamp)
+				} else {
+					!(end..start).contains(&timestamp)
+				};
+				if is_daylight {
+					daylight
+				} else {
+					standard
+				}
+			}
+		}
+	}
+	pub fn at(&self, timestamp: i64) -> ZonedTime {
+		ZonedTime {
+			timestamp,
+			offset: self.offset_at(timestamp),
+		}
+	}
+}
+
+/// Skips a zone abbreviation, either letters or anything in angle brackets.
+fn skip_name(rest: &mut &str) -> Option<()> {
+	let length = match rest.strip_prefix('<') {
+		Some(quoted) => quoted.find('>')? + 2,
+		None => rest
+			.find(|char: char| !char.is_ascii_alphabetic())
+			.unwrap_or(rest.len()),
+	};
+	if length < 3 {
+		return None;
+	}
+	*rest = &rest[length..];
+	Some(())
+}
+
+fn parse_number(rest: &mut &str) -> Option<i32> {
+	let length = rest
+		.find(|char: char| !char.is_ascii_digit())
+		.unwrap_or(rest.len());
+	let number = rest[..length].parse().ok()?;
+	*rest = &rest[length..];
+	Some(number)
+}
+
+/// Reads `[+-]hh[:mm[:ss]]` as seconds.
+fn parse_time(rest: &mut &str) -> Option<i32> {
+	let sign = match rest.as_bytes().first()? {
+		b'-' => -1,
+		b'+' => 1,
+		_ => 0,
+	};
+	if sign != 0 {
+		*rest = &rest[1..];
+	}
+	let mut seconds = parse_number(rest)? * 3600;
+	for unit in [60, 1] {
+		match rest.strip_prefix(':') {
+			Some(after) => {
+				*rest = after;
+				seconds += parse_number(rest)? * unit;
+			}
+			None => break,
+		}
+	}
+	Some(if sign < 0 { -seconds } else { seconds })
+}
+
+/// 0 is Sunday.
+fn weekday(days: i64) -> u8 {
+	// 1970-01-01 was a Thursday.
+	(days + 4).rem_euclid(7) as u8
+}
+
+/// Converts days since 1970-01-01 into a (year, month, day) date in the proleptic Gregorian calendar.
+///
+/// This is [Howard Hinnant's algorithm](https://howardhinnant.github.io/date_algorithms.html#civil_from_days).
+pub(crate) fn civil_from_days(days: i64) -> (i64, u8, u8) {
+	let days = days + 719_468;
+	let era = days.div_euclid(146_097);
+	let day_of_era = days.rem_euclid(146_097);
+	let year_of_era =
+		(day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let shifted_month = (5 * day_of_year + 2) / 153;
+	let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u8;
+	let month = if shifted_month < 10 {
+		shifted_month + 3
+	} else {
+		shifted_month - 9
+	} as u8;
+	let year = year_of_era + era * 400 + (month <= 2) as i64;
+	(year, month, day)
+}
+
+/// The inverse of [`civil_from_days`], also by [Howard Hinnant](https://howardhinnant.github.io/date_algorithms.html#days_from_civil).
+pub(crate) fn days_from_civil(year: i64, month: u8, day: u8) -> i64 {
+	let year = year - (month <= 2) as i64;
+	let era = year.div_euclid(400);
+	let year_of_era = year.rem_euclid(400);
+	let shifted_month = (month as i64 + 9) % 12;
+	let day_of_year = (153 * shifted_month + 2) / 5 + day as i64 - 1;
+	let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+	era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn daylight_saving() {
+		assert_eq!(days_from_civil(2024, 2, 29), 19_782);
+		assert_eq!(days_from_civil(1969, 12, 31), -1);
+
+		let europe = TimeZone::from_posix("CET-1CEST,M3.5.0,M10.5.0/3").unwrap();
+		// 2024-03-31 00:59 and 01:00 UTC, either side of the change to summer time
+		assert_eq!(europe.offset_at(1_711_846_740), 3600);
+		assert_eq!(europe.offset_at(1_711_846_800), 7200);
+		// 2024-10-27 00:59 and 01:00 UTC
+		assert_eq!(europe.offset_at(1_729_990_740), 7200);
+		assert_eq!(europe.offset_at(1_729_990_800), 3600);
+		assert_eq!(europe.at(1_711_846_800).hour(), 3);
+
+		let new_zealand = TimeZone::from_posix("NZST-12NZDT,M9.5.0,M4.1.0/3").unwrap();
+		// 2024-01-01 00:00 UTC is in summer.
+		assert_eq!(new_zealand.offset_at(1_704_067_200), 13 * 3600);
+		assert_eq!(new_zealand.at(1_704_067_200).day_label(), "Mon 1");
+
+		assert_eq!(
+			TimeZone::from_posix("<-03>3").unwrap(),
+			TimeZone::Fixed(-3 * 3600)
+		);
+		assert!(TimeZone::from_posix("CET-1CEST,J60,M10.5.0").is_err());
+		assert!(TimeZone::from_posix("UTC+").is_err());
+	}
+}
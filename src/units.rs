@@ -0,0 +1,227 @@
+//! Units the charts can be drawn in.
+//!
+//! Data is always read in metric units (hundredths of °C, cm/s and hundredths of mm) and only converted when it is charted, into hundredths of the chosen unit.
+
+/// The units each kind of quantity is charted in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Units {
+	pub temperature: Temperature,
+	pub speed: Speed,
+	pub precipitation: Precipitation,
+}
+
+impl Units {
+	pub const METRIC: Self = Self {
+		temperature: Temperature::Celsius,
+		speed: Speed::MetresPerSecond,
+		precipitation: Precipitation::Millimetres,
+	};
+	/// As used in the United States.
+	pub const IMPERIAL: Self = Self {
+		temperature: Temperature::Fahrenheit,
+		speed: Speed::MilesPerHour,
+		precipitation: Precipitation::Inches,
+	};
+}
+
+/// How a unit is laid out on the vertical axis. Amounts are in hundredths of the unit, like the converted data.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Axis {
+	/// The ends of the chart are rounded out to multiples of this.
+	pub(crate) rounding: i32,
+	/// Between grid lines
+	pub(crate) line: usize,
+	/// Between labels, a multiple of `line`
+	pub(crate) label: usize,
+	/// Pixels per whole unit
+	pub(crate) spacing: u32,
+}
+
+impl Axis {
+	/// Height in pixels of a converted amount.
+	pub(crate) const fn pixels(&self, amount: i32) -> u32 {
+		amount.unsigned_abs() * self.spacing / 100
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Temperature {
+	#[default]
+	Celsius,
+	Fahrenheit,
+}
+
+impl Temperature {
+	/// Converts from hundredths of a degree Celsius.
+	pub(crate) fn convert(&self, centidegrees: i32) -> i32 {
+		match self {
+			Self::Celsius => centidegrees,
+			Self::Fahrenheit => (centidegrees as f32 * 1.8).round() as i32 + 3200,
+		}
+	}
+	pub(crate) const fn symbol(&self) -> &'static str {
+		match self {
+			Self::Celsius => "°C",
+			Self::Fahrenheit => "°F",
+		}
+	}
+	pub(crate) const fn axis(&self) -> Axis {
+		match self {
+			Self::Celsius => Axis {
+				rounding: 400,
+				line: 200,
+				label: 400,
+				spacing: 3,
+			},
+			Self::Fahrenheit => Axis {
+				rounding: 1000,
+				line: 500,
+				label: 1000,
+				spacing: 2,
+			},
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Speed {
+	#[default]
+	MetresPerSecond,
+	KilometresPerHour,
+	MilesPerHour,
+	Knots,
+	/// The [Beaufort scale](https://en.wikipedia.org/wiki/Beaufort_scale), charted as a continuous number rather than in whole forces.
+	Beaufort,
+}
+
+impl Speed {
+	/// Converts from cm/s.
+	pub(crate) fn convert(&self, centimetres_per_second: i32) -> i32 {
+		let metres_per_second = centimetres_per_second as f32 / 100.0;
+		let converted = match self {
+			Self::MetresPerSecond => return centimetres_per_second,
+			Self::KilometresPerHour => metres_per_second * 3.6,
+			Self::MilesPerHour => metres_per_second * 3600.0 / 1609.344,
+			Self::Knots => metres_per_second * 3600.0 / 1852.0,
+			// v = 0.836 B^(3/2) m/s
+			Self::Beaufort => (metres_per_second.max(0.0) / 0.836).powf(2.0 / 3.0),
+		};
+		(converted * 100.0).round() as i32
+	}
+	pub(crate) const fn symbol(&self) -> &'static str {
+		match self {
+			Self::MetresPerSecond => "m/s",
+			Self::KilometresPerHour => "km/h",
+			Self::MilesPerHour => "mph",
+			Self::Knots => "kn",
+			Self::Beaufort => "Bft",
+		}
+	}
+	pub(crate) const fn axis(&self) -> Axis {
+		match self {
+			Self::MetresPerSecond => Axis {
+				rounding: 500,
+				line: 500,
+				label: 500,
+				spacing: 5,
+			},
+			Self::KilometresPerHour => Axis {
+				rounding: 2000,
+				line: 1000,
+				label: 2000,
+				spacing: 1,
+			},
+			Self::MilesPerHour => Axis {
+				rounding: 1000,
+				line: 1000,
+				label: 1000,
+				spacing: 2,
+			},
+			Self::Knots => Axis {
+				rounding: 1000,
+				line: 1000,
+				label: 1000,
+				spacing: 3,
+			},
+			Self::Beaufort => Axis {
+				rounding: 200,
+				line: 100,
+				label: 200,
+				spacing: 10,
+			},
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Precipitation {
+	#[default]
+	Millimetres,
+	Inches,
+}
+
+impl Precipitation {
+	/// Converts from hundredths of a mm.
+	pub(crate) fn convert(&self, centimillimetres: i32) -> i32 {
+		match self {
+			Self::Millimetres => centimillimetres,
+			Self::Inches => (centimillimetres as f32 / 25.4).round() as i32,
+		}
+	}
+	pub(crate) const fn symbol(&self) -> &'static str {
+		match self {
+			Self::Millimetres => "mm",
+			Self::Inches => "in",
+		}
+	}
+	pub(crate) const fn axis(&self) -> Axis {
+		match self {
+			Self::Millimetres => Axis {
+				rounding: 100,
+				line: 100,
+				label: 100,
+				spacing: 16,
+			},
+			Self::Inches => Axis {
+				rounding: 5,
+				line: 5,
+				label: 5,
+				spacing: 320,
+			},
+		}
+	}
+	/// For daily totals, which are much larger than hourly amounts. The rounding is the label interval, so the chart is always a whole number of labels tall.
+	pub(crate) const fn daily_axis(&self) -> Axis {
+		match self {
+			Self::Millimetres => Axis {
+				rounding: 1000,
+				line: 500,
+				label: 1000,
+				spacing: 4,
+			},
+			Self::Inches => Axis {
+				rounding: 50,
+				line: 25,
+				label: 50,
+				spacing: 100,
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn conversions() {
+		assert_eq!(Temperature::Fahrenheit.convert(-4000), -4000);
+		assert_eq!(Temperature::Fahrenheit.convert(3700), 9860);
+		assert_eq!(Speed::KilometresPerHour.convert(1000), 3600);
+		assert_eq!(Speed::Knots.convert(1000), 1944);
+		// The lower limits of force 4 and force 12
+		assert_eq!(Speed::Beaufort.convert(550), 351);
+		assert_eq!(Speed::Beaufort.convert(3270), 1152);
+		assert_eq!(Precipitation::Inches.convert(2540), 100);
+	}
+}
@@ -1,10 +1,19 @@
-use image::{codecs::png::PngEncoder, imageops, ColorType, ImageEncoder, RgbImage};
+use image::{codecs::png::PngEncoder, ColorType, ImageEncoder, Rgb, RgbImage};
 
-use crate::common_types::Range;
+use crate::{canvas::Canvas, common_types::Range, error::Error};
 
-/// The highest value the chart will include.
+/// The highest value the chart will include, with `interval` in whole units of the data's hundredths.
 pub fn next_multiple(highest: i32, interval: i32) -> i32 {
-	let interval = interval * 100;
+	next_multiple_of_hundredths(highest, interval * 100)
+}
+
+/// Get the lowest and highest values that the chart will include, with `interval` in whole units of the data's hundredths.
+pub fn previous_and_next_multiple(range: Range<i32>, interval: i32) -> Result<Range<i32>, Error> {
+	previous_and_next_multiple_of_hundredths(range, interval * 100)
+}
+
+/// Like [`next_multiple`], but `interval` is in the same hundredths as the data, for intervals that aren't whole units.
+pub fn next_multiple_of_hundredths(highest: i32, interval: i32) -> i32 {
 	let round_up = match highest.rem_euclid(interval) {
 		0 => 0,
 		n => interval - n,
@@ -12,9 +21,11 @@ pub fn next_multiple(highest: i32, interval: i32) -> i32 {
 	highest + round_up
 }
 
-/// Get the lowest and highest values that the chart will include.
-pub fn previous_and_next_multiple(range: Range<i32>, interval: i32) -> Range<i32> {
-	let interval = interval * 100;
+/// Like [`previous_and_next_multiple`], but `interval` is in the same hundredths as the data, for intervals that aren't whole units.
+pub fn previous_and_next_multiple_of_hundredths(
+	range: Range<i32>,
+	interval: i32,
+) -> Result<Range<i32>, Error> {
 	let round_up = match range.end().rem_euclid(interval) {
 		0 => 0,
 		n => interval - n,
@@ -25,24 +36,72 @@ pub fn previous_and_next_multiple(range: Range<i32>, interval: i32) -> Range<i32
 	)
 }
 
-pub fn make_png(canvas: RgbImage) -> Vec<u8> {
+/// The smallest range that covers `range` and splits into `steps` labels that are each a multiple of `interval`, for a second axis that shares the chart's grid lines. Both `interval` and `steps` need to be positive.
+pub fn secondary_range(range: Range<i32>, interval: i32, steps: u32) -> Result<Range<i32>, Error> {
+	if interval <= 0 || steps == 0 {
+		return Err(Error::SecondaryRange { interval, steps });
+	}
+	let steps = steps as i32;
+	let mut step = interval;
+	loop {
+		let start = range.start() - range.start().rem_euclid(step);
+		if start + step * steps >= range.end() {
+			return Range::new(start, start + step * steps);
+		}
+		step += interval;
+	}
+}
+
+pub fn make_png(canvas: RgbImage) -> Result<Vec<u8>, Error> {
 	let (width, height) = (canvas.width(), canvas.height());
 	let mut buffer = Vec::new();
 	let encoder = PngEncoder::new(&mut buffer);
-	encoder
-		.write_image(&canvas, width, height, ColorType::Rgb8.into())
-		.unwrap();
-	buffer
+	encoder.write_image(&canvas, width, height, ColorType::Rgb8.into())?;
+	Ok(buffer)
 }
 
-pub fn composite(images: &[RgbImage]) -> RgbImage {
-	let max_width = images.iter().map(|image| image.width()).max().unwrap();
-	let total_height = images.iter().map(|image| image.height()).sum::<u32>();
-	let mut canvas = RgbImage::new(max_width, total_height);
+/// Stacks the canvases from top to bottom, filling any space to the right of narrower ones with `background`.
+pub fn composite<C: Canvas>(canvases: Vec<C>, background: Rgb<u8>) -> Result<C, Error> {
+	let max_width = canvases
+		.iter()
+		.map(|canvas| canvas.width())
+		.max()
+		.ok_or(Error::NoData)?;
+	let total_height = canvases.iter().map(|canvas| canvas.height()).sum::<u32>();
+	let mut composite = C::new(max_width, total_height, background);
 	let mut last_height = 0_u32;
-	for image in images {
-		imageops::replace(&mut canvas, image, 0, last_height as i64);
-		last_height += image.height();
+	for canvas in canvases {
+		let height = canvas.height();
+		composite.overlay(canvas, 0, last_height);
+		last_height += height;
+	}
+	Ok(composite)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn multiples() {
+		assert_eq!(next_multiple(1230, 5), 1500);
+		assert_eq!(next_multiple_of_hundredths(1230, 5), 1230);
+		let range = previous_and_next_multiple(Range::new(-150, 1230).unwrap(), 4).unwrap();
+		assert_eq!((range.start(), range.end()), (-400, 1600));
+		for (interval, steps) in [(0, 5), (-200, 5), (200, 0)] {
+			assert!(matches!(
+				secondary_range(Range::new(0, 100).unwrap(), interval, steps),
+				Err(Error::SecondaryRange { .. })
+			));
+		}
+		let range =
+			previous_and_next_multiple_of_hundredths(Range::new(-150, 1230).unwrap(), 25).unwrap();
+		assert_eq!((range.start(), range.end()), (-150, 1250));
+	}
+
+	#[test]
+	fn secondary_ranges() {
+		let range = secondary_range(Range::new(-150, 1230).unwrap(), 200, 5).unwrap();
+		assert_eq!((range.start(), range.end()), (-400, 1600));
 	}
-	canvas
 }